@@ -4,6 +4,7 @@ use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use lsp_types::{
     CompletionParams, Position, TextDocumentIdentifier, TextDocumentPositionParams, Url,
 };
+use rowan::{TextRange, TextSize};
 use texlab::{features::FeatureRequest, syntax::latex, DocumentLanguage, Workspace};
 
 fn criterion_benchmark(c: &mut Criterion) {
@@ -11,6 +12,19 @@ fn criterion_benchmark(c: &mut Criterion) {
         b.iter(|| latex::parse(black_box(include_str!("../texlab.tex"))));
     });
 
+    c.bench_function("LaTeX/Parser/Edit/Full", |b| {
+        let text = include_str!("../texlab.tex").replacen("brew", "brews", 1);
+        b.iter(|| latex::parse(black_box(&text)));
+    });
+
+    c.bench_function("LaTeX/Parser/Edit/Incremental", |b| {
+        let text = include_str!("../texlab.tex");
+        let green = latex::parse(text).green;
+        let offset = TextSize::try_from(text.find("brew").unwrap()).unwrap();
+        let range = TextRange::at(offset, TextSize::from(4));
+        b.iter(|| latex::reparse(black_box(&green), range, black_box("brews")).unwrap());
+    });
+
     c.bench_function("LaTeX/Completion/Command", |b| {
         let uri = Arc::new(Url::parse("http://example.com/texlab.tex").unwrap());
         let text = Arc::new(include_str!("../texlab.tex").to_string());
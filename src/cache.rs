@@ -0,0 +1,512 @@
+use std::{
+    env, fmt,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::SystemTime,
+};
+
+use anyhow::Result;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use log::warn;
+use rustc_hash::{FxHashMap, FxHasher};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use crate::{
+    distro::{DistributionKind, Resolver},
+    syntax::latex,
+    Environment,
+};
+
+const RESOLVER_FILE: &str = "resolver.json.gz";
+
+const EXTRAS_FILE: &str = "extras.json.gz";
+
+/// A persistent cache in the user cache directory that speeds up the startup of the server.
+/// Every file is tagged with the version of the server and every entry with the
+/// modification times of its inputs, so that outdated entries are never reused.
+pub struct Cache {
+    directory: PathBuf,
+    extras_by_path: Mutex<FxHashMap<PathBuf, ExtrasEntry>>,
+    is_dirty: AtomicBool,
+    resolver_hash: Mutex<Option<(Arc<Resolver>, u64)>>,
+}
+
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.directory.display())
+    }
+}
+
+/// Identifies the state of a file on disk.
+/// Files that do not exist (yet) are stamped as well, so that creating them invalidates the entry.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct FileStamp {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: Option<u64>,
+}
+
+impl FileStamp {
+    #[must_use]
+    pub fn new(path: &Path) -> Self {
+        let metadata = fs::metadata(path).ok();
+        Self {
+            path: path.to_path_buf(),
+            modified: metadata
+                .as_ref()
+                .and_then(|metadata| metadata.modified().ok()),
+            len: metadata.as_ref().map(fs::Metadata::len),
+        }
+    }
+
+    fn exists(&self) -> bool {
+        self.modified.is_some()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Versioned<T> {
+    version: String,
+    value: T,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ResolverEntry {
+    kind: DistributionKind,
+    root_directories: Vec<PathBuf>,
+    database_files: Vec<FileStamp>,
+    resolver: Resolver,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ExtrasEntry {
+    stamp: FileStamp,
+    fingerprint: u64,
+    extras: latex::Extras,
+}
+
+impl Cache {
+    /// Opens the cache in the user cache directory (e.g. `~/.cache/texlab`).
+    #[must_use]
+    pub fn user() -> Option<Self> {
+        let directory = if cfg!(windows) {
+            env::var_os("LOCALAPPDATA").map(PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Caches"))
+        } else {
+            env::var_os("XDG_CACHE_HOME")
+                .map(PathBuf::from)
+                .filter(|path| path.is_absolute())
+                .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        }?;
+
+        Some(Self::open(directory.join("texlab")))
+    }
+
+    /// Opens the cache in the given directory.
+    /// Entries of files that have been modified or deleted since are dropped,
+    /// so that the cache does not grow indefinitely.
+    #[must_use]
+    pub fn open(directory: PathBuf) -> Self {
+        let mut extras_by_path: FxHashMap<PathBuf, ExtrasEntry> =
+            read_file(&directory.join(EXTRAS_FILE)).unwrap_or_default();
+
+        let count = extras_by_path.len();
+        extras_by_path.retain(|path, entry| entry.stamp == FileStamp::new(path));
+        Self {
+            directory,
+            is_dirty: AtomicBool::new(extras_by_path.len() != count),
+            extras_by_path: Mutex::new(extras_by_path),
+            resolver_hash: Mutex::default(),
+        }
+    }
+
+    /// Loads the resolver if it has been stored for the same distribution and root directories
+    /// and none of its database files have changed since.
+    #[must_use]
+    pub fn load_resolver(
+        &self,
+        kind: DistributionKind,
+        root_directories: &[PathBuf],
+    ) -> Option<Resolver> {
+        let entry: ResolverEntry = read_file(&self.directory.join(RESOLVER_FILE))?;
+        let is_valid = entry.kind == kind
+            && entry.root_directories == root_directories
+            && entry
+                .database_files
+                .iter()
+                .all(|stamp| *stamp == FileStamp::new(&stamp.path));
+
+        if is_valid {
+            Some(entry.resolver)
+        } else {
+            None
+        }
+    }
+
+    pub fn store_resolver(
+        &self,
+        kind: DistributionKind,
+        root_directories: &[PathBuf],
+        database_files: &[PathBuf],
+        resolver: &Resolver,
+    ) {
+        let entry = ResolverEntry {
+            kind,
+            root_directories: root_directories.to_vec(),
+            database_files: database_files
+                .iter()
+                .map(|path| FileStamp::new(path))
+                .collect(),
+            resolver: resolver.clone(),
+        };
+
+        if let Err(why) = write_file(&self.directory, RESOLVER_FILE, &entry) {
+            warn!("Failed to write the resolver cache: {}", why);
+        }
+    }
+
+    /// Finds the analysis results of a LaTeX file if the file has not been modified since.
+    /// The results also depend on the environment that has been used to analyze the file.
+    #[must_use]
+    pub fn get_extras(
+        &self,
+        stamp: &FileStamp,
+        environment: &Environment,
+    ) -> Option<latex::Extras> {
+        let fingerprint = self.fingerprint(environment);
+        let extras_by_path = self.extras_by_path.lock().unwrap();
+        let entry = extras_by_path.get(&stamp.path)?;
+        if entry.stamp == *stamp && entry.fingerprint == fingerprint {
+            Some(entry.extras.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Stores the analysis results of a LaTeX file.
+    /// The stamp should be taken before reading the file to avoid caching results of an outdated version.
+    pub fn insert_extras(
+        &self,
+        stamp: FileStamp,
+        environment: &Environment,
+        extras: &latex::Extras,
+    ) {
+        if !stamp.exists() {
+            return;
+        }
+
+        let entry = ExtrasEntry {
+            stamp,
+            fingerprint: self.fingerprint(environment),
+            extras: extras.clone(),
+        };
+
+        self.extras_by_path
+            .lock()
+            .unwrap()
+            .insert(entry.stamp.path.clone(), entry);
+
+        self.is_dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Writes the analysis results to disk if they have changed.
+    pub fn flush(&self) {
+        if !self.is_dirty.swap(false, Ordering::SeqCst) {
+            return;
+        }
+
+        let extras_by_path = self.extras_by_path.lock().unwrap();
+        if let Err(why) = write_file(&self.directory, EXTRAS_FILE, &*extras_by_path) {
+            warn!("Failed to write the analysis cache: {}", why);
+        }
+    }
+
+    /// Summarizes the parts of the environment that affect the analysis of a LaTeX document.
+    fn fingerprint(&self, environment: &Environment) -> u64 {
+        let mut hasher = FxHasher::default();
+        environment.current_directory.hash(&mut hasher);
+        environment.options.root_directory.hash(&mut hasher);
+        environment.options.aux_directory.hash(&mut hasher);
+        self.resolver_hash(&environment.resolver).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Hashes the file database of the distribution.
+    /// The hash of the last resolver is remembered because the database is large.
+    fn resolver_hash(&self, resolver: &Arc<Resolver>) -> u64 {
+        let mut resolver_hash = self.resolver_hash.lock().unwrap();
+        match &*resolver_hash {
+            Some((last_resolver, hash)) if Arc::ptr_eq(last_resolver, resolver) => *hash,
+            _ => {
+                // The order of the entries is arbitrary, so the hashes of the entries are combined commutatively.
+                let hash = resolver
+                    .files_by_name
+                    .iter()
+                    .map(|entry| {
+                        let mut hasher = FxHasher::default();
+                        entry.hash(&mut hasher);
+                        hasher.finish()
+                    })
+                    .fold(0, u64::wrapping_add);
+
+                *resolver_hash = Some((Arc::clone(resolver), hash));
+                hash
+            }
+        }
+    }
+}
+
+/// Reads a cache file. Files that have been written by a different version of the server are ignored.
+fn read_file<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let file = File::open(path).ok()?;
+    let reader = BufReader::new(GzDecoder::new(file));
+    let versioned: Versioned<T> = serde_json::from_reader(reader).ok()?;
+    if versioned.version == env!("CARGO_PKG_VERSION") {
+        Some(versioned.value)
+    } else {
+        None
+    }
+}
+
+/// Writes a cache file atomically, so that concurrent server instances never see partial files.
+fn write_file<T: Serialize>(directory: &Path, name: &str, value: &T) -> Result<()> {
+    fs::create_dir_all(directory)?;
+    let file = NamedTempFile::new_in(directory)?;
+    let mut writer = BufWriter::new(GzEncoder::new(file, Compression::fast()));
+    let versioned = Versioned {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        value,
+    };
+
+    serde_json::to_writer(&mut writer, &versioned)?;
+    let file = writer.into_inner()?.finish()?;
+    file.persist(directory.join(name))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashMap;
+    use smol_str::SmolStr;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_resolver_unchanged() -> Result<()> {
+        let directory = tempdir()?;
+        let database = directory.path().join("ls-R");
+        fs::write(&database, "foo.sty")?;
+
+        let mut files_by_name = FxHashMap::default();
+        files_by_name.insert(SmolStr::new("foo.sty"), PathBuf::from("/foo.sty"));
+        let resolver = Resolver::new(files_by_name);
+
+        let root_directories = [directory.path().to_path_buf()];
+        let cache = Cache::open(directory.path().join("cache"));
+        cache.store_resolver(
+            DistributionKind::Texlive,
+            &root_directories,
+            &[database],
+            &resolver,
+        );
+
+        assert_eq!(
+            cache.load_resolver(DistributionKind::Texlive, &root_directories),
+            Some(resolver)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolver_other_distribution() -> Result<()> {
+        let directory = tempdir()?;
+        let database = directory.path().join("ls-R");
+        fs::write(&database, "foo.sty")?;
+
+        let root_directories = [directory.path().to_path_buf()];
+        let cache = Cache::open(directory.path().join("cache"));
+        cache.store_resolver(
+            DistributionKind::Texlive,
+            &root_directories,
+            &[database],
+            &Resolver::default(),
+        );
+
+        assert_eq!(
+            cache.load_resolver(DistributionKind::Miktex, &root_directories),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolver_other_root_directories() -> Result<()> {
+        let directory = tempdir()?;
+        let database = directory.path().join("ls-R");
+        fs::write(&database, "foo.sty")?;
+
+        let cache = Cache::open(directory.path().join("cache"));
+        cache.store_resolver(
+            DistributionKind::Texlive,
+            &[directory.path().to_path_buf()],
+            &[database],
+            &Resolver::default(),
+        );
+
+        let root_directories = [directory.path().join("texmf")];
+        assert_eq!(
+            cache.load_resolver(DistributionKind::Texlive, &root_directories),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolver_changed() -> Result<()> {
+        let directory = tempdir()?;
+        let database = directory.path().join("ls-R");
+        fs::write(&database, "foo.sty")?;
+
+        let root_directories = [directory.path().to_path_buf()];
+        let cache = Cache::open(directory.path().join("cache"));
+        cache.store_resolver(
+            DistributionKind::Texlive,
+            &root_directories,
+            std::slice::from_ref(&database),
+            &Resolver::default(),
+        );
+
+        fs::write(&database, "foo.sty\nbar.sty")?;
+        assert_eq!(
+            cache.load_resolver(DistributionKind::Texlive, &root_directories),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolver_created() -> Result<()> {
+        let directory = tempdir()?;
+        let database = directory.path().join("ls-R");
+
+        let root_directories = [directory.path().to_path_buf()];
+        let cache = Cache::open(directory.path().join("cache"));
+        cache.store_resolver(
+            DistributionKind::Texlive,
+            &root_directories,
+            std::slice::from_ref(&database),
+            &Resolver::default(),
+        );
+
+        fs::write(&database, "foo.sty")?;
+        assert_eq!(
+            cache.load_resolver(DistributionKind::Texlive, &root_directories),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_extras_persisted() -> Result<()> {
+        let directory = tempdir()?;
+        let path = directory.path().join("main.tex");
+        fs::write(&path, "\\begin{document}\\end{document}")?;
+
+        let environment = Environment::new(Arc::new(directory.path().to_path_buf()));
+        let extras = latex::Extras {
+            has_document_environment: true,
+            ..latex::Extras::default()
+        };
+
+        let cache = Cache::open(directory.path().join("cache"));
+        cache.insert_extras(FileStamp::new(&path), &environment, &extras);
+        cache.flush();
+
+        let cache = Cache::open(directory.path().join("cache"));
+        let cached_extras = cache
+            .get_extras(&FileStamp::new(&path), &environment)
+            .unwrap();
+
+        assert!(cached_extras.has_document_environment);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extras_pruned() -> Result<()> {
+        let directory = tempdir()?;
+        let path = directory.path().join("main.tex");
+        fs::write(&path, "")?;
+
+        let environment = Environment::new(Arc::new(directory.path().to_path_buf()));
+        let cache = Cache::open(directory.path().join("cache"));
+        cache.insert_extras(
+            FileStamp::new(&path),
+            &environment,
+            &latex::Extras::default(),
+        );
+        cache.flush();
+
+        fs::remove_file(&path)?;
+        let cache = Cache::open(directory.path().join("cache"));
+        assert!(cache.extras_by_path.lock().unwrap().is_empty());
+        cache.flush();
+
+        let extras_by_path: FxHashMap<PathBuf, ExtrasEntry> =
+            read_file(&directory.path().join("cache").join(EXTRAS_FILE)).unwrap();
+        assert!(extras_by_path.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_extras_modified() -> Result<()> {
+        let directory = tempdir()?;
+        let path = directory.path().join("main.tex");
+        fs::write(&path, "")?;
+
+        let environment = Environment::new(Arc::new(directory.path().to_path_buf()));
+        let cache = Cache::open(directory.path().join("cache"));
+        cache.insert_extras(
+            FileStamp::new(&path),
+            &environment,
+            &latex::Extras::default(),
+        );
+
+        fs::write(&path, "\\begin{document}\\end{document}")?;
+        assert!(cache
+            .get_extras(&FileStamp::new(&path), &environment)
+            .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_extras_other_resolver() -> Result<()> {
+        let directory = tempdir()?;
+        let path = directory.path().join("main.tex");
+        fs::write(&path, "")?;
+
+        let mut environment = Environment::new(Arc::new(directory.path().to_path_buf()));
+        let cache = Cache::open(directory.path().join("cache"));
+        cache.insert_extras(
+            FileStamp::new(&path),
+            &environment,
+            &latex::Extras::default(),
+        );
+
+        let mut files_by_name = FxHashMap::default();
+        files_by_name.insert(SmolStr::new("foo.sty"), PathBuf::from("/foo.sty"));
+        environment.resolver = Arc::new(Resolver::new(files_by_name));
+        assert!(cache
+            .get_extras(&FileStamp::new(&path), &environment)
+            .is_none());
+        Ok(())
+    }
+}
@@ -14,6 +14,10 @@ pub trait ClientCapabilitiesExt {
     fn has_push_configuration_support(&self) -> bool;
 
     fn has_file_watching_support(&self) -> bool;
+
+    fn has_pull_diagnostics_support(&self) -> bool;
+
+    fn has_diagnostic_refresh_support(&self) -> bool;
 }
 
 impl ClientCapabilitiesExt for ClientCapabilities {
@@ -65,14 +69,30 @@ impl ClientCapabilitiesExt for ClientCapabilities {
             .and_then(|cap| cap.dynamic_registration)
             == Some(true)
     }
+
+    fn has_pull_diagnostics_support(&self) -> bool {
+        self.text_document
+            .as_ref()
+            .and_then(|cap| cap.diagnostic.as_ref())
+            .is_some()
+    }
+
+    fn has_diagnostic_refresh_support(&self) -> bool {
+        self.workspace
+            .as_ref()
+            .and_then(|cap| cap.diagnostic.as_ref())
+            .and_then(|cap| cap.refresh_support)
+            == Some(true)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use lsp_types::{
+        DiagnosticClientCapabilities, DiagnosticWorkspaceClientCapabilities,
         DocumentSymbolClientCapabilities, GotoCapability, HoverClientCapabilities,
-        TextDocumentClientCapabilities, WindowClientCapabilities,
+        TextDocumentClientCapabilities, WindowClientCapabilities, WorkspaceClientCapabilities,
     };
 
     #[test]
@@ -155,4 +175,42 @@ mod tests {
         let capabilities = ClientCapabilities::default();
         assert!(!capabilities.has_hover_markdown_support());
     }
+
+    #[test]
+    fn test_has_pull_diagnostics_support_true() {
+        let capabilities = ClientCapabilities {
+            text_document: Some(TextDocumentClientCapabilities {
+                diagnostic: Some(DiagnosticClientCapabilities::default()),
+                ..TextDocumentClientCapabilities::default()
+            }),
+            ..ClientCapabilities::default()
+        };
+        assert!(capabilities.has_pull_diagnostics_support());
+    }
+
+    #[test]
+    fn test_has_pull_diagnostics_support_false() {
+        let capabilities = ClientCapabilities::default();
+        assert!(!capabilities.has_pull_diagnostics_support());
+    }
+
+    #[test]
+    fn test_has_diagnostic_refresh_support_true() {
+        let capabilities = ClientCapabilities {
+            workspace: Some(WorkspaceClientCapabilities {
+                diagnostic: Some(DiagnosticWorkspaceClientCapabilities {
+                    refresh_support: Some(true),
+                }),
+                ..WorkspaceClientCapabilities::default()
+            }),
+            ..ClientCapabilities::default()
+        };
+        assert!(capabilities.has_diagnostic_refresh_support());
+    }
+
+    #[test]
+    fn test_has_diagnostic_refresh_support_false() {
+        let capabilities = ClientCapabilities::default();
+        assert!(!capabilities.has_diagnostic_refresh_support());
+    }
 }
@@ -0,0 +1,187 @@
+use std::{fmt, path::Path, sync::Arc};
+
+use anyhow::{bail, Context, Result};
+use lsp_types::{DiagnosticSeverity, NumberOrString, Url};
+use serde::Serialize;
+
+use crate::{diagnostics::DiagnosticManager, DocumentData, Environment, Workspace};
+
+/// The diagnostics of a project that has been checked without a language client.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize)]
+pub struct CheckReport {
+    pub documents: Vec<CheckedDocument>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct CheckedDocument {
+    pub uri: Arc<Url>,
+    pub diagnostics: Vec<lsp_types::Diagnostic>,
+}
+
+impl CheckReport {
+    /// Loads the project of the given root document including all files that are referenced by it
+    /// and collects the same diagnostics that the server reports to the client.
+    /// ChkTeX is only called if `chktex` is set.
+    pub fn new(environment: Environment, root_file: &Path, chktex: bool) -> Result<Self> {
+        let root_file = environment.current_directory.join(root_file);
+        let mut workspace = Workspace::new(environment);
+        let document = workspace
+            .load(root_file.clone())
+            .with_context(|| format!("failed to load {}", root_file.display()))?;
+
+        if document.is_none() {
+            bail!("unsupported file type: {}", root_file.display());
+        }
+
+        let diagnostic_manager = DiagnosticManager::default();
+        diagnostic_manager.push_all(&workspace);
+        if chktex {
+            for document in workspace.documents_by_uri.values() {
+                diagnostic_manager.push_chktex(&workspace, &document.uri);
+            }
+        }
+
+        let mut documents: Vec<_> = workspace
+            .documents_by_uri
+            .values()
+            .filter(|document| !matches!(document.data, DocumentData::BuildLog(_)))
+            .map(|document| {
+                let mut diagnostics = diagnostic_manager.publish(&workspace, &document.uri);
+                diagnostics.sort_by_key(|diagnostic| diagnostic.range.start);
+                CheckedDocument {
+                    uri: Arc::clone(&document.uri),
+                    diagnostics,
+                }
+            })
+            .collect();
+
+        documents.sort_by(|a, b| a.uri.cmp(&b.uri));
+        Ok(Self { documents })
+    }
+
+    #[must_use]
+    pub fn count(&self, severity: DiagnosticSeverity) -> usize {
+        self.documents
+            .iter()
+            .flat_map(|document| &document.diagnostics)
+            .filter(|diagnostic| diagnostic.severity == Some(severity))
+            .count()
+    }
+}
+
+/// Prints the diagnostics like a compiler would, e.g. `main.tex:3:5: warning: Unused entry`.
+/// Paths are relative to the current directory if possible.
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let current_dir = std::env::current_dir().ok();
+        for document in &self.documents {
+            let path = document
+                .uri
+                .to_file_path()
+                .ok()
+                .map(|path| match &current_dir {
+                    Some(current_dir) => path
+                        .strip_prefix(current_dir)
+                        .map_or_else(|_| path.clone(), Path::to_path_buf),
+                    None => path,
+                })
+                .map_or_else(
+                    || document.uri.to_string(),
+                    |path| path.display().to_string(),
+                );
+
+            for diagnostic in &document.diagnostics {
+                let severity = match diagnostic.severity {
+                    Some(DiagnosticSeverity::ERROR) => "error",
+                    Some(DiagnosticSeverity::WARNING) => "warning",
+                    Some(DiagnosticSeverity::INFORMATION) => "info",
+                    _ => "hint",
+                };
+
+                write!(
+                    f,
+                    "{}:{}:{}: {}: {}",
+                    path,
+                    diagnostic.range.start.line + 1,
+                    diagnostic.range.start.character + 1,
+                    severity,
+                    diagnostic.message
+                )?;
+
+                let code = match &diagnostic.code {
+                    Some(NumberOrString::Number(code)) => Some(code.to_string()),
+                    Some(NumberOrString::String(code)) => Some(code.clone()),
+                    None => None,
+                };
+
+                match (&diagnostic.source, code) {
+                    (Some(source), Some(code)) => writeln!(f, " [{}({})]", source, code)?,
+                    (Some(source), None) => writeln!(f, " [{}]", source)?,
+                    (None, _) => writeln!(f)?,
+                };
+            }
+        }
+
+        write!(
+            f,
+            "{} error(s), {} warning(s)",
+            self.count(DiagnosticSeverity::ERROR),
+            self.count(DiagnosticSeverity::WARNING)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_undefined_reference() -> Result<()> {
+        let directory = tempdir()?;
+        fs::write(
+            directory.path().join("main.tex"),
+            "\\begin{document}\n\\input{chapter}\n\\label{foo}\n\\end{document}",
+        )?;
+        fs::write(
+            directory.path().join("chapter.tex"),
+            "\\ref{foo}\n\\ref{bar}",
+        )?;
+
+        let environment = Environment::new(Arc::new(directory.path().to_path_buf()));
+        let report = CheckReport::new(environment, Path::new("main.tex"), false)?;
+        assert_eq!(report.documents.len(), 2);
+
+        let chapter = &report.documents[0];
+        assert!(chapter.uri.as_str().ends_with("chapter.tex"));
+        assert_eq!(chapter.diagnostics.len(), 1);
+        assert_eq!(chapter.diagnostics[0].range.start.line, 1);
+        assert_eq!(report.count(DiagnosticSeverity::WARNING), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_syntax_error() -> Result<()> {
+        let directory = tempdir()?;
+        fs::write(directory.path().join("main.tex"), "\\begin{foo}\\end{bar}")?;
+
+        let environment = Environment::new(Arc::new(directory.path().to_path_buf()));
+        let report = CheckReport::new(environment, Path::new("main.tex"), false)?;
+        assert_eq!(report.count(DiagnosticSeverity::ERROR), 1);
+        assert!(report.to_string().starts_with(&format!(
+            "{}:1:8: error: Mismatched environment",
+            directory.path().join("main.tex").display()
+        )));
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let directory = tempdir().unwrap();
+        let environment = Environment::new(Arc::new(directory.path().to_path_buf()));
+        assert!(CheckReport::new(environment, Path::new("main.tex"), false).is_err());
+    }
+}
@@ -1,19 +1,27 @@
 mod bibtex;
 mod build;
 mod chktex;
+mod citation;
+mod label;
 mod latex;
 
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 
 use dashmap::DashMap;
-use lsp_types::{DiagnosticSeverity, NumberOrString, Range, Url};
+use lsp_types::{DiagnosticRelatedInformation, DiagnosticSeverity, NumberOrString, Range, Url};
 use regex::Regex;
+use rustc_hash::FxHashSet;
+use strum::{EnumIter, IntoEnumIterator};
 
-use crate::Workspace;
+use crate::{Document, DocumentLanguage, Workspace};
 
 use self::{
     bibtex::collect_bibtex_diagnostics, build::collect_build_diagnostics,
-    chktex::collect_chktex_diagnostics, latex::collect_latex_diagnostics,
+    chktex::collect_chktex_diagnostics, citation::collect_citation_diagnostics,
+    label::collect_label_diagnostics, latex::collect_latex_diagnostics,
 };
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -22,6 +30,7 @@ pub struct Diagnostic {
     pub range: Range,
     pub code: DiagnosticCode,
     pub message: String,
+    pub related_information: Vec<DiagnosticRelatedInformation>,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
@@ -32,11 +41,35 @@ pub enum DiagnosticCode {
     Build(Arc<Url>),
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+impl DiagnosticCode {
+    /// Recovers the code of a diagnostic that has been published by the server.
+    /// Only diagnostics with the source `texlab` can be recovered.
+    #[must_use]
+    pub fn from_lsp(diagnostic: &lsp_types::Diagnostic) -> Option<Self> {
+        if diagnostic.source.as_deref() != Some("texlab") {
+            return None;
+        }
+
+        let code = diagnostic.code.as_ref()?;
+        LatexCode::iter()
+            .find(|&latex_code| NumberOrString::from(latex_code) == *code)
+            .map(Self::Latex)
+            .or_else(|| {
+                BibtexCode::iter()
+                    .find(|&bibtex_code| NumberOrString::from(bibtex_code) == *code)
+                    .map(Self::Bibtex)
+            })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, EnumIter)]
 pub enum LatexCode {
     UnexpectedRCurly,
     RCurlyInserted,
     MismatchedEnvironment,
+    UndefinedLabel,
+    UndefinedCitation,
+    DuplicateLabel,
 }
 
 impl From<LatexCode> for String {
@@ -45,6 +78,9 @@ impl From<LatexCode> for String {
             LatexCode::UnexpectedRCurly => "Unexpected \"}\"".to_string(),
             LatexCode::RCurlyInserted => "Missing \"}\" inserted".to_string(),
             LatexCode::MismatchedEnvironment => "Mismatched environment".to_string(),
+            LatexCode::UndefinedLabel => "Reference to undefined label".to_string(),
+            LatexCode::UndefinedCitation => "Reference to undefined citation key".to_string(),
+            LatexCode::DuplicateLabel => "Duplicate label".to_string(),
         }
     }
 }
@@ -55,11 +91,14 @@ impl From<LatexCode> for NumberOrString {
             LatexCode::UnexpectedRCurly => NumberOrString::Number(1),
             LatexCode::RCurlyInserted => NumberOrString::Number(2),
             LatexCode::MismatchedEnvironment => NumberOrString::Number(3),
+            LatexCode::UndefinedLabel => NumberOrString::Number(9),
+            LatexCode::UndefinedCitation => NumberOrString::Number(10),
+            LatexCode::DuplicateLabel => NumberOrString::Number(11),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, EnumIter)]
 #[allow(clippy::enum_variant_names)]
 pub enum BibtexCode {
     ExpectingLCurly,
@@ -67,6 +106,8 @@ pub enum BibtexCode {
     ExpectingRCurly,
     ExpectingEq,
     ExpectingFieldValue,
+    UnusedEntry,
+    DuplicateEntry,
 }
 
 impl From<BibtexCode> for String {
@@ -77,6 +118,8 @@ impl From<BibtexCode> for String {
             BibtexCode::ExpectingRCurly => "Expecting a curly bracket: \"}\"".to_string(),
             BibtexCode::ExpectingEq => "Expecting an equality sign: \"=\"".to_string(),
             BibtexCode::ExpectingFieldValue => "Expecting a field value".to_string(),
+            BibtexCode::UnusedEntry => "Unused entry".to_string(),
+            BibtexCode::DuplicateEntry => "Duplicate entry key".to_string(),
         }
     }
 }
@@ -89,28 +132,106 @@ impl From<BibtexCode> for NumberOrString {
             BibtexCode::ExpectingRCurly => NumberOrString::Number(6),
             BibtexCode::ExpectingEq => NumberOrString::Number(7),
             BibtexCode::ExpectingFieldValue => NumberOrString::Number(8),
+            BibtexCode::UnusedEntry => NumberOrString::Number(12),
+            BibtexCode::DuplicateEntry => NumberOrString::Number(13),
         }
     }
 }
 
+/// The diagnostics of a document as requested by the pull model.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DiagnosticReport {
+    Full {
+        result_id: String,
+        items: Vec<lsp_types::Diagnostic>,
+    },
+    Unchanged {
+        result_id: String,
+    },
+}
+
+#[derive(Debug)]
+struct PulledDiagnostics {
+    result_id: String,
+    items: Vec<lsp_types::Diagnostic>,
+}
+
 #[derive(Default, Clone)]
 pub struct DiagnosticManager {
     all_diagnostics: Arc<DashMap<Arc<Url>, Vec<Diagnostic>>>,
+    pulled_diagnostics: Arc<DashMap<Arc<Url>, PulledDiagnostics>>,
+    refreshed_diagnostics: Arc<DashMap<Arc<Url>, Vec<lsp_types::Diagnostic>>>,
+    next_result_id: Arc<AtomicU64>,
 }
 
 impl DiagnosticManager {
     pub fn push_syntax(&self, workspace: &Workspace, uri: &Url) {
+        self.collect_syntax(workspace, uri);
+
+        // Changing one document can affect the references of all other documents in its project.
+        self.collect_references(workspace, [uri]);
+    }
+
+    pub fn push_all(&self, workspace: &Workspace) {
+        for document in workspace.documents_by_uri.values() {
+            self.collect_syntax(workspace, &document.uri);
+        }
+
+        self.collect_all_references(workspace);
+    }
+
+    pub fn remove(&self, workspace: &Workspace, uri: &Url) {
+        self.all_diagnostics.remove(uri);
+        self.pulled_diagnostics.remove(uri);
+        self.refreshed_diagnostics.remove(uri);
+        self.collect_all_references(workspace);
+    }
+
+    fn collect_syntax(&self, workspace: &Workspace, uri: &Url) {
         collect_bibtex_diagnostics(&self.all_diagnostics, workspace, uri)
             .or_else(|| collect_latex_diagnostics(&self.all_diagnostics, workspace, uri))
             .or_else(|| collect_build_diagnostics(&self.all_diagnostics, workspace, uri));
     }
 
+    fn collect_all_references(&self, workspace: &Workspace) {
+        // Starting with the root documents makes sure that each project is only analyzed once.
+        let mut documents: Vec<_> = workspace.documents_by_uri.values().collect();
+        documents.sort_by_key(|document| {
+            !document
+                .data
+                .as_latex()
+                .map_or(false, |data| data.extras.has_document_environment)
+        });
+
+        self.collect_references(workspace, documents.iter().map(|document| &*document.uri));
+    }
+
+    /// Analyzes the references of the projects that contain the given documents.
+    /// The label and citation tables are built once per project instead of once per document.
+    fn collect_references<'a>(
+        &self,
+        workspace: &Workspace,
+        uris: impl IntoIterator<Item = &'a Url>,
+    ) {
+        let mut visited = FxHashSet::default();
+        for uri in uris {
+            if visited.contains(uri) {
+                continue;
+            }
+
+            let project = workspace.slice(uri);
+            collect_label_diagnostics(&self.all_diagnostics, &project);
+            collect_citation_diagnostics(&self.all_diagnostics, &project);
+            visited.extend(project.documents_by_uri.into_keys());
+        }
+    }
+
     pub fn push_chktex(&self, workspace: &Workspace, uri: &Url) {
         collect_chktex_diagnostics(&self.all_diagnostics, workspace, uri);
     }
 
     pub fn publish(&self, workspace: &Workspace, uri: &Url) -> Vec<lsp_types::Diagnostic> {
-        let options = &workspace.environment.options.diagnostics;
+        let options = &workspace.environment_for(uri).options.diagnostics;
 
         let mut results = Vec::new();
         if let Some(diagnostics) = self.all_diagnostics.get(uri) {
@@ -151,6 +272,8 @@ impl DiagnosticManager {
                     severity: Some(diagnostic.severity),
                     message: diagnostic.message.clone(),
                     source: Some(source.to_string()),
+                    related_information: Some(diagnostic.related_information.clone())
+                        .filter(|info| !info.is_empty()),
                     ..Default::default()
                 });
             }
@@ -158,6 +281,98 @@ impl DiagnosticManager {
 
         results
     }
+
+    /// Checks if the diagnostics of a document have changed since the client has last pulled them
+    /// or has been asked to refresh them.
+    /// Each change is only reported once, so that the client is not asked to refresh repeatedly.
+    pub fn needs_refresh(&self, workspace: &Workspace) -> bool {
+        let mut changed = false;
+        for document in workspace.documents_by_uri.values() {
+            if document.data.as_build_log().is_some() {
+                continue;
+            }
+
+            let items = self.publish(workspace, &document.uri);
+            let is_pulled = self
+                .pulled_diagnostics
+                .get(&document.uri)
+                .map_or(items.is_empty(), |pulled| pulled.items == items);
+
+            let is_refreshed = self
+                .refreshed_diagnostics
+                .get(&document.uri)
+                .map_or(false, |refreshed| *refreshed == items);
+
+            if !is_pulled && !is_refreshed {
+                self.refreshed_diagnostics
+                    .insert(Arc::clone(&document.uri), items);
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Creates a report of the current diagnostics of a document.
+    /// The report is unchanged if the client already knows the diagnostics
+    /// identified by `previous_result_id`.
+    pub fn report(
+        &self,
+        workspace: &Workspace,
+        uri: &Arc<Url>,
+        previous_result_id: Option<&str>,
+    ) -> DiagnosticReport {
+        let items = self.publish(workspace, uri);
+        if let Some(pulled) = self.pulled_diagnostics.get(uri) {
+            if previous_result_id == Some(pulled.result_id.as_str()) && pulled.items == items {
+                return DiagnosticReport::Unchanged {
+                    result_id: pulled.result_id.clone(),
+                };
+            }
+        }
+
+        let result_id = self
+            .next_result_id
+            .fetch_add(1, Ordering::SeqCst)
+            .to_string();
+
+        self.refreshed_diagnostics.remove(uri);
+        self.pulled_diagnostics.insert(
+            Arc::clone(uri),
+            PulledDiagnostics {
+                result_id: result_id.clone(),
+                items: items.clone(),
+            },
+        );
+
+        DiagnosticReport::Full { result_id, items }
+    }
+}
+
+/// Checks if a LaTeX document has been written by the user.
+/// Auxiliary files and the files of the TeX distribution do not contain references that the user can fix.
+fn is_user_document(project: &Workspace, document: &Document) -> bool {
+    if document.data.language() != DocumentLanguage::Latex {
+        return false;
+    }
+
+    let path = match document.uri.to_file_path() {
+        Ok(path) => path,
+        Err(()) => return true,
+    };
+
+    let is_aux = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| extension.eq_ignore_ascii_case("aux"));
+
+    let is_distro_file = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| project.environment.resolver.files_by_name.get(name))
+        .map_or(false, |distro_path| *distro_path == path);
+
+    !is_aux && !is_distro_file
 }
 
 #[derive(Debug, Default)]
@@ -51,6 +51,7 @@ fn analyze_entry(
                     .line_col_lsp_range(entry.type_token()?.text_range()),
                 code: DiagnosticCode::Bibtex(code),
                 message: String::from(code),
+                related_information: Vec::new(),
             });
 
         return Some(());
@@ -68,6 +69,7 @@ fn analyze_entry(
                     .line_col_lsp_range(entry.left_delim_token()?.text_range()),
                 code: DiagnosticCode::Bibtex(code),
                 message: String::from(code),
+                related_information: Vec::new(),
             });
 
         return Some(());
@@ -85,6 +87,7 @@ fn analyze_entry(
                     .line_col_lsp_range(TextRange::empty(entry.syntax().text_range().end())),
                 code: DiagnosticCode::Bibtex(code),
                 message: String::from(code),
+                related_information: Vec::new(),
             });
 
         return Some(());
@@ -112,6 +115,7 @@ fn analyze_field(
 
                 code: DiagnosticCode::Bibtex(code),
                 message: String::from(code),
+                related_information: Vec::new(),
             });
 
         return Some(());
@@ -130,6 +134,7 @@ fn analyze_field(
 
                 code: DiagnosticCode::Bibtex(code),
                 message: String::from(code),
+                related_information: Vec::new(),
             });
 
         return Some(());
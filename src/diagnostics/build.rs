@@ -47,6 +47,7 @@ pub fn collect_build_diagnostics(
             range,
             code: DiagnosticCode::Build(Arc::clone(&build_log_document.uri)),
             message: error.message.clone(),
+            related_information: Vec::new(),
         };
 
         let full_path = base_path.join(&error.relative_path);
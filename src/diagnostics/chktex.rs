@@ -31,7 +31,7 @@ pub fn collect_chktex_diagnostics(
     let current_dir = find_chktexrc_directory(document)
         .or_else(|| {
             workspace
-                .environment
+                .environment_for(uri)
                 .options
                 .root_directory
                 .as_ref()
@@ -117,6 +117,7 @@ fn lint(text: &str, current_dir: &Path) -> io::Result<Vec<Diagnostic>> {
             severity,
             code: DiagnosticCode::Chktex(code.into()),
             message,
+            related_information: Vec::new(),
         });
     }
 
@@ -0,0 +1,268 @@
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use lsp_types::{DiagnosticRelatedInformation, DiagnosticSeverity, Location, Url};
+use rowan::ast::AstNode;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    syntax::{
+        bibtex::{self, HasName},
+        latex::{self, HasCurly},
+    },
+    Document, LatexDocumentData, LineIndexExt, Workspace,
+};
+
+use super::{is_user_document, BibtexCode, Diagnostic, DiagnosticCode, LatexCode};
+
+/// The entry keys of all BibTeX documents in a project grouped by their name.
+type KeysByName<'a> = FxHashMap<String, Vec<(&'a Document, bibtex::SyntaxToken)>>;
+
+/// Reports the undefined citations, unused entries and duplicate entry keys
+/// of all documents in the given project.
+pub fn collect_citation_diagnostics(
+    all_diagnostics: &DashMap<Arc<Url>, Vec<Diagnostic>>,
+    project: &Workspace,
+) {
+    let mut keys_by_name = KeysByName::default();
+    let mut has_bibliography = false;
+    for (document, data) in project
+        .documents_by_uri
+        .values()
+        .filter_map(|document| Some((document, document.data.as_bibtex()?)))
+    {
+        has_bibliography = true;
+        for key in bibtex::SyntaxNode::new_root(data.green.clone())
+            .children()
+            .filter_map(bibtex::Entry::cast)
+            .filter_map(|entry| entry.name_token())
+        {
+            keys_by_name
+                .entry(key.text().to_string())
+                .or_default()
+                .push((document, key));
+        }
+    }
+
+    let mut entry_keys: FxHashSet<String> = keys_by_name.keys().cloned().collect();
+
+    let user_documents: Vec<_> = project
+        .documents_by_uri
+        .values()
+        .filter(|document| is_user_document(project, document))
+        .collect();
+
+    for data in user_documents
+        .iter()
+        .filter_map(|document| document.data.as_latex())
+    {
+        let bibitem_keys = find_bibitem_keys(data);
+        has_bibliography |= !bibitem_keys.is_empty();
+        entry_keys.extend(bibitem_keys);
+    }
+
+    let entry_keys = if has_bibliography {
+        Some(&entry_keys)
+    } else {
+        None
+    };
+
+    let mut cited_keys = FxHashSet::default();
+    for document in &user_documents {
+        cited_keys.extend(analyze_undefined_citations(
+            all_diagnostics,
+            document,
+            entry_keys,
+        ));
+    }
+
+    for document in project
+        .documents_by_uri
+        .values()
+        .filter(|document| document.data.as_bibtex().is_some())
+    {
+        // Without a document that includes the bibliography, every entry would be reported.
+        if !user_documents.is_empty() {
+            analyze_unused_entries(all_diagnostics, document, &cited_keys);
+        }
+
+        analyze_duplicate_entries(all_diagnostics, document, &keys_by_name);
+    }
+}
+
+/// Finds the keys of `\bibitem` commands, which define the entries of a `thebibliography` environment.
+fn find_bibitem_keys(data: &LatexDocumentData) -> Vec<String> {
+    latex::SyntaxNode::new_root(data.green.clone())
+        .descendants()
+        .filter_map(latex::GenericCommand::cast)
+        .filter(|command| {
+            command
+                .name()
+                .map_or(false, |name| name.text() == "\\bibitem")
+        })
+        .filter_map(|command| {
+            command
+                .syntax()
+                .children()
+                .find_map(latex::CurlyGroup::cast)
+        })
+        .filter_map(|group| group.content_text())
+        .collect()
+}
+
+/// Reports the citations of a document whose key is not defined
+/// and returns the keys of all citations.
+/// The citations are not checked if the project does not have a bibliography yet.
+fn analyze_undefined_citations(
+    all_diagnostics: &DashMap<Arc<Url>, Vec<Diagnostic>>,
+    document: &Document,
+    entry_keys: Option<&FxHashSet<String>>,
+) -> Vec<String> {
+    let data = match document.data.as_latex() {
+        Some(data) => data,
+        None => return Vec::new(),
+    };
+
+    all_diagnostics.alter(&document.uri, |_, mut diagnostics| {
+        diagnostics.retain(|diag| {
+            !matches!(
+                diag.code,
+                DiagnosticCode::Latex(LatexCode::UndefinedCitation)
+            )
+        });
+        diagnostics
+    });
+
+    let mut cited_keys = Vec::new();
+    for key in latex::SyntaxNode::new_root(data.green.clone())
+        .descendants()
+        .filter_map(latex::Citation::cast)
+        .filter_map(|citation| citation.key_list())
+        .flat_map(|keys| keys.keys())
+    {
+        let text = key.to_string();
+        let is_defined =
+            text == "*" || entry_keys.map_or(true, |entry_keys| entry_keys.contains(&text));
+
+        cited_keys.push(text);
+        if is_defined {
+            continue;
+        }
+
+        let code = LatexCode::UndefinedCitation;
+        all_diagnostics
+            .entry(Arc::clone(&document.uri))
+            .or_default()
+            .push(Diagnostic {
+                severity: DiagnosticSeverity::WARNING,
+                range: document
+                    .line_index
+                    .line_col_lsp_range(latex::small_range(&key)),
+                code: DiagnosticCode::Latex(code),
+                message: String::from(code),
+                related_information: Vec::new(),
+            });
+    }
+
+    cited_keys
+}
+
+fn analyze_unused_entries(
+    all_diagnostics: &DashMap<Arc<Url>, Vec<Diagnostic>>,
+    document: &Document,
+    cited_keys: &FxHashSet<String>,
+) -> Option<()> {
+    let data = document.data.as_bibtex()?;
+
+    all_diagnostics.alter(&document.uri, |_, mut diagnostics| {
+        diagnostics
+            .retain(|diag| !matches!(diag.code, DiagnosticCode::Bibtex(BibtexCode::UnusedEntry)));
+        diagnostics
+    });
+
+    // With \nocite{*}, all entries are cited.
+    if cited_keys.contains("*") {
+        return Some(());
+    }
+
+    for key in bibtex::SyntaxNode::new_root(data.green.clone())
+        .children()
+        .filter_map(bibtex::Entry::cast)
+        .filter_map(|entry| entry.name_token())
+        .filter(|key| !cited_keys.contains(key.text()))
+    {
+        let code = BibtexCode::UnusedEntry;
+        all_diagnostics
+            .entry(Arc::clone(&document.uri))
+            .or_default()
+            .push(Diagnostic {
+                severity: DiagnosticSeverity::HINT,
+                range: document.line_index.line_col_lsp_range(key.text_range()),
+                code: DiagnosticCode::Bibtex(code),
+                message: String::from(code),
+                related_information: Vec::new(),
+            });
+    }
+
+    Some(())
+}
+
+fn analyze_duplicate_entries(
+    all_diagnostics: &DashMap<Arc<Url>, Vec<Diagnostic>>,
+    document: &Document,
+    keys_by_name: &KeysByName,
+) -> Option<()> {
+    let data = document.data.as_bibtex()?;
+
+    all_diagnostics.alter(&document.uri, |_, mut diagnostics| {
+        diagnostics.retain(|diag| {
+            !matches!(
+                diag.code,
+                DiagnosticCode::Bibtex(BibtexCode::DuplicateEntry)
+            )
+        });
+        diagnostics
+    });
+
+    for key in bibtex::SyntaxNode::new_root(data.green.clone())
+        .children()
+        .filter_map(bibtex::Entry::cast)
+        .filter_map(|entry| entry.name_token())
+    {
+        let related_information: Vec<_> = keys_by_name
+            .get(key.text())
+            .into_iter()
+            .flatten()
+            .filter(|(other_document, other)| {
+                other_document.uri != document.uri || other.text_range() != key.text_range()
+            })
+            .map(|(other_document, other)| DiagnosticRelatedInformation {
+                location: Location::new(
+                    other_document.uri.as_ref().clone(),
+                    other_document
+                        .line_index
+                        .line_col_lsp_range(other.text_range()),
+                ),
+                message: format!("\"{}\" is also defined here", other.text()),
+            })
+            .collect();
+
+        if related_information.is_empty() {
+            continue;
+        }
+
+        let code = BibtexCode::DuplicateEntry;
+        all_diagnostics
+            .entry(Arc::clone(&document.uri))
+            .or_default()
+            .push(Diagnostic {
+                severity: DiagnosticSeverity::WARNING,
+                range: document.line_index.line_col_lsp_range(key.text_range()),
+                code: DiagnosticCode::Bibtex(code),
+                message: String::from(code),
+                related_information,
+            });
+    }
+
+    Some(())
+}
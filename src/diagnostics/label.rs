@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use lsp_types::{DiagnosticRelatedInformation, DiagnosticSeverity, Location, Url};
+use rustc_hash::FxHashMap;
+
+use crate::{syntax::latex::LabelName, Document, LineIndexExt, Workspace};
+
+use super::{is_user_document, Diagnostic, DiagnosticCode, LatexCode};
+
+/// The label definitions of a project grouped by their name.
+type DefinitionsByName<'a> = FxHashMap<&'a str, Vec<(&'a Document, &'a LabelName)>>;
+
+/// Reports the undefined and duplicate labels of all documents in the given project.
+pub fn collect_label_diagnostics(
+    all_diagnostics: &DashMap<Arc<Url>, Vec<Diagnostic>>,
+    project: &Workspace,
+) {
+    let mut definitions_by_name = DefinitionsByName::default();
+    for (document, data) in project
+        .documents_by_uri
+        .values()
+        .filter_map(|document| Some((document, document.data.as_latex()?)))
+    {
+        for name in data
+            .extras
+            .label_names
+            .iter()
+            .filter(|name| name.is_definition)
+        {
+            definitions_by_name
+                .entry(name.text.as_str())
+                .or_default()
+                .push((document, name));
+        }
+    }
+
+    for document in project
+        .documents_by_uri
+        .values()
+        .filter(|document| is_user_document(project, document))
+    {
+        all_diagnostics.alter(&document.uri, |_, mut diagnostics| {
+            diagnostics.retain(|diag| {
+                !matches!(
+                    diag.code,
+                    DiagnosticCode::Latex(LatexCode::UndefinedLabel | LatexCode::DuplicateLabel)
+                )
+            });
+            diagnostics
+        });
+
+        analyze_undefined_labels(all_diagnostics, document, &definitions_by_name);
+        analyze_duplicate_labels(all_diagnostics, document, &definitions_by_name);
+    }
+}
+
+fn analyze_undefined_labels(
+    all_diagnostics: &DashMap<Arc<Url>, Vec<Diagnostic>>,
+    document: &Document,
+    definitions_by_name: &DefinitionsByName,
+) -> Option<()> {
+    let data = document.data.as_latex()?;
+    for name in data
+        .extras
+        .label_names
+        .iter()
+        .filter(|name| !name.is_definition)
+        .filter(|name| !definitions_by_name.contains_key(name.text.as_str()))
+    {
+        // Only labels that look like a typo of the reference are suggested.
+        let max_distance = (name.text.chars().count() / 3).max(2);
+        let related_information = definitions_by_name
+            .values()
+            .flatten()
+            .map(|(def_document, def)| (def_document, def, edit_distance(&def.text, &name.text)))
+            .filter(|(_, _, distance)| *distance <= max_distance)
+            .min_by_key(|(_, _, distance)| *distance)
+            .map(|(def_document, def, _)| DiagnosticRelatedInformation {
+                location: Location::new(
+                    def_document.uri.as_ref().clone(),
+                    def_document.line_index.line_col_lsp_range(def.range),
+                ),
+                message: format!("Did you mean \"{}\"?", def.text),
+            })
+            .into_iter()
+            .collect();
+
+        let code = LatexCode::UndefinedLabel;
+        all_diagnostics
+            .entry(Arc::clone(&document.uri))
+            .or_default()
+            .push(Diagnostic {
+                severity: DiagnosticSeverity::WARNING,
+                range: document.line_index.line_col_lsp_range(name.range),
+                code: DiagnosticCode::Latex(code),
+                message: String::from(code),
+                related_information,
+            });
+    }
+
+    Some(())
+}
+
+fn analyze_duplicate_labels(
+    all_diagnostics: &DashMap<Arc<Url>, Vec<Diagnostic>>,
+    document: &Document,
+    definitions_by_name: &DefinitionsByName,
+) -> Option<()> {
+    let data = document.data.as_latex()?;
+    for name in data
+        .extras
+        .label_names
+        .iter()
+        .filter(|name| name.is_definition)
+    {
+        let related_information: Vec<_> = definitions_by_name
+            .get(name.text.as_str())
+            .into_iter()
+            .flatten()
+            .filter(|(other_document, other)| {
+                other_document.uri != document.uri || other.range != name.range
+            })
+            .map(|(other_document, other)| DiagnosticRelatedInformation {
+                location: Location::new(
+                    other_document.uri.as_ref().clone(),
+                    other_document.line_index.line_col_lsp_range(other.range),
+                ),
+                message: format!("\"{}\" is also defined here", other.text),
+            })
+            .collect();
+
+        if related_information.is_empty() {
+            continue;
+        }
+
+        let code = LatexCode::DuplicateLabel;
+        all_diagnostics
+            .entry(Arc::clone(&document.uri))
+            .or_default()
+            .push(Diagnostic {
+                severity: DiagnosticSeverity::WARNING,
+                range: document.line_index.line_col_lsp_range(name.range),
+                code: DiagnosticCode::Latex(code),
+                message: String::from(code),
+                related_information,
+            });
+    }
+
+    Some(())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, c1) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &c2) in b.iter().enumerate() {
+            let cost = if c1 == c2 { diagonal } else { diagonal + 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = cost.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
@@ -39,6 +39,7 @@ pub fn collect_latex_diagnostics(
                             range: document.line_index.line_col_lsp_range(node.text_range()),
                             code: DiagnosticCode::Latex(code),
                             message: String::from(code),
+                            related_information: Vec::new(),
                         });
 
                     Some(())
@@ -71,6 +72,7 @@ fn analyze_environment(
                     .line_col_lsp_range(latex::small_range(&name1)),
                 code: DiagnosticCode::Latex(code),
                 message: String::from(code),
+                related_information: Vec::new(),
             });
     }
     Some(())
@@ -119,6 +121,7 @@ fn analyze_curly_group(
                     .line_col_lsp_range(TextRange::empty(node.text_range().end())),
                 code: DiagnosticCode::Latex(code),
                 message: String::from(code),
+                related_information: Vec::new(),
             });
     }
 
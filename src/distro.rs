@@ -7,10 +7,13 @@ use std::process::{Command, Stdio};
 use anyhow::Result;
 use derive_more::Display;
 use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::Cache;
 
 pub use kpsewhich::Resolver;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Display)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Display, Serialize, Deserialize)]
 pub enum DistributionKind {
     #[display(fmt = "TeXLive")]
     Texlive,
@@ -29,8 +32,10 @@ pub struct Distribution {
 }
 
 impl Distribution {
+    /// Detects the installed distribution.
+    /// If a cache is given, the file database is only read again if it has changed.
     #[must_use]
-    pub fn detect() -> Self {
+    pub fn detect(cache: Option<&Cache>) -> Self {
         let kind = match Command::new("latex").arg("--version").output() {
             Ok(output) => {
                 let stdout = String::from_utf8_lossy(&output.stdout);
@@ -58,8 +63,8 @@ impl Distribution {
         };
 
         let resolver = match kind {
-            DistributionKind::Texlive => Self::load_resolver(texlive::load_resolver),
-            DistributionKind::Miktex => Self::load_resolver(miktex::load_resolver),
+            DistributionKind::Texlive => Self::load_resolver(|| texlive::load_resolver(cache)),
+            DistributionKind::Miktex => Self::load_resolver(|| miktex::load_resolver(cache)),
             DistributionKind::Tectonic | DistributionKind::Unknown => Resolver::default(),
         };
         Self { kind, resolver }
@@ -8,11 +8,14 @@ use std::{
 
 use anyhow::Result;
 use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 
-use crate::DocumentLanguage;
+use crate::{Cache, DocumentLanguage};
 
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
+use super::DistributionKind;
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
 pub struct Resolver {
     pub files_by_name: FxHashMap<SmolStr, PathBuf>,
 }
@@ -24,6 +27,33 @@ impl Resolver {
     }
 }
 
+/// Loads the resolver from the cache if the distribution and its root directories are the same
+/// and the database files have not changed. Otherwise, the database is parsed again.
+/// Querying the root directories using `kpsewhich` is cheap compared to parsing the database.
+pub fn load_resolver(
+    cache: Option<&Cache>,
+    kind: DistributionKind,
+    database_files: impl Fn(&Path) -> Vec<PathBuf>,
+    reader: impl FnMut(&Path) -> Result<Vec<PathBuf>>,
+) -> Result<Resolver> {
+    let root_directories = root_directories()?;
+    if let Some(resolver) = cache.and_then(|cache| cache.load_resolver(kind, &root_directories)) {
+        return Ok(resolver);
+    }
+
+    let resolver = parse_database(&root_directories, reader)?;
+    if let Some(cache) = cache {
+        let database_files: Vec<_> = root_directories
+            .iter()
+            .flat_map(|directory| database_files(directory))
+            .collect();
+
+        cache.store_resolver(kind, &root_directories, &database_files, &resolver);
+    }
+
+    Ok(resolver)
+}
+
 pub fn parse_database(
     root_directories: &[PathBuf],
     mut reader: impl FnMut(&Path) -> Result<Vec<PathBuf>>,
@@ -8,12 +8,20 @@ use std::{
 use anyhow::{Context, Result};
 use byteorder::{LittleEndian, ReadBytesExt};
 
-use super::kpsewhich::{self, Resolver};
+use crate::Cache;
 
-pub fn load_resolver() -> Result<Resolver> {
-    let root_directories = kpsewhich::root_directories()?;
-    let resolver = kpsewhich::parse_database(&root_directories, read_database)?;
-    Ok(resolver)
+use super::{
+    kpsewhich::{self, Resolver},
+    DistributionKind,
+};
+
+pub fn load_resolver(cache: Option<&Cache>) -> Result<Resolver> {
+    kpsewhich::load_resolver(
+        cache,
+        DistributionKind::Miktex,
+        database_files,
+        read_database,
+    )
 }
 
 const DATABASE_PATH: &str = "miktex/data/le";
@@ -23,6 +31,22 @@ const FNDB_TABLE_POINTER_OFFSET: u32 = 4 * FNDB_WORD_SIZE;
 const FNDB_TABLE_SIZE_OFFSET: u32 = 6 * FNDB_WORD_SIZE;
 const FNDB_ENTRY_SIZE: u32 = 4 * FNDB_WORD_SIZE;
 
+/// Includes the database directory itself because adding a database file changes its timestamp.
+fn database_files(directory: &Path) -> Vec<PathBuf> {
+    let database_directory = directory.join(DATABASE_PATH);
+    let mut files: Vec<_> = fs::read_dir(&database_directory)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|file| file.path())
+        .filter(|path| path.extension().and_then(OsStr::to_str) == Some("fndb-5"))
+        .collect();
+
+    files.sort();
+    files.insert(0, database_directory);
+    files
+}
+
 fn read_database(directory: &Path) -> Result<Vec<PathBuf>> {
     let database_directory = directory.join(DATABASE_PATH);
     if !database_directory.exists() {
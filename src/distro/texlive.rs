@@ -6,16 +6,28 @@ use std::{
 
 use anyhow::Result;
 
-use super::kpsewhich::{self, Resolver};
+use crate::Cache;
 
-pub fn load_resolver() -> Result<Resolver> {
-    let root_directories = kpsewhich::root_directories()?;
-    let resolver = kpsewhich::parse_database(&root_directories, read_database)?;
-    Ok(resolver)
+use super::{
+    kpsewhich::{self, Resolver},
+    DistributionKind,
+};
+
+pub fn load_resolver(cache: Option<&Cache>) -> Result<Resolver> {
+    kpsewhich::load_resolver(
+        cache,
+        DistributionKind::Texlive,
+        database_files,
+        read_database,
+    )
 }
 
 const DATABASE_PATH: &str = "ls-R";
 
+fn database_files(directory: &Path) -> Vec<PathBuf> {
+    vec![directory.join(DATABASE_PATH)]
+}
+
 fn read_database(directory: &Path) -> Result<Vec<PathBuf>> {
     let file = directory.join(DATABASE_PATH);
     if !file.is_file() {
@@ -1,10 +1,13 @@
-use std::{fmt, sync::Arc};
+use std::{fmt, fs, path::Path, sync::Arc};
 
+use anyhow::{anyhow, Result};
 use derive_more::From;
-use lsp_types::Url;
+use lsp_types::{TextDocumentContentChangeEvent, Url};
 
 use crate::{
+    cache::FileStamp,
     line_index::LineIndex,
+    line_index_ext::LineIndexExt,
     syntax::{
         bibtex, build_log,
         latex::{self, LatexAnalyzerContext},
@@ -94,26 +97,7 @@ impl Document {
         let data = match language {
             DocumentLanguage::Latex => {
                 let green = latex::parse(&text).green;
-                let root = latex::SyntaxNode::new_root(green.clone());
-
-                let base_uri = match &environment.options.root_directory {
-                    Some(root_dir) => {
-                        let root_dir = environment.current_directory.join(&root_dir);
-                        Url::from_directory_path(root_dir)
-                            .map_or_else(|()| Arc::clone(&uri), Arc::new)
-                    }
-                    None => Arc::clone(&uri),
-                };
-
-                let mut context = LatexAnalyzerContext {
-                    environment,
-                    extras: latex::Extras::default(),
-                    document_uri: Arc::clone(&uri),
-                    base_uri,
-                };
-                latex::analyze(&mut context, &root);
-                let extras = Arc::new(context.extras);
-                DocumentData::Latex(Box::new(LatexDocumentData { green, extras }))
+                Self::analyze_latex(environment, &uri, green)
             }
             DocumentLanguage::Bibtex => {
                 let green = bibtex::parse(&text);
@@ -132,4 +116,124 @@ impl Document {
             data,
         }
     }
+
+    /// Reads a document from disk.
+    /// The analysis of a LaTeX document is taken from the cache if the file has not changed since.
+    /// The syntax tree is not cached, so the document is always parsed again.
+    pub fn load(
+        environment: &Environment,
+        path: &Path,
+        language: DocumentLanguage,
+    ) -> Result<Self> {
+        let uri = Arc::new(Url::from_file_path(path).map_err(|()| anyhow!("invalid path"))?);
+        let stamp = FileStamp::new(path);
+        let data = fs::read(path)?;
+        let text = Arc::new(String::from_utf8_lossy(&data).into_owned());
+
+        let cache = match (&environment.cache, language) {
+            (Some(cache), DocumentLanguage::Latex) => cache,
+            _ => return Ok(Self::parse(environment, uri, text, language)),
+        };
+
+        // Most features need the syntax tree, so only the analysis of the tree is skipped.
+        let green = latex::parse(&text).green;
+        let extras = match cache.get_extras(&stamp, environment) {
+            Some(extras) => extras,
+            None => {
+                let root = latex::SyntaxNode::new_root(green.clone());
+                let extras = Self::analyze_extras(environment, &uri, &root);
+                cache.insert_extras(stamp, environment, &extras);
+                extras
+            }
+        };
+
+        let line_index = Arc::new(LineIndex::new(&text));
+        let extras = Arc::new(extras);
+        Ok(Self {
+            uri,
+            text,
+            line_index,
+            data: DocumentData::Latex(Box::new(LatexDocumentData { green, extras })),
+        })
+    }
+
+    /// Applies the given changes to the document and parses it again.
+    /// LaTeX documents are parsed incrementally if possible,
+    /// so that only the parts of the syntax tree that are affected by the edit are rebuilt.
+    /// The analysis of the document still runs over the whole tree.
+    #[must_use]
+    pub fn edit(
+        &self,
+        environment: &Environment,
+        changes: Vec<TextDocumentContentChangeEvent>,
+    ) -> Self {
+        let mut text = self.text.to_string();
+        let mut green = self.data.as_latex().map(|data| data.green.clone());
+        for change in changes {
+            match change.range {
+                Some(range) => {
+                    let line_index = LineIndex::new(&text);
+                    let range = line_index.offset_lsp_range(range);
+                    green = green
+                        .and_then(|green| latex::reparse(&green, range, &change.text))
+                        .map(|parse| parse.green);
+
+                    text.replace_range(std::ops::Range::<usize>::from(range), &change.text);
+                }
+                None => {
+                    text = change.text;
+                    green = None;
+                }
+            };
+        }
+
+        let uri = Arc::clone(&self.uri);
+        let text = Arc::new(text);
+        match green {
+            Some(green) => {
+                let line_index = Arc::new(LineIndex::new(&text));
+                let data = Self::analyze_latex(environment, &uri, green);
+                Self {
+                    uri,
+                    text,
+                    line_index,
+                    data,
+                }
+            }
+            None => Self::parse(environment, uri, text, self.data.language()),
+        }
+    }
+
+    fn analyze_latex(
+        environment: &Environment,
+        uri: &Arc<Url>,
+        green: rowan::GreenNode,
+    ) -> DocumentData {
+        let root = latex::SyntaxNode::new_root(green.clone());
+        let extras = Arc::new(Self::analyze_extras(environment, uri, &root));
+        DocumentData::Latex(Box::new(LatexDocumentData { green, extras }))
+    }
+
+    fn analyze_extras(
+        environment: &Environment,
+        uri: &Arc<Url>,
+        root: &latex::SyntaxNode,
+    ) -> latex::Extras {
+        let base_uri = match &environment.options.root_directory {
+            Some(root_dir) => {
+                let root_dir = environment.current_directory.join(root_dir);
+                Url::from_directory_path(root_dir).map_or_else(|()| Arc::clone(uri), Arc::new)
+            }
+            None => Arc::clone(uri),
+        };
+
+        let mut context = LatexAnalyzerContext {
+            environment,
+            extras: latex::Extras::default(),
+            document_uri: Arc::clone(uri),
+            base_uri,
+        };
+        latex::analyze(&mut context, root);
+        context.extras
+    }
 }
@@ -2,7 +2,7 @@ use std::{path::PathBuf, sync::Arc};
 
 use lsp_types::{ClientCapabilities, ClientInfo};
 
-use crate::{distro::Resolver, Options};
+use crate::{distro::Resolver, Cache, Options};
 
 #[derive(Debug, Clone)]
 pub struct Environment {
@@ -11,6 +11,7 @@ pub struct Environment {
     pub client_info: Option<Arc<ClientInfo>>,
     pub options: Arc<Options>,
     pub resolver: Arc<Resolver>,
+    pub cache: Option<Arc<Cache>>,
 }
 
 impl Environment {
@@ -22,6 +23,7 @@ impl Environment {
             client_info: None,
             options: Arc::new(Options::default()),
             resolver: Arc::new(Resolver::default()),
+            cache: None,
         }
     }
 }
@@ -1,4 +1,6 @@
 mod build;
+mod code_action;
+mod color;
 mod completion;
 mod cursor;
 mod definition;
@@ -8,11 +10,17 @@ mod formatting;
 mod forward_search;
 mod highlight;
 mod hover;
+mod inlay_hint;
 mod link;
+mod linked_editing_range;
 mod lsp_kinds;
 mod reference;
 mod rename;
+mod selection_range;
+mod semantic_tokens;
+mod signature_help;
 mod symbol;
+mod synctex;
 
 use std::sync::Arc;
 
@@ -21,19 +29,34 @@ use lsp_types::Url;
 use crate::{Document, Workspace};
 
 pub use self::{
-    build::{BuildEngine, BuildParams, BuildResult, BuildStatus},
+    build::{
+        BuildEngine, BuildHandle, BuildParams, BuildResult, BuildStatus, ContinuousBuildStatus,
+    },
+    code_action::find_code_actions,
+    color::{find_color_presentations, find_document_colors},
     completion::{complete, CompletionItemData, COMPLETION_LIMIT},
     definition::goto_definition,
     execute_command::execute_command,
     folding::find_foldings,
-    formatting::format_source_code,
+    formatting::{format_on_type, format_source_code, format_source_code_range},
     forward_search::{execute_forward_search, ForwardSearchResult, ForwardSearchStatus},
     highlight::find_document_highlights,
     hover::find_hover,
+    inlay_hint::find_inlay_hints,
     link::find_document_links,
+    linked_editing_range::find_linked_editing_ranges,
     reference::find_all_references,
     rename::{prepare_rename_all, rename_all},
+    selection_range::find_selection_ranges,
+    semantic_tokens::{
+        find_semantic_tokens_full, find_semantic_tokens_range, semantic_tokens_legend,
+    },
+    signature_help::find_signature_help,
     symbol::{find_document_symbols, find_workspace_symbols},
+    synctex::{
+        execute_synctex_forward, execute_synctex_inverse, SyncTexForwardResult,
+        SyncTexInverseParams,
+    },
 };
 
 #[derive(Clone)]
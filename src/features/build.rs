@@ -1,32 +1,44 @@
+mod continuous;
+
 use std::{
     io::{BufRead, BufReader, Read},
-    path::Path,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
     thread::{self, JoinHandle},
 };
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use crossbeam_channel::{Receiver, Sender};
 use dashmap::DashMap;
 use encoding_rs_io::DecodeReaderBytesBuilder;
+use log::warn;
+use lsp_server::RequestId;
 use lsp_types::{
     notification::{LogMessage, Progress},
     LogMessageParams, NumberOrString, Position, ProgressParams, ProgressParamsValue,
     TextDocumentIdentifier, TextDocumentPositionParams, Url, WorkDoneProgress,
     WorkDoneProgressBegin, WorkDoneProgressCreateParams, WorkDoneProgressEnd,
 };
+use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use uuid::Uuid;
 
 use crate::{
     client::{self, ReqQueue},
-    ClientCapabilitiesExt, DocumentLanguage,
+    ClientCapabilitiesExt, ContinuousBuildArgs, Document, DocumentLanguage, Options,
 };
 
+use self::continuous::ContinuousBuild;
+
 use super::{forward_search, FeatureRequest};
 
+pub use self::continuous::ContinuousBuildStatus;
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BuildParams {
@@ -73,7 +85,7 @@ impl<'a> ProgressReporter<'a> {
                         WorkDoneProgressBegin {
                             title: "Building".to_string(),
                             message: Some(uri.as_str().to_string()),
-                            cancellable: Some(false),
+                            cancellable: Some(true),
                             percentage: None,
                         },
                     )),
@@ -100,33 +112,143 @@ impl<'a> Drop for ProgressReporter<'a> {
     }
 }
 
+/// Allows cancelling a build that is either running or waiting for the previous build to finish.
+#[derive(Debug)]
+pub struct BuildHandle {
+    token: String,
+    request_id: Option<RequestId>,
+    cancelled: AtomicBool,
+    process_id: Mutex<Option<u32>>,
+}
+
+impl BuildHandle {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        if let Some(pid) = *self.process_id.lock().unwrap() {
+            kill_process_tree(pid);
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Unregisters the build from the engine once it has finished.
+struct BuildGuard<'a> {
+    engine: &'a BuildEngine,
+    handle: &'a BuildHandle,
+}
+
+impl<'a> Drop for BuildGuard<'a> {
+    fn drop(&mut self) {
+        self.engine.unregister(self.handle);
+    }
+}
+
+/// Ensures that only one build is running at a time.
+struct BuildLock<'a> {
+    engine: &'a BuildEngine,
+}
+
+impl<'a> Drop for BuildLock<'a> {
+    fn drop(&mut self) {
+        *self.engine.busy.lock().unwrap() = false;
+        self.engine.changed.notify_all();
+    }
+}
+
 #[derive(Default)]
 pub struct BuildEngine {
-    lock: Mutex<()>,
+    busy: Mutex<bool>,
+    changed: Condvar,
+    handles_by_token: DashMap<String, Arc<BuildHandle>>,
+    continuous_builds: Mutex<FxHashMap<Arc<Url>, ContinuousBuild>>,
     pub positions_by_uri: DashMap<Arc<Url>, Position>,
 }
 
 impl BuildEngine {
+    /// Cancels the build that has been started by the request with the given ID.
+    pub fn cancel_request(&self, request_id: &RequestId) {
+        for handle in self.handles_by_token.iter() {
+            if handle.request_id.as_ref() == Some(request_id) {
+                handle.cancel();
+            }
+        }
+
+        self.changed.notify_all();
+    }
+
+    /// Cancels the build that reports its progress using the given token.
+    pub fn cancel_progress(&self, token: &NumberOrString) {
+        if let NumberOrString::String(token) = token {
+            if let Some(handle) = self.handles_by_token.get(token) {
+                handle.cancel();
+            }
+        }
+
+        self.changed.notify_all();
+    }
+
+    /// Waits until the previous build has finished or the given build has been cancelled.
+    fn acquire(&self, handle: &BuildHandle) -> Option<BuildLock<'_>> {
+        let mut busy = self.busy.lock().unwrap();
+        while *busy && !handle.is_cancelled() {
+            busy = self.changed.wait(busy).unwrap();
+        }
+
+        if handle.is_cancelled() {
+            return None;
+        }
+
+        *busy = true;
+        Some(BuildLock { engine: self })
+    }
+
+    /// Registers a new build, which can be cancelled from now on.
+    /// Registering the build before it is scheduled ensures that cancellation requests
+    /// cannot overtake the build.
+    pub fn register(&self, request_id: Option<RequestId>) -> Arc<BuildHandle> {
+        let handle = Arc::new(BuildHandle {
+            token: format!("texlab-build-{}", Uuid::new_v4()),
+            request_id,
+            cancelled: AtomicBool::new(false),
+            process_id: Mutex::new(None),
+        });
+
+        self.handles_by_token
+            .insert(handle.token.clone(), Arc::clone(&handle));
+
+        handle
+    }
+
+    /// Removes a handle, e.g. when the build has finished or never started.
+    pub fn unregister(&self, handle: &BuildHandle) {
+        self.handles_by_token.remove(&handle.token);
+    }
+
     pub fn build(
         &self,
         request: FeatureRequest<BuildParams>,
+        handle: &BuildHandle,
         req_queue: &Mutex<ReqQueue>,
         lsp_sender: &Sender<lsp_server::Message>,
     ) -> Result<BuildResult> {
-        let lock = self.lock.lock().unwrap();
+        let _guard = BuildGuard {
+            engine: self,
+            handle,
+        };
 
-        let document = request
-            .workspace
-            .documents_by_uri
-            .values()
-            .find(|document| {
-                if let Some(data) = document.data.as_latex() {
-                    data.extras.has_document_environment
-                } else {
-                    false
-                }
-            })
-            .unwrap_or_else(|| request.main_document());
+        let lock = match self.acquire(handle) {
+            Some(lock) => lock,
+            None => {
+                return Ok(BuildResult {
+                    status: BuildStatus::CANCELLED,
+                })
+            }
+        };
+
+        let document = find_root_document(&request);
 
         if document.data.language() != DocumentLanguage::Latex {
             return Ok(BuildResult {
@@ -147,48 +269,35 @@ impl BuildEngine {
             .client_capabilities
             .has_work_done_progress_support();
 
-        let token = format!("texlab-build-{}", Uuid::new_v4());
         let progress_reporter = ProgressReporter {
             supports_progress,
             req_queue,
             lsp_sender: lsp_sender.clone(),
-            token: &token,
+            token: &handle.token,
         };
         progress_reporter.start(&document.uri)?;
 
         let options = &request.workspace.environment.options;
+        let program = find_program(&request, document);
+        let mut process = build_command(options, &path, &[], program).spawn()?;
 
-        let build_dir = options
-            .root_directory
-            .as_ref()
-            .map(AsRef::as_ref)
-            .or_else(|| path.parent())
-            .unwrap();
-
-        let args: Vec<_> = options
-            .build
-            .args
-            .0
-            .iter()
-            .map(|arg| replace_placeholder(arg.clone(), &path))
-            .collect();
-
-        let mut process = Command::new(&options.build.executable.0)
-            .args(args)
-            .stdin(Stdio::null())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .current_dir(build_dir)
-            .spawn()?;
+        *handle.process_id.lock().unwrap() = Some(process.id());
+        if handle.is_cancelled() {
+            // The build has been cancelled before the process was started.
+            kill_process_tree(process.id());
+        }
 
         let (exit_sender, exit_receiver) = crossbeam_channel::bounded(1);
         let log_handle = capture_output(&mut process, lsp_sender, exit_receiver);
         let success = process.wait().map(|status| status.success())?;
+        *handle.process_id.lock().unwrap() = None;
         exit_sender.send(())?;
         drop(exit_sender);
 
         log_handle.join().unwrap();
-        let status = if success {
+        let status = if handle.is_cancelled() {
+            BuildStatus::CANCELLED
+        } else if success {
             BuildStatus::SUCCESS
         } else {
             BuildStatus::ERROR
@@ -197,7 +306,7 @@ impl BuildEngine {
         drop(progress_reporter);
         drop(lock);
 
-        if options.build.forward_search_after {
+        if options.build.forward_search_after && status != BuildStatus::CANCELLED {
             let request = FeatureRequest {
                 params: TextDocumentPositionParams {
                     position: self
@@ -215,6 +324,213 @@ impl BuildEngine {
 
         Ok(BuildResult { status })
     }
+
+    /// Starts a continuous build (`latexmk -pvc`) of the root document unless it is already running.
+    /// The given callback receives the log files after each pass.
+    pub fn start_continuous(
+        &self,
+        request: FeatureRequest<BuildParams>,
+        lsp_sender: &Sender<lsp_server::Message>,
+        on_pass: impl Fn(Vec<PathBuf>) + Send + 'static,
+    ) -> Result<ContinuousBuildStatus> {
+        let document = find_root_document(&request);
+        let data = match document.data.as_latex() {
+            Some(data) if document.uri.scheme() == "file" => data,
+            _ => {
+                return Ok(ContinuousBuildStatus::stopped(
+                    document.uri.as_ref().clone(),
+                ))
+            }
+        };
+
+        let mut builds = self.continuous_builds.lock().unwrap();
+        if let Some(build) = builds.get_mut(&document.uri) {
+            if build.is_running() {
+                return Ok(build.status(&document.uri));
+            }
+        }
+
+        let path = document.uri.to_file_path().unwrap();
+        let options = &request.workspace.environment.options;
+        let program = find_program(&request, document);
+        let executable = &options.build.executable.0;
+        let continuous_args = match &options.build.continuous_args {
+            Some(args) => args.0.clone(),
+            None if is_latexmk(executable) => ContinuousBuildArgs::default().0,
+            None => bail!(
+                "Continuous builds require latexmk; set texlab.build.continuousArgs to use {}",
+                executable
+            ),
+        };
+
+        let command = build_command(options, &path, &continuous_args, program);
+        let log_paths = data
+            .extras
+            .implicit_links
+            .log
+            .iter()
+            .filter_map(|uri| uri.to_file_path().ok())
+            .collect();
+
+        let mut build = ContinuousBuild::spawn(command, log_paths, lsp_sender, on_pass)?;
+        let status = build.status(&document.uri);
+        if let Some(old_build) = builds.insert(Arc::clone(&document.uri), build) {
+            old_build.stop(&document.uri);
+        }
+
+        Ok(status)
+    }
+
+    pub fn stop_continuous(&self, request: FeatureRequest<BuildParams>) -> ContinuousBuildStatus {
+        let uri = &find_root_document(&request).uri;
+        let build = self.continuous_builds.lock().unwrap().remove(uri);
+        match build {
+            Some(build) => build.stop(uri),
+            None => ContinuousBuildStatus::stopped(uri.as_ref().clone()),
+        }
+    }
+
+    pub fn continuous_status(&self, request: FeatureRequest<BuildParams>) -> ContinuousBuildStatus {
+        let uri = &find_root_document(&request).uri;
+        match self.continuous_builds.lock().unwrap().get_mut(uri) {
+            Some(build) => build.status(uri),
+            None => ContinuousBuildStatus::stopped(uri.as_ref().clone()),
+        }
+    }
+
+    pub fn stop_all_continuous(&self) {
+        let builds = std::mem::take(&mut *self.continuous_builds.lock().unwrap());
+        for (uri, build) in builds {
+            build.stop(&uri);
+        }
+    }
+}
+
+fn find_root_document<P>(request: &FeatureRequest<P>) -> &Document {
+    let workspace = &request.workspace;
+    workspace
+        .find_magic_root(&request.uri)
+        .or_else(|| {
+            workspace.documents_by_uri.values().find(|document| {
+                if let Some(data) = document.data.as_latex() {
+                    data.extras.has_document_environment
+                } else {
+                    false
+                }
+            })
+        })
+        .unwrap_or_else(|| request.main_document())
+}
+
+/// Finds the program that has been specified using `% !TEX program`
+/// either in the root document or in the requested document.
+fn find_program<'a, P>(
+    request: &'a FeatureRequest<P>,
+    root_document: &'a Document,
+) -> Option<&'a str> {
+    [root_document, request.main_document()]
+        .into_iter()
+        .filter_map(|document| document.data.as_latex())
+        .find_map(|data| data.extras.magic_comments.program.as_deref())
+}
+
+/// The engines that can be selected using `% !TEX program` and their `latexmk` flags.
+const LATEXMK_ENGINE_FLAGS: &[(&str, &str)] = &[
+    ("pdflatex", "-pdf"),
+    ("xelatex", "-xelatex"),
+    ("lualatex", "-lualatex"),
+    ("latex", "-dvi"),
+];
+
+/// The arguments that are passed to the engine if it replaces the configured build tool.
+const ENGINE_ARGS: &[&str] = &["-interaction=nonstopmode", "-synctex=1", "%f"];
+
+const LATEXMK_OUTPUT_FLAGS: &[&str] = &[
+    "-pdf",
+    "-pdflatex",
+    "-xelatex",
+    "-lualatex",
+    "-pdfxe",
+    "-pdflua",
+    "-pdfdvi",
+    "-pdfps",
+    "-dvi",
+    "-ps",
+];
+
+/// The working directory of the build tool, which is either the configured root directory
+/// or the directory of the root document.
+pub(super) fn build_directory<'a>(options: &'a Options, path: &'a Path) -> Option<&'a Path> {
+    options
+        .root_directory
+        .as_ref()
+        .map(AsRef::as_ref)
+        .or_else(|| path.parent())
+}
+
+fn is_latexmk(executable: &str) -> bool {
+    Path::new(executable)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        == Some("latexmk")
+}
+
+fn build_command(
+    options: &Options,
+    path: &Path,
+    extra_args: &[String],
+    program: Option<&str>,
+) -> Command {
+    let build_dir = build_directory(options, path).unwrap();
+
+    let mut executable = options.build.executable.0.as_str();
+    let mut args: Vec<_> = extra_args
+        .iter()
+        .chain(&options.build.args.0)
+        .map(|arg| replace_placeholder(arg.clone(), path))
+        .collect();
+
+    // The program comes from the document, so only well-known engines are accepted.
+    let engine = program.and_then(|program| {
+        let engine = LATEXMK_ENGINE_FLAGS
+            .iter()
+            .find(|(engine, _)| engine.eq_ignore_ascii_case(program));
+
+        if engine.is_none() {
+            warn!("Ignoring unsupported TeX program: {}", program);
+        }
+
+        engine
+    });
+
+    if let Some((engine, flag)) = engine {
+        if is_latexmk(executable) {
+            args.retain(|arg| !LATEXMK_OUTPUT_FLAGS.contains(&arg.as_str()));
+            args.insert(0, flag.to_string());
+        } else if options.build.allow_program_override {
+            // The arguments of the configured build tool are not meant for the engine.
+            executable = engine;
+            args = ENGINE_ARGS
+                .iter()
+                .map(|arg| replace_placeholder(arg.to_string(), path))
+                .collect();
+        } else {
+            warn!(
+                "Ignoring TeX program {} because texlab.build.allowProgramOverride is disabled",
+                engine
+            );
+        }
+    }
+
+    let mut command = Command::new(executable);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .current_dir(build_dir);
+
+    command
 }
 
 fn capture_output(
@@ -245,6 +561,47 @@ fn capture_output(
     })
 }
 
+/// Kills the given process including all of its descendants
+/// because build tools like `latexmk` run the TeX engine in a child process.
+#[cfg(windows)]
+fn kill_process_tree(pid: u32) {
+    let _ = Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Kills the given process including all of its descendants
+/// because build tools like `latexmk` run the TeX engine in a child process.
+#[cfg(not(windows))]
+fn kill_process_tree(pid: u32) {
+    let mut pids = vec![pid.to_string()];
+    let mut index = 0;
+    while index < pids.len() {
+        if let Ok(output) = Command::new("pgrep")
+            .args(["-P", &pids[index]])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+        {
+            let children = String::from_utf8_lossy(&output.stdout);
+            pids.extend(children.split_whitespace().map(ToString::to_string));
+        }
+
+        index += 1;
+    }
+
+    let _ = Command::new("kill")
+        .arg("-KILL")
+        .args(pids)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
 fn replace_placeholder(arg: String, file: &Path) -> String {
     if arg.starts_with('"') || arg.ends_with('"') {
         arg
@@ -268,3 +625,84 @@ fn track_output(output: impl Read + Send + 'static, sender: Sender<String>) -> J
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_args(executable: &str, args: &[&str], program: Option<&str>) -> Vec<String> {
+        let mut options = Options::default();
+        options.build.executable.0 = executable.to_string();
+        options.build.args.0 = args.iter().map(ToString::to_string).collect();
+        build_args_with_options(&options, program)
+    }
+
+    fn build_args_with_options(options: &Options, program: Option<&str>) -> Vec<String> {
+        let command = build_command(options, Path::new("/foo/main.tex"), &[], program);
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_latexmk_engine() {
+        assert_eq!(
+            build_args("latexmk", &["-pdf", "-synctex=1", "%f"], Some("xelatex")),
+            vec!["latexmk", "-xelatex", "-synctex=1", "/foo/main.tex"]
+        );
+    }
+
+    #[test]
+    fn test_latexmk_unknown_engine() {
+        assert_eq!(
+            build_args("latexmk", &["-pdf", "%f"], Some("foo")),
+            vec!["latexmk", "-pdf", "/foo/main.tex"]
+        );
+    }
+
+    #[test]
+    fn test_other_executable() {
+        assert_eq!(
+            build_args("pdflatex", &["%f"], Some("lualatex")),
+            vec!["pdflatex", "/foo/main.tex"]
+        );
+    }
+
+    #[test]
+    fn test_other_executable_override() {
+        let mut options = Options::default();
+        options.build.executable.0 = "tectonic".to_string();
+        options.build.args.0 = vec!["--synctex".to_string(), "%f".to_string()];
+        options.build.allow_program_override = true;
+        assert_eq!(
+            build_args_with_options(&options, Some("lualatex")),
+            vec![
+                "lualatex",
+                "-interaction=nonstopmode",
+                "-synctex=1",
+                "/foo/main.tex"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_other_executable_unknown_engine() {
+        let mut options = Options::default();
+        options.build.executable.0 = "pdflatex".to_string();
+        options.build.args.0 = vec!["%f".to_string()];
+        options.build.allow_program_override = true;
+        assert_eq!(
+            build_args_with_options(&options, Some("sh")),
+            vec!["pdflatex", "/foo/main.tex"]
+        );
+    }
+
+    #[test]
+    fn test_no_program() {
+        assert_eq!(
+            build_args("latexmk", &["-pdf", "%f"], None),
+            vec!["latexmk", "-pdf", "/foo/main.tex"]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    path::PathBuf,
+    process::{Child, Command},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use anyhow::Result;
+use crossbeam_channel::Sender;
+use lsp_types::{notification::LogMessage, LogMessageParams, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::client;
+
+use super::{kill_process_tree, track_output};
+
+/// The message that `latexmk -pvc` prints after each pass before it waits for changes.
+const PASS_FINISHED_MESSAGE: &str = "=== Watching for updated files.";
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinuousBuildStatus {
+    pub root_uri: Url,
+    pub running: bool,
+    pub passes: u32,
+}
+
+impl ContinuousBuildStatus {
+    #[must_use]
+    pub fn stopped(root_uri: Url) -> Self {
+        Self {
+            root_uri,
+            running: false,
+            passes: 0,
+        }
+    }
+}
+
+/// A long-running build process, which recompiles the root document whenever one of its
+/// dependencies changes.
+pub(super) struct ContinuousBuild {
+    process: Child,
+    passes: Arc<AtomicU32>,
+}
+
+impl ContinuousBuild {
+    pub fn spawn(
+        mut command: Command,
+        log_paths: Vec<PathBuf>,
+        lsp_sender: &Sender<lsp_server::Message>,
+        on_pass: impl Fn(Vec<PathBuf>) + Send + 'static,
+    ) -> Result<Self> {
+        let mut process = command.spawn()?;
+        let passes = Arc::new(AtomicU32::new(0));
+
+        let (log_sender, log_receiver) = crossbeam_channel::unbounded();
+        track_output(process.stdout.take().unwrap(), log_sender.clone());
+        track_output(process.stderr.take().unwrap(), log_sender);
+
+        let lsp_sender = lsp_sender.clone();
+        let pass_counter = Arc::clone(&passes);
+        thread::spawn(move || {
+            for message in log_receiver {
+                let pass_finished = message.starts_with(PASS_FINISHED_MESSAGE);
+                let _ = client::send_notification::<LogMessage>(
+                    &lsp_sender,
+                    LogMessageParams {
+                        message,
+                        typ: lsp_types::MessageType::LOG,
+                    },
+                );
+
+                if pass_finished {
+                    pass_counter.fetch_add(1, Ordering::SeqCst);
+                    on_pass(
+                        log_paths
+                            .iter()
+                            .filter(|path| path.exists())
+                            .cloned()
+                            .collect(),
+                    );
+                }
+            }
+        });
+
+        Ok(Self { process, passes })
+    }
+
+    pub fn is_running(&mut self) -> bool {
+        matches!(self.process.try_wait(), Ok(None))
+    }
+
+    pub fn status(&mut self, root_uri: &Url) -> ContinuousBuildStatus {
+        ContinuousBuildStatus {
+            root_uri: root_uri.clone(),
+            running: self.is_running(),
+            passes: self.passes.load(Ordering::SeqCst),
+        }
+    }
+
+    pub fn stop(mut self, root_uri: &Url) -> ContinuousBuildStatus {
+        if self.is_running() {
+            kill_process_tree(self.process.id());
+        }
+
+        let _ = self.process.wait();
+        self.status(root_uri)
+    }
+}
@@ -0,0 +1,72 @@
+mod bibtex;
+mod latex;
+
+use std::collections::HashMap;
+
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, Diagnostic, TextEdit,
+    WorkspaceEdit,
+};
+
+use crate::diagnostics::DiagnosticCode;
+
+use self::{bibtex::find_bibtex_quick_fix, latex::find_latex_quick_fix};
+
+use super::FeatureRequest;
+
+pub fn find_code_actions(request: FeatureRequest<CodeActionParams>) -> Vec<CodeActionOrCommand> {
+    let mut actions = Vec::new();
+    if let Some(only) = &request.params.context.only {
+        if !only
+            .iter()
+            .any(|kind| CodeActionKind::QUICKFIX.as_str().starts_with(kind.as_str()))
+        {
+            return actions;
+        }
+    }
+
+    for diagnostic in &request.params.context.diagnostics {
+        let quick_fix = match DiagnosticCode::from_lsp(diagnostic) {
+            Some(DiagnosticCode::Latex(code)) => find_latex_quick_fix(&request, diagnostic, code),
+            Some(DiagnosticCode::Bibtex(code)) => find_bibtex_quick_fix(&request, diagnostic, code),
+            Some(DiagnosticCode::Chktex(_) | DiagnosticCode::Build(_)) | None => None,
+        };
+
+        if let Some(quick_fix) = quick_fix {
+            actions.push(CodeActionOrCommand::CodeAction(
+                quick_fix.into_code_action(&request, diagnostic),
+            ));
+        }
+    }
+
+    actions
+}
+
+/// A fix for a single diagnostic of the main document.
+/// Every diagnostic code can provide at most one quick fix,
+/// which is looked up by the `find_*_quick_fix` function of its language.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct QuickFix {
+    title: String,
+    edits: Vec<TextEdit>,
+}
+
+impl QuickFix {
+    fn into_code_action<P>(
+        self,
+        request: &FeatureRequest<P>,
+        diagnostic: &Diagnostic,
+    ) -> CodeAction {
+        let mut changes = HashMap::new();
+        changes.insert(request.uri.as_ref().clone(), self.edits);
+
+        CodeAction {
+            title: self.title,
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(WorkspaceEdit::new(changes)),
+            is_preferred: Some(true),
+            ..CodeAction::default()
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use lsp_types::{CodeActionParams, Diagnostic, Range, TextEdit};
+
+use crate::{diagnostics::BibtexCode, features::FeatureRequest};
+
+use super::QuickFix;
+
+pub(super) fn find_bibtex_quick_fix(
+    _request: &FeatureRequest<CodeActionParams>,
+    diagnostic: &Diagnostic,
+    code: BibtexCode,
+) -> Option<QuickFix> {
+    match code {
+        BibtexCode::ExpectingEq => Some(insert_eq(diagnostic)),
+        BibtexCode::ExpectingRCurly => Some(insert_r_curly(diagnostic)),
+        BibtexCode::ExpectingLCurly
+        | BibtexCode::ExpectingKey
+        | BibtexCode::ExpectingFieldValue
+        | BibtexCode::UnusedEntry
+        | BibtexCode::DuplicateEntry => None,
+    }
+}
+
+fn insert_eq(diagnostic: &Diagnostic) -> QuickFix {
+    // The diagnostic points to the name of the field.
+    let position = diagnostic.range.end;
+    QuickFix {
+        title: "Insert missing \"=\"".into(),
+        edits: vec![TextEdit::new(Range::new(position, position), " =".into())],
+    }
+}
+
+fn insert_r_curly(diagnostic: &Diagnostic) -> QuickFix {
+    let position = diagnostic.range.start;
+    QuickFix {
+        title: "Insert missing \"}\"".into(),
+        edits: vec![TextEdit::new(Range::new(position, position), "}".into())],
+    }
+}
@@ -0,0 +1,64 @@
+use lsp_types::{CodeActionParams, Diagnostic, Range, TextEdit};
+use rowan::ast::AstNode;
+
+use crate::{diagnostics::LatexCode, features::FeatureRequest, syntax::latex, LineIndexExt};
+
+use super::QuickFix;
+
+pub(super) fn find_latex_quick_fix(
+    request: &FeatureRequest<CodeActionParams>,
+    diagnostic: &Diagnostic,
+    code: LatexCode,
+) -> Option<QuickFix> {
+    match code {
+        LatexCode::RCurlyInserted => Some(insert_r_curly(diagnostic)),
+        LatexCode::MismatchedEnvironment => rename_environment_end(request, diagnostic),
+        LatexCode::UnexpectedRCurly
+        | LatexCode::UndefinedLabel
+        | LatexCode::UndefinedCitation
+        | LatexCode::DuplicateLabel => None,
+    }
+}
+
+fn insert_r_curly(diagnostic: &Diagnostic) -> QuickFix {
+    let position = diagnostic.range.start;
+    QuickFix {
+        title: "Insert missing \"}\"".into(),
+        edits: vec![TextEdit::new(Range::new(position, position), "}".into())],
+    }
+}
+
+fn rename_environment_end(
+    request: &FeatureRequest<CodeActionParams>,
+    diagnostic: &Diagnostic,
+) -> Option<QuickFix> {
+    let document = request.main_document();
+    let data = document.data.as_latex()?;
+    let range = document.line_index.offset_lsp_range(diagnostic.range);
+
+    let environment = latex::SyntaxNode::new_root(data.green.clone())
+        .descendants()
+        .filter_map(latex::Environment::cast)
+        .find(|environment| {
+            environment
+                .begin()
+                .and_then(|begin| begin.name())
+                .and_then(|name| name.key())
+                .map_or(false, |name| latex::small_range(&name) == range)
+        })?;
+
+    let begin_name = environment.begin()?.name()?.key()?.to_string();
+    let end_name = environment.end()?.name()?.key()?;
+    let end_range = document
+        .line_index
+        .line_col_lsp_range(latex::small_range(&end_name));
+
+    Some(QuickFix {
+        title: format!(
+            "Change \\end{{{}}} to \\end{{{}}}",
+            end_name.to_string(),
+            begin_name
+        ),
+        edits: vec![TextEdit::new(end_range, begin_name)],
+    })
+}
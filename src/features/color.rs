@@ -0,0 +1,360 @@
+use lsp_types::{
+    Color, ColorInformation, ColorPresentation, ColorPresentationParams, DocumentColorParams,
+    TextEdit,
+};
+use rowan::{ast::AstNode, TextRange};
+
+use crate::{
+    syntax::latex::{self, HasCurly},
+    LineIndexExt, Workspace,
+};
+
+use super::FeatureRequest;
+
+pub fn find_document_colors(request: FeatureRequest<DocumentColorParams>) -> Vec<ColorInformation> {
+    let mut results = Vec::new();
+    let document = request.main_document();
+    let data = match document.data.as_latex() {
+        Some(data) => data,
+        None => return results,
+    };
+
+    for node in latex::SyntaxNode::new_root(data.green.clone()).descendants() {
+        let color = if let Some(definition) = latex::ColorDefinition::cast(node.clone()) {
+            find_definition_color(&definition)
+        } else if let Some(reference) = latex::ColorReference::cast(node) {
+            reference
+                .name()
+                .and_then(|name| name.key())
+                .and_then(|key| {
+                    let color = resolve_color(&request.workspace, &key.to_string())?;
+                    Some((latex::small_range(&key), color))
+                })
+        } else {
+            None
+        };
+
+        if let Some((range, [red, green, blue])) = color {
+            results.push(ColorInformation {
+                range: document.line_index.line_col_lsp_range(range),
+                color: Color {
+                    red,
+                    green,
+                    blue,
+                    alpha: 1.0,
+                },
+            });
+        }
+    }
+
+    results
+}
+
+pub fn find_color_presentations(
+    request: FeatureRequest<ColorPresentationParams>,
+) -> Vec<ColorPresentation> {
+    let mut results = Vec::new();
+    let document = request.main_document();
+    let data = match document.data.as_latex() {
+        Some(data) => data,
+        None => return results,
+    };
+
+    let range = document.line_index.offset_lsp_range(request.params.range);
+    let color = &request.params.color;
+    let rgb = [color.red, color.green, color.blue];
+
+    let model = latex::SyntaxNode::new_root(data.green.clone())
+        .descendants()
+        .filter_map(latex::ColorDefinition::cast)
+        .filter(|definition| spec_range(definition) == Some(range))
+        .find_map(|definition| Some(definition.model()?.key()?.to_string()));
+
+    if let Some(new_text) = model.and_then(|model| format_color(&model, rgb)) {
+        results.push(ColorPresentation {
+            label: new_text.clone(),
+            text_edit: Some(TextEdit::new(request.params.range, new_text)),
+            additional_text_edits: None,
+        });
+    }
+
+    results
+}
+
+type Rgb = [f32; 3];
+
+fn find_definition_color(definition: &latex::ColorDefinition) -> Option<(TextRange, Rgb)> {
+    let model = definition.model()?.key()?.to_string();
+    let spec = definition.spec()?.content_text()?;
+    Some((spec_range(definition)?, parse_color(&model, &spec)?))
+}
+
+/// Computes the range between the curly braces of the color specification.
+fn spec_range(definition: &latex::ColorDefinition) -> Option<TextRange> {
+    let spec = definition.spec()?;
+    Some(TextRange::new(
+        spec.left_curly()?.text_range().end(),
+        spec.right_curly()?.text_range().start(),
+    ))
+}
+
+/// Resolves a color expression like `red!30!blue`
+/// using the colors that are defined in the workspace or by `xcolor`.
+fn resolve_color(workspace: &Workspace, expression: &str) -> Option<Rgb> {
+    let mut parts = expression.split('!').map(str::trim);
+    let mut color = resolve_color_name(workspace, parts.next()?)?;
+    while let Some(percentage) = parts.next() {
+        let percentage = percentage.parse::<f32>().ok()?.clamp(0.0, 100.0) / 100.0;
+        let other = match parts.next() {
+            Some(name) => resolve_color_name(workspace, name)?,
+            None => [1.0, 1.0, 1.0],
+        };
+
+        for (value, other) in color.iter_mut().zip(other) {
+            *value = percentage * *value + (1.0 - percentage) * other;
+        }
+    }
+
+    Some(color)
+}
+
+fn resolve_color_name(workspace: &Workspace, name: &str) -> Option<Rgb> {
+    workspace
+        .documents_by_uri
+        .values()
+        .filter_map(|document| document.data.as_latex())
+        .find_map(|data| {
+            latex::SyntaxNode::new_root(data.green.clone())
+                .descendants()
+                .filter_map(latex::ColorDefinition::cast)
+                .filter(|definition| {
+                    definition
+                        .name()
+                        .and_then(|group| group.key())
+                        .map_or(false, |key| key.to_string() == name)
+                })
+                .find_map(|definition| find_definition_color(&definition))
+        })
+        .map(|(_, color)| color)
+        .or_else(|| {
+            BASE_COLORS
+                .iter()
+                .find(|(base_name, _)| *base_name == name)
+                .map(|(_, color)| *color)
+        })
+        .or_else(|| {
+            DVIPS_COLORS
+                .iter()
+                .find(|(dvips_name, _)| *dvips_name == name)
+                .map(|(_, [c, m, y, k])| cmyk_to_rgb(*c, *m, *y, *k))
+        })
+}
+
+/// Parses the color specification of `\definecolor` in one of the supported color models.
+fn parse_color(model: &str, spec: &str) -> Option<Rgb> {
+    let values = || {
+        spec.split(',')
+            .map(|value| value.trim().parse::<f32>().ok())
+    };
+
+    match model {
+        "rgb" => {
+            let values = values().collect::<Option<Vec<_>>>()?;
+            match values.as_slice() {
+                [r, g, b] => Some([*r, *g, *b].map(|value| value.clamp(0.0, 1.0))),
+                _ => None,
+            }
+        }
+        "RGB" => {
+            let values = values().collect::<Option<Vec<_>>>()?;
+            match values.as_slice() {
+                [r, g, b] => Some([*r, *g, *b].map(|value| (value / 255.0).clamp(0.0, 1.0))),
+                _ => None,
+            }
+        }
+        "HTML" => {
+            let spec = spec.trim();
+            if spec.len() != 6 {
+                return None;
+            }
+
+            let component = |i: usize| {
+                let value = u8::from_str_radix(spec.get(i..i + 2)?, 16).ok()?;
+                Some(f32::from(value) / 255.0)
+            };
+
+            Some([component(0)?, component(2)?, component(4)?])
+        }
+        "cmyk" => {
+            let values = values().collect::<Option<Vec<_>>>()?;
+            match values.as_slice() {
+                [c, m, y, k] => Some(cmyk_to_rgb(*c, *m, *y, *k)),
+                _ => None,
+            }
+        }
+        "gray" => {
+            let value = spec.trim().parse::<f32>().ok()?.clamp(0.0, 1.0);
+            Some([value, value, value])
+        }
+        _ => None,
+    }
+}
+
+/// Formats a color so that it can be used as the specification of `\definecolor`.
+fn format_color(model: &str, [r, g, b]: Rgb) -> Option<String> {
+    let text = match model {
+        "rgb" => [r, g, b].map(format_number).join(","),
+        "RGB" => [r, g, b]
+            .map(|value| ((value * 255.0).round() as u8).to_string())
+            .join(","),
+        "HTML" => [r, g, b]
+            .map(|value| format!("{:02X}", (value * 255.0).round() as u8))
+            .join(""),
+        "cmyk" => {
+            let k = 1.0 - r.max(g).max(b);
+            let [c, m, y] = if k < 1.0 {
+                [r, g, b].map(|value| (1.0 - value - k) / (1.0 - k))
+            } else {
+                [0.0, 0.0, 0.0]
+            };
+
+            [c, m, y, k].map(format_number).join(",")
+        }
+        "gray" => format_number(0.3 * r + 0.59 * g + 0.11 * b),
+        _ => return None,
+    };
+
+    Some(text)
+}
+
+fn format_number(value: f32) -> String {
+    let text = format!("{:.3}", value.clamp(0.0, 1.0));
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    text.to_string()
+}
+
+/// Converts a color from the CMYK model like `xcolor` does.
+fn cmyk_to_rgb(c: f32, m: f32, y: f32, k: f32) -> Rgb {
+    [c, m, y].map(|value| 1.0 - (value + k).min(1.0))
+}
+
+/// The colors that are always provided by `xcolor`.
+static BASE_COLORS: &[(&str, Rgb)] = &[
+    ("red", [1.0, 0.0, 0.0]),
+    ("green", [0.0, 1.0, 0.0]),
+    ("blue", [0.0, 0.0, 1.0]),
+    ("brown", [0.75, 0.5, 0.25]),
+    ("lime", [0.75, 1.0, 0.0]),
+    ("orange", [1.0, 0.5, 0.0]),
+    ("pink", [1.0, 0.75, 0.75]),
+    ("purple", [0.75, 0.0, 0.25]),
+    ("teal", [0.0, 0.5, 0.5]),
+    ("violet", [0.5, 0.0, 0.5]),
+    ("cyan", [0.0, 1.0, 1.0]),
+    ("magenta", [1.0, 0.0, 1.0]),
+    ("yellow", [1.0, 1.0, 0.0]),
+    ("olive", [0.5, 0.5, 0.0]),
+    ("black", [0.0, 0.0, 0.0]),
+    ("darkgray", [0.25, 0.25, 0.25]),
+    ("gray", [0.5, 0.5, 0.5]),
+    ("lightgray", [0.75, 0.75, 0.75]),
+    ("white", [1.0, 1.0, 1.0]),
+];
+
+/// The colors of the `dvipsnames` option of `xcolor` in the CMYK model.
+static DVIPS_COLORS: &[(&str, [f32; 4])] = &[
+    ("GreenYellow", [0.15, 0.0, 0.69, 0.0]),
+    ("Yellow", [0.0, 0.0, 1.0, 0.0]),
+    ("Goldenrod", [0.0, 0.10, 0.84, 0.0]),
+    ("Dandelion", [0.0, 0.29, 0.84, 0.0]),
+    ("Apricot", [0.0, 0.32, 0.52, 0.0]),
+    ("Peach", [0.0, 0.50, 0.70, 0.0]),
+    ("Melon", [0.0, 0.46, 0.50, 0.0]),
+    ("YellowOrange", [0.0, 0.42, 1.0, 0.0]),
+    ("Orange", [0.0, 0.61, 0.87, 0.0]),
+    ("BurntOrange", [0.0, 0.51, 1.0, 0.0]),
+    ("Bittersweet", [0.0, 0.75, 1.0, 0.24]),
+    ("RedOrange", [0.0, 0.77, 0.87, 0.0]),
+    ("Mahogany", [0.0, 0.85, 0.87, 0.35]),
+    ("Maroon", [0.0, 0.87, 0.68, 0.32]),
+    ("BrickRed", [0.0, 0.89, 0.94, 0.28]),
+    ("Red", [0.0, 1.0, 1.0, 0.0]),
+    ("OrangeRed", [0.0, 1.0, 0.50, 0.0]),
+    ("RubineRed", [0.0, 1.0, 0.13, 0.0]),
+    ("WildStrawberry", [0.0, 0.96, 0.39, 0.0]),
+    ("Salmon", [0.0, 0.53, 0.38, 0.0]),
+    ("CarnationPink", [0.0, 0.63, 0.0, 0.0]),
+    ("Magenta", [0.0, 1.0, 0.0, 0.0]),
+    ("VioletRed", [0.0, 0.81, 0.0, 0.0]),
+    ("Rhodamine", [0.0, 0.82, 0.0, 0.0]),
+    ("Mulberry", [0.34, 0.90, 0.0, 0.02]),
+    ("RedViolet", [0.07, 0.90, 0.0, 0.34]),
+    ("Fuchsia", [0.47, 0.91, 0.0, 0.08]),
+    ("Lavender", [0.0, 0.48, 0.0, 0.0]),
+    ("Thistle", [0.12, 0.59, 0.0, 0.0]),
+    ("Orchid", [0.32, 0.64, 0.0, 0.0]),
+    ("DarkOrchid", [0.40, 0.80, 0.20, 0.0]),
+    ("Purple", [0.45, 0.86, 0.0, 0.0]),
+    ("Plum", [0.50, 1.0, 0.0, 0.0]),
+    ("Violet", [0.79, 0.88, 0.0, 0.0]),
+    ("RoyalPurple", [0.75, 0.90, 0.0, 0.0]),
+    ("BlueViolet", [0.86, 0.91, 0.0, 0.04]),
+    ("Periwinkle", [0.57, 0.55, 0.0, 0.0]),
+    ("CadetBlue", [0.62, 0.57, 0.23, 0.0]),
+    ("CornflowerBlue", [0.65, 0.13, 0.0, 0.0]),
+    ("MidnightBlue", [0.98, 0.13, 0.0, 0.43]),
+    ("NavyBlue", [0.94, 0.54, 0.0, 0.0]),
+    ("RoyalBlue", [1.0, 0.50, 0.0, 0.0]),
+    ("Blue", [1.0, 1.0, 0.0, 0.0]),
+    ("Cerulean", [0.94, 0.11, 0.0, 0.0]),
+    ("Cyan", [1.0, 0.0, 0.0, 0.0]),
+    ("ProcessBlue", [0.96, 0.0, 0.0, 0.0]),
+    ("SkyBlue", [0.62, 0.0, 0.12, 0.0]),
+    ("Turquoise", [0.85, 0.0, 0.20, 0.0]),
+    ("TealBlue", [0.86, 0.0, 0.34, 0.02]),
+    ("Aquamarine", [0.82, 0.0, 0.30, 0.0]),
+    ("BlueGreen", [0.85, 0.0, 0.33, 0.0]),
+    ("Emerald", [1.0, 0.0, 0.50, 0.0]),
+    ("JungleGreen", [0.99, 0.0, 0.52, 0.0]),
+    ("SeaGreen", [0.69, 0.0, 0.50, 0.0]),
+    ("Green", [1.0, 0.0, 1.0, 0.0]),
+    ("ForestGreen", [0.91, 0.0, 0.88, 0.12]),
+    ("PineGreen", [0.92, 0.0, 0.59, 0.25]),
+    ("LimeGreen", [0.50, 0.0, 1.0, 0.0]),
+    ("YellowGreen", [0.44, 0.0, 0.74, 0.0]),
+    ("SpringGreen", [0.26, 0.0, 0.76, 0.0]),
+    ("OliveGreen", [0.64, 0.0, 0.95, 0.40]),
+    ("RawSienna", [0.0, 0.72, 1.0, 0.45]),
+    ("Sepia", [0.0, 0.83, 1.0, 0.70]),
+    ("Brown", [0.0, 0.81, 1.0, 0.60]),
+    ("Tan", [0.14, 0.42, 0.56, 0.0]),
+    ("Gray", [0.0, 0.0, 0.0, 0.50]),
+    ("Black", [0.0, 0.0, 0.0, 1.0]),
+    ("White", [0.0, 0.0, 0.0, 0.0]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_models() {
+        assert_eq!(parse_color("rgb", "1, 0.5, 0"), Some([1.0, 0.5, 0.0]));
+        assert_eq!(parse_color("RGB", "255,0,51"), Some([1.0, 0.0, 0.2]));
+        assert_eq!(parse_color("HTML", "FF0033"), Some([1.0, 0.0, 0.2]));
+        assert_eq!(parse_color("cmyk", "0,1,1,0.5"), Some([0.5, 0.0, 0.0]));
+        assert_eq!(parse_color("gray", "0.25"), Some([0.25, 0.25, 0.25]));
+        assert_eq!(parse_color("rgb", "1,0"), None);
+        assert_eq!(parse_color("hsb", "1,1,1"), None);
+    }
+
+    #[test]
+    fn test_format_models() {
+        let color = [1.0, 0.5, 0.0];
+        assert_eq!(format_color("rgb", color).as_deref(), Some("1,0.5,0"));
+        assert_eq!(format_color("RGB", color).as_deref(), Some("255,128,0"));
+        assert_eq!(format_color("HTML", color).as_deref(), Some("FF8000"));
+        assert_eq!(format_color("cmyk", color).as_deref(), Some("0,0.5,1,0"));
+        assert_eq!(format_color("gray", color).as_deref(), Some("0.595"));
+    }
+}
@@ -1,6 +1,7 @@
 use lsp_types::{
-    CompletionParams, DocumentHighlightParams, GotoDefinitionParams, HoverParams, Position,
-    ReferenceParams, RenameParams, TextDocumentPositionParams,
+    CompletionParams, DocumentHighlightParams, GotoDefinitionParams, HoverParams,
+    LinkedEditingRangeParams, Position, ReferenceParams, RenameParams, SignatureHelpParams,
+    TextDocumentPositionParams,
 };
 use rowan::{ast::AstNode, TextRange, TextSize};
 
@@ -331,3 +332,15 @@ impl HasPosition for DocumentHighlightParams {
         self.text_document_position_params.position
     }
 }
+
+impl HasPosition for SignatureHelpParams {
+    fn position(&self) -> Position {
+        self.text_document_position_params.position
+    }
+}
+
+impl HasPosition for LinkedEditingRangeParams {
+    fn position(&self) -> Position {
+        self.text_document_position_params.position
+    }
+}
@@ -53,5 +53,6 @@ fn create_range(range: Range) -> FoldingRange {
         end_line: range.end.line,
         end_character: Some(range.end.character),
         kind: Some(FoldingRangeKind::Region),
+        collapsed_text: None,
     }
 }
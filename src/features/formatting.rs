@@ -1,26 +1,182 @@
 mod bibtex_internal;
+mod latex_internal;
 mod latexindent;
 
-use lsp_types::{DocumentFormattingParams, TextEdit};
+use lsp_types::{
+    DocumentFormattingParams, DocumentOnTypeFormattingParams, DocumentRangeFormattingParams,
+    FormattingOptions, Range, TextEdit,
+};
+use rowan::{TextRange, TextSize};
+use similar::{DiffTag, TextDiff};
 
-use crate::{BibtexFormatter, LatexFormatter};
+use crate::{BibtexFormatter, LatexFormatter, LineIndex, LineIndexExt};
 
-use self::{bibtex_internal::format_bibtex_internal, latexindent::format_with_latexindent};
+use self::{
+    bibtex_internal::format_bibtex_internal,
+    latex_internal::{format_latex_internal, indent_latex_line},
+    latexindent::format_with_latexindent,
+};
 
 use super::FeatureRequest;
 
 pub fn format_source_code(
     request: FeatureRequest<DocumentFormattingParams>,
+) -> Option<Vec<TextEdit>> {
+    format_document(&request, &request.params.options, None)
+}
+
+pub fn format_source_code_range(
+    request: FeatureRequest<DocumentRangeFormattingParams>,
+) -> Option<Vec<TextEdit>> {
+    format_document(
+        &request,
+        &request.params.options,
+        Some(request.params.range),
+    )
+}
+
+pub fn format_on_type(
+    request: FeatureRequest<DocumentOnTypeFormattingParams>,
+) -> Option<Vec<TextEdit>> {
+    if request.workspace.environment.options.latex_formatter != LatexFormatter::Texlab {
+        return None;
+    }
+
+    indent_latex_line(&request)
+}
+
+fn format_document<P>(
+    request: &FeatureRequest<P>,
+    options: &FormattingOptions,
+    range: Option<Range>,
 ) -> Option<Vec<TextEdit>> {
     let mut edits = None;
     if request.workspace.environment.options.bibtex_formatter == BibtexFormatter::Texlab {
-        edits = edits.or_else(|| format_bibtex_internal(&request));
+        edits = edits.or_else(|| format_bibtex_internal(request, options, range));
     }
 
     if request.workspace.environment.options.latex_formatter == LatexFormatter::Texlab {
-        edits = edits.or_else(|| Some(vec![]));
+        edits = edits.or_else(|| format_latex_internal(request, options, range));
+    }
+
+    edits = edits.or_else(|| format_with_latexindent(request, range));
+    edits
+}
+
+fn formatting_indent(options: &FormattingOptions) -> String {
+    let mut indent = String::new();
+    if options.insert_spaces {
+        for _ in 0..options.tab_size {
+            indent.push(' ');
+        }
+    } else {
+        indent.push('\t');
+    }
+
+    indent
+}
+
+fn formatting_line_length<P>(request: &FeatureRequest<P>) -> usize {
+    request
+        .workspace
+        .environment
+        .options
+        .formatter_line_length
+        .map(|value| {
+            if value <= 0 {
+                usize::MAX
+            } else {
+                value as usize
+            }
+        })
+        .unwrap_or(80)
+}
+
+/// Checks if a part of the document should be formatted when only the given range is requested.
+/// An empty range selects the part of the document that contains it.
+fn is_in_range(node_range: TextRange, range: Option<TextRange>) -> bool {
+    match range {
+        None => true,
+        Some(range) if range.is_empty() || node_range.is_empty() => {
+            node_range.contains_inclusive(range.start()) || range.contains(node_range.start())
+        }
+        Some(range) => node_range.start() < range.end() && range.start() < node_range.end(),
+    }
+}
+
+/// Computes the edits that turn the old text into the new text.
+/// Only the changed lines are replaced so that clients can keep their cursors and folds.
+fn diff_lines(line_index: &LineIndex, old_text: &str, new_text: &str) -> Vec<TextEdit> {
+    let diff = TextDiff::from_lines(old_text, new_text);
+
+    let mut offsets = vec![TextSize::from(0)];
+    for line in diff.old_slices() {
+        offsets.push(*offsets.last().unwrap() + TextSize::of(*line));
+    }
+
+    let mut edits = Vec::new();
+    let mut changes: Vec<(std::ops::Range<usize>, std::ops::Range<usize>)> = Vec::new();
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            continue;
+        }
+
+        match changes.last_mut() {
+            Some((old, new)) if old_range.start == old.end => {
+                *old = old.start..old_range.end;
+                *new = new.start..new_range.end;
+            }
+            _ => changes.push((old_range, new_range)),
+        };
+    }
+
+    for (old_range, new_range) in changes {
+        let range = TextRange::new(offsets[old_range.start], offsets[old_range.end]);
+        edits.push(TextEdit {
+            range: line_index.line_col_lsp_range(range),
+            new_text: diff.new_slices()[new_range].concat(),
+        });
     }
 
-    edits = edits.or_else(|| format_with_latexindent(&request));
     edits
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(old_text: &str, new_text: &str) -> (String, usize) {
+        let line_index = LineIndex::new(old_text);
+        let edits = diff_lines(&line_index, old_text, new_text);
+        let mut text = old_text.to_string();
+        for edit in edits.iter().rev() {
+            let range = line_index.offset_lsp_range(edit.range);
+            text.replace_range::<std::ops::Range<usize>>(range.into(), &edit.new_text);
+        }
+
+        (text, edits.len())
+    }
+
+    #[test]
+    fn test_unchanged() {
+        assert_eq!(
+            apply("foo\nbar\n", "foo\nbar\n"),
+            ("foo\nbar\n".to_string(), 0)
+        );
+    }
+
+    #[test]
+    fn test_separate_changes() {
+        let old_text = "a\nb\nc\nd\ne\n";
+        let new_text = "a\n  b\nc\nd\n  e\n  f\n";
+        assert_eq!(apply(old_text, new_text), (new_text.to_string(), 2));
+    }
+
+    #[test]
+    fn test_missing_line_break() {
+        let old_text = "a\nb";
+        let new_text = "a\nb\n";
+        assert_eq!(apply(old_text, new_text), (new_text.to_string(), 1));
+    }
+}
@@ -1,93 +1,226 @@
-use lsp_types::{DocumentFormattingParams, TextEdit};
-use rowan::{ast::AstNode, NodeOrToken};
+use lsp_types::{FormattingOptions, Range, TextEdit};
+use rowan::{ast::AstNode, NodeOrToken, TextRange, TextSize};
 
 use crate::{
     features::FeatureRequest,
     syntax::bibtex::{self, HasName, HasType, HasValue},
-    LineIndex, LineIndexExt,
+    BibtexDelimiters, BibtexEntrySorting, BibtexFormattingOptions, LineIndex, LineIndexExt,
 };
 
-pub fn format_bibtex_internal(
-    request: &FeatureRequest<DocumentFormattingParams>,
-) -> Option<Vec<TextEdit>> {
-    let mut indent = String::new();
-    if request.params.options.insert_spaces {
-        for _ in 0..request.params.options.tab_size {
-            indent.push(' ');
-        }
-    } else {
-        indent.push('\t');
-    }
+use super::{diff_lines, formatting_indent, formatting_line_length, is_in_range};
 
-    let line_length = request
-        .workspace
-        .environment
-        .options
-        .formatter_line_length
-        .map(|value| {
-            if value <= 0 {
-                usize::MAX
-            } else {
-                value as usize
-            }
-        })
-        .unwrap_or(80);
+pub fn format_bibtex_internal<P>(
+    request: &FeatureRequest<P>,
+    options: &FormattingOptions,
+    range: Option<Range>,
+) -> Option<Vec<TextEdit>> {
+    let indent = formatting_indent(options);
+    let line_length = formatting_line_length(request);
+    let style = &request.workspace.environment.options.bibtex_formatting;
 
     let document = request.main_document();
     let data = document.data.as_bibtex()?;
-    let mut edits = Vec::new();
+    let range = range.map(|range| document.line_index.offset_lsp_range(range));
+    let mut new_text = String::new();
 
-    for node in bibtex::SyntaxNode::new_root(data.green.clone())
+    let nodes: Vec<_> = bibtex::SyntaxNode::new_root(data.green.clone())
         .children()
         .filter(|node| {
             matches!(
                 node.kind(),
                 bibtex::PREAMBLE | bibtex::STRING | bibtex::ENTRY
-            )
+            ) && is_in_range(node.text_range(), range)
         })
-    {
-        let range = node.text_range();
+        .collect();
+
+    let mut last_end = TextSize::from(0);
+    let mut gaps = Vec::new();
+    for node in &nodes {
+        let gap = &document.text[TextRange::new(last_end, node.text_range().start())];
+        gaps.push(split_gap(gap, last_end == TextSize::from(0)));
+        last_end = node.text_range().end();
+    }
+
+    let mut order: Vec<usize> = (0..nodes.len()).collect();
+    if range.is_none() {
+        sort_entries(&nodes, &mut order, style.sort_entries);
+    }
+
+    for (i, &j) in order.iter().enumerate() {
+        let gap = format!("{}{}", gaps[i].0, gaps[j].1);
+        match style.blank_lines_between_entries {
+            Some(count) if i > 0 && gap.trim().is_empty() => {
+                for _ in 0..=count {
+                    new_text.push('\n');
+                }
+            }
+            _ => new_text.push_str(&gap),
+        };
 
         let mut formatter = Formatter::new(
             indent.clone(),
-            request.params.options.tab_size,
+            options.tab_size,
             line_length,
+            style,
             &document.line_index,
         );
 
-        formatter.visit_node(node);
-        edits.push(TextEdit {
-            range: document.line_index.line_col_lsp_range(range),
-            new_text: formatter.output,
-        });
+        formatter.visit_node(nodes[j].clone());
+        new_text.push_str(&formatter.output);
     }
 
-    Some(edits)
+    new_text.push_str(&document.text[usize::from(last_end)..]);
+    Some(diff_lines(&document.line_index, &document.text, &new_text))
+}
+
+/// Splits the text in front of a node into the part that stays in place
+/// and the lines directly above the node (e.g. comments), which move together with it.
+/// The rest of the line of the previous node and everything up to the last blank line stay in place.
+fn split_gap(gap: &str, is_start: bool) -> (&str, &str) {
+    let mut split = 0;
+    let mut offset = 0;
+    for (i, line) in gap.split_inclusive('\n').enumerate() {
+        offset += line.len();
+        if (i == 0 && !is_start) || (line.ends_with('\n') && line.trim().is_empty()) {
+            split = offset;
+        }
+    }
+
+    gap.split_at(split)
+}
+
+/// Sorts the indices of the entries between `@string` and `@preamble` definitions
+/// so that strings are still defined before they are used.
+fn sort_entries(nodes: &[bibtex::SyntaxNode], order: &mut [usize], sorting: BibtexEntrySorting) {
+    for group in order.split_mut(|&i| nodes[i].kind() != bibtex::ENTRY) {
+        match sorting {
+            BibtexEntrySorting::None => {}
+            BibtexEntrySorting::Key => group.sort_by_cached_key(|&i| {
+                bibtex::Entry::cast(nodes[i].clone())
+                    .and_then(|entry| entry.name_token())
+                    .map(|name| name.text().to_lowercase())
+            }),
+            BibtexEntrySorting::Year => group.sort_by_cached_key(|&i| {
+                let year =
+                    bibtex::Entry::cast(nodes[i].clone()).and_then(|entry| entry_year(&entry));
+                (year.is_none(), year)
+            }),
+        };
+    }
+}
+
+fn entry_year(entry: &bibtex::Entry) -> Option<u32> {
+    ["year", "date"].into_iter().find_map(|name| {
+        let field = entry.fields().find(|field| {
+            field
+                .name_token()
+                .map_or(false, |token| token.text().eq_ignore_ascii_case(name))
+        })?;
+
+        let text = field.value()?.syntax().text().to_string();
+        let start = text.find(|c: char| c.is_ascii_digit())?;
+        let digits: String = text[start..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        digits.parse().ok()
+    })
+}
+
+/// Checks if the value contains a `"` outside of nested braces (e.g. `{Schr\"odinger}`),
+/// which would terminate the value early if the braces were replaced with quotes.
+fn has_unbraced_quote(node: &bibtex::SyntaxNode) -> bool {
+    node.children_with_tokens().any(|element| match element {
+        NodeOrToken::Node(child) => {
+            child.kind() != bibtex::CURLY_GROUP && has_unbraced_quote(&child)
+        }
+        NodeOrToken::Token(token) => token.text().contains('"'),
+    })
 }
 
 struct Formatter<'a> {
     indent: String,
     tab_size: u32,
     line_length: usize,
+    style: &'a BibtexFormattingOptions,
     output: String,
     align: Vec<usize>,
+    field_name_width: usize,
     line_index: &'a LineIndex,
 }
 
 impl<'a> Formatter<'a> {
-    fn new(indent: String, tab_size: u32, line_length: usize, line_index: &'a LineIndex) -> Self {
+    fn new(
+        indent: String,
+        tab_size: u32,
+        line_length: usize,
+        style: &'a BibtexFormattingOptions,
+        line_index: &'a LineIndex,
+    ) -> Self {
         Self {
             indent,
             tab_size,
             line_length,
+            style,
             output: String::new(),
             align: Vec::new(),
+            field_name_width: 0,
             line_index,
         }
     }
 
-    fn visit_token_lowercase(&mut self, token: &bibtex::SyntaxToken) {
-        self.output.push_str(&token.text().to_lowercase());
+    fn visit_type_token(&mut self, token: &bibtex::SyntaxToken) {
+        if self.style.lowercase_entry_types {
+            self.output.push_str(&token.text().to_lowercase());
+        } else {
+            self.output.push_str(token.text());
+        }
+    }
+
+    fn field_name(&self, token: &bibtex::SyntaxToken) -> String {
+        if self.style.lowercase_field_names {
+            token.text().to_lowercase()
+        } else {
+            token.text().to_string()
+        }
+    }
+
+    /// Finds the delimiters of the given value that need to be replaced
+    /// to match the configured style.
+    fn find_delimiter_replacements(
+        &self,
+        node: &bibtex::SyntaxNode,
+        replacements: &mut Vec<(TextSize, &'static str)>,
+    ) {
+        let tokens: Vec<_> = node
+            .children_with_tokens()
+            .filter_map(|element| element.into_token())
+            .collect();
+
+        match (node.kind(), self.style.delimiters) {
+            (bibtex::JOIN, _) => {
+                for child in node.children() {
+                    self.find_delimiter_replacements(&child, replacements);
+                }
+            }
+            (bibtex::CURLY_GROUP, BibtexDelimiters::Quotes)
+                if tokens.len() >= 2
+                    && tokens.last().unwrap().kind() == bibtex::R_CURLY
+                    && !has_unbraced_quote(node) =>
+            {
+                replacements.push((tokens[0].text_range().start(), "\""));
+                replacements.push((tokens.last().unwrap().text_range().start(), "\""));
+            }
+            (bibtex::QUOTE_GROUP, BibtexDelimiters::Braces)
+                if tokens.len() >= 2
+                    && tokens.last().unwrap().kind() == bibtex::QUOTE
+                    && tokens.iter().all(|token| token.kind() != bibtex::R_CURLY) =>
+            {
+                replacements.push((tokens[0].text_range().start(), "{"));
+                replacements.push((tokens.last().unwrap().text_range().start(), "}"));
+            }
+            _ => {}
+        };
     }
 
     fn should_insert_space(
@@ -111,7 +244,7 @@ impl<'a> Formatter<'a> {
         match parent.kind() {
             bibtex::PREAMBLE => {
                 let preamble = bibtex::Preamble::cast(parent).unwrap();
-                self.visit_token_lowercase(&preamble.type_token().unwrap());
+                self.visit_type_token(&preamble.type_token().unwrap());
                 self.output.push('{');
                 if preamble.syntax().children().next().is_some() {
                     self.align.push(self.base_align());
@@ -123,7 +256,7 @@ impl<'a> Formatter<'a> {
             }
             bibtex::STRING => {
                 let string = bibtex::StringDef::cast(parent).unwrap();
-                self.visit_token_lowercase(&string.type_token().unwrap());
+                self.visit_type_token(&string.type_token().unwrap());
                 self.output.push('{');
                 if let Some(name) = string.name_token() {
                     self.output.push_str(name.text());
@@ -137,26 +270,57 @@ impl<'a> Formatter<'a> {
             }
             bibtex::ENTRY => {
                 let entry = bibtex::Entry::cast(parent).unwrap();
-                self.visit_token_lowercase(&entry.type_token().unwrap());
+                self.visit_type_token(&entry.type_token().unwrap());
                 self.output.push('{');
                 if let Some(key) = entry.name_token() {
                     self.output.push_str(&key.to_string());
                     self.output.push(',');
                     self.output.push('\n');
-                    for field in entry.fields() {
+
+                    let mut fields: Vec<_> = entry.fields().collect();
+                    fields.sort_by_key(|field| {
+                        let name = field.name_token().unwrap();
+                        self.style
+                            .field_order
+                            .iter()
+                            .position(|other| other.eq_ignore_ascii_case(name.text()))
+                            .unwrap_or(usize::MAX)
+                    });
+
+                    if self.style.align_fields {
+                        self.field_name_width = fields
+                            .iter()
+                            .filter_map(|field| field.name_token())
+                            .map(|name| name.text().chars().count())
+                            .max()
+                            .unwrap_or_default();
+                    }
+
+                    for field in fields {
                         self.visit_node(field.syntax().clone());
                     }
+
+                    if !self.style.trailing_comma && self.output.ends_with(",\n") {
+                        self.output.truncate(self.output.len() - 2);
+                        self.output.push('\n');
+                    }
+
                     self.output.push('}');
                 }
             }
             bibtex::FIELD => {
                 let field = bibtex::Field::cast(parent).unwrap();
                 self.output.push_str(&self.indent);
-                let name = field.name_token().unwrap();
-                self.output.push_str(name.text());
+                let name = self.field_name(&field.name_token().unwrap());
+                self.output.push_str(&name);
+                let mut count = name.chars().count();
+                while count < self.field_name_width {
+                    self.output.push(' ');
+                    count += 1;
+                }
+
                 self.output.push_str(" = ");
                 if let Some(value) = field.value() {
-                    let count = name.text().chars().count();
                     self.align.push(self.tab_size as usize + count + 3);
                     self.visit_node(value.syntax().clone());
                     self.output.push(',');
@@ -170,7 +334,17 @@ impl<'a> Formatter<'a> {
                     .filter(|token| token.kind() != bibtex::WHITESPACE)
                     .collect();
 
-                self.output.push_str(tokens[0].text());
+                let mut replacements = Vec::new();
+                self.find_delimiter_replacements(&parent, &mut replacements);
+                let token_text = |token: &bibtex::SyntaxToken| {
+                    let start = token.text_range().start();
+                    replacements
+                        .iter()
+                        .find(|(offset, _)| *offset == start)
+                        .map_or_else(|| token.text().to_string(), |(_, text)| text.to_string())
+                };
+
+                self.output.push_str(&token_text(&tokens[0]));
 
                 let align = self.align.pop().unwrap_or_default();
                 let mut length = align + tokens[0].text().chars().count();
@@ -193,7 +367,7 @@ impl<'a> Formatter<'a> {
                         self.output.push(' ');
                         length += 1;
                     }
-                    self.output.push_str(current.text());
+                    self.output.push_str(&token_text(current));
                     length += current_length;
                 }
             }
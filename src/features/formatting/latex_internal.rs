@@ -0,0 +1,500 @@
+use lsp_types::{DocumentOnTypeFormattingParams, FormattingOptions, Position, Range, TextEdit};
+use rowan::{ast::AstNode, TextRange, TextSize};
+
+use crate::{features::FeatureRequest, syntax::latex, LineIndexExt, LANGUAGE_DATA};
+
+use super::{formatting_indent, formatting_line_length, is_in_range};
+
+pub fn format_latex_internal<P>(
+    request: &FeatureRequest<P>,
+    options: &FormattingOptions,
+    range: Option<Range>,
+) -> Option<Vec<TextEdit>> {
+    let document = request.main_document();
+    let data = document.data.as_latex()?;
+    let root = latex::SyntaxNode::new_root(data.green.clone());
+    let range = range.map(|range| document.line_index.offset_lsp_range(range));
+
+    let formatter = Formatter {
+        text: &document.text,
+        indent: formatting_indent(options),
+        tab_size: options.tab_size as usize,
+        line_length: formatting_line_length(request),
+    };
+
+    let mut edits = Vec::new();
+    for line in split_lines(&root)
+        .into_iter()
+        .filter(|line| is_in_range(line.range, range))
+    {
+        let old_text = &document.text[line.range];
+        let new_text = formatter.format_line(&line);
+        if old_text != new_text {
+            edits.push(TextEdit {
+                range: document.line_index.line_col_lsp_range(line.range),
+                new_text,
+            });
+        }
+    }
+
+    Some(edits)
+}
+
+/// Re-indents the current line after typing `\item `, `\end{...}` or a line break.
+pub fn indent_latex_line(
+    request: &FeatureRequest<DocumentOnTypeFormattingParams>,
+) -> Option<Vec<TextEdit>> {
+    let document = request.main_document();
+    let data = document.data.as_latex()?;
+    let root = latex::SyntaxNode::new_root(data.green.clone());
+
+    let params = &request.params;
+    let position = params.text_document_position.position;
+    let line_start = document
+        .line_index
+        .offset_lsp(Position::new(position.line, 0));
+
+    // Lines inside of a verbatim environment are not indented.
+    let first_token = root.token_at_offset(line_start).right_biased();
+    if first_token.as_ref().map_or(false, |token| {
+        token.text_range().start() < line_start
+            && !matches!(token.kind(), latex::WHITESPACE | latex::LINE_BREAK)
+    }) {
+        return None;
+    }
+
+    // Only the current line is inspected because this runs on every keystroke.
+    let indent_len: TextSize = document.text[usize::from(line_start)..]
+        .chars()
+        .take_while(|c| c.is_whitespace() && *c != '\r' && *c != '\n')
+        .map(TextSize::of)
+        .sum();
+
+    let indent_end = line_start + indent_len;
+    let is_blank = document.text[usize::from(indent_end)..]
+        .chars()
+        .next()
+        .map_or(true, |c| c == '\r' || c == '\n');
+
+    let cursor = document.line_index.offset_lsp(position).max(indent_end);
+    let content = &document.text[TextRange::new(indent_end, cursor)];
+    let should_indent = match params.ch.as_str() {
+        "\n" => true,
+        " " => content == "\\item ",
+        "}" => content.starts_with("\\end"),
+        _ => false,
+    };
+
+    if !should_indent {
+        return Some(Vec::new());
+    }
+
+    let token = if is_blank {
+        root.token_at_offset(line_start)
+            .left_biased()
+            .or_else(|| root.last_token())?
+    } else {
+        root.token_at_offset(indent_end).right_biased()?
+    };
+
+    if is_protected(&token, indent_end) {
+        return Some(Vec::new());
+    }
+
+    let formatter = Formatter {
+        text: &document.text,
+        indent: formatting_indent(&params.options),
+        tab_size: params.options.tab_size as usize,
+        line_length: usize::MAX,
+    };
+
+    let mut new_text = String::new();
+    formatter.push_indent(depth(&token, indent_end), &mut new_text);
+
+    let range = TextRange::new(line_start, indent_end);
+    if document.text[range] == new_text {
+        return Some(Vec::new());
+    }
+
+    Some(vec![TextEdit {
+        range: document.line_index.line_col_lsp_range(range),
+        new_text,
+    }])
+}
+
+/// A token or a part of a whitespace token that does not contain line breaks.
+#[derive(Debug, Clone)]
+struct Piece {
+    token: latex::SyntaxToken,
+    range: TextRange,
+}
+
+impl Piece {
+    fn is_whitespace(&self) -> bool {
+        matches!(self.token.kind(), latex::WHITESPACE | latex::LINE_BREAK)
+    }
+}
+
+/// A line of the source text without its line terminator.
+/// Verbatim tokens spanning multiple lines are kept as a single piece.
+#[derive(Debug)]
+struct Line {
+    range: TextRange,
+    pieces: Vec<Piece>,
+}
+
+fn split_lines(root: &latex::SyntaxNode) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut start = TextSize::from(0);
+    let mut pieces = Vec::new();
+    for token in root
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+    {
+        if !matches!(token.kind(), latex::WHITESPACE | latex::LINE_BREAK) {
+            pieces.push(Piece {
+                range: token.text_range(),
+                token,
+            });
+            continue;
+        }
+
+        let mut offset = token.text_range().start();
+        let mut chars = token.text().chars().peekable();
+        let mut space_start = offset;
+        while let Some(c) = chars.next() {
+            let next_offset = offset + TextSize::of(c);
+            if c == '\r' || c == '\n' {
+                if space_start < offset {
+                    pieces.push(Piece {
+                        token: token.clone(),
+                        range: TextRange::new(space_start, offset),
+                    });
+                }
+
+                lines.push(Line {
+                    range: TextRange::new(start, offset),
+                    pieces: std::mem::take(&mut pieces),
+                });
+
+                offset = next_offset;
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                    offset += TextSize::of('\n');
+                }
+
+                start = offset;
+                space_start = offset;
+            } else {
+                offset = next_offset;
+            }
+        }
+
+        if space_start < offset {
+            pieces.push(Piece {
+                token: token.clone(),
+                range: TextRange::new(space_start, offset),
+            });
+        }
+    }
+
+    lines.push(Line {
+        range: TextRange::new(start, root.text_range().end()),
+        pieces,
+    });
+
+    lines
+}
+
+/// A part of a formatted line.
+#[derive(Debug)]
+struct Chunk<'a> {
+    piece: &'a Piece,
+    text: &'a str,
+    breakable: bool,
+}
+
+impl<'a> Chunk<'a> {
+    fn width(&self) -> usize {
+        self.text.chars().count()
+    }
+}
+
+/// A line of the output that may still be wrapped.
+#[derive(Debug, Default)]
+struct OutputLine<'a> {
+    chunks: Vec<Chunk<'a>>,
+}
+
+impl<'a> OutputLine<'a> {
+    fn trim_end(&mut self) {
+        while self
+            .chunks
+            .last()
+            .filter(|chunk| chunk.piece.is_whitespace())
+            .is_some()
+        {
+            self.chunks.pop();
+        }
+    }
+}
+
+struct Formatter<'a> {
+    text: &'a str,
+    indent: String,
+    tab_size: usize,
+    line_length: usize,
+}
+
+impl<'a> Formatter<'a> {
+    fn format_line(&self, line: &Line) -> String {
+        let first = match line.pieces.iter().find(|piece| !piece.is_whitespace()) {
+            Some(piece) => piece,
+            None => return String::new(),
+        };
+
+        if is_protected(&first.token, first.range.start()) {
+            return self.text[line.range].to_string();
+        }
+
+        let mut output_lines = vec![OutputLine::default()];
+        let mut squeeze_space = false;
+        for piece in line.pieces.iter().skip_while(|piece| piece.is_whitespace()) {
+            let text = &self.text[piece.range];
+            if piece.is_whitespace() {
+                let (text, breakable) = if squeeze_space {
+                    (" ", false)
+                } else {
+                    (text, is_breakable(piece))
+                };
+
+                squeeze_space = false;
+                output_lines.last_mut().unwrap().chunks.push(Chunk {
+                    piece,
+                    text,
+                    breakable,
+                });
+                continue;
+            }
+
+            if is_enum_item_command(&piece.token) {
+                let output_line = output_lines.last_mut().unwrap();
+                output_line.trim_end();
+                if !output_line.chunks.is_empty() {
+                    output_lines.push(OutputLine::default());
+                }
+
+                squeeze_space = true;
+            } else {
+                squeeze_space = is_enum_item_label_end(&piece.token);
+            }
+
+            output_lines.last_mut().unwrap().chunks.push(Chunk {
+                piece,
+                text,
+                breakable: false,
+            });
+        }
+
+        let mut new_text = String::new();
+        for (i, output_line) in output_lines.iter_mut().enumerate() {
+            output_line.trim_end();
+            if i > 0 {
+                new_text.push('\n');
+            }
+
+            self.render_line(output_line, &mut new_text);
+        }
+
+        new_text
+    }
+
+    fn render_line(&self, line: &OutputLine, output: &mut String) {
+        let first = &line.chunks[0].piece;
+        let mut width = self.push_indent(depth(&first.token, first.range.start()), output);
+
+        let can_wrap = line
+            .chunks
+            .iter()
+            .all(|chunk| !chunk.text.contains('\n') && !is_inline_verbatim(&chunk.piece.token));
+
+        for (i, chunk) in line.chunks.iter().enumerate() {
+            if can_wrap && chunk.breakable {
+                let word_width: usize = line.chunks[i + 1..]
+                    .iter()
+                    .take_while(|chunk| !chunk.breakable)
+                    .map(Chunk::width)
+                    .sum();
+
+                if width + chunk.width() + word_width > self.line_length {
+                    let next = &line.chunks[i + 1].piece;
+                    output.push('\n');
+                    width = self.push_indent(depth(&next.token, next.range.start()), output);
+                    continue;
+                }
+            }
+
+            output.push_str(chunk.text);
+            width += chunk.width();
+        }
+    }
+
+    fn push_indent(&self, depth: usize, output: &mut String) -> usize {
+        let mut width = 0;
+        for _ in 0..depth {
+            output.push_str(&self.indent);
+            width += if self.indent == "\t" {
+                self.tab_size
+            } else {
+                self.indent.len()
+            };
+        }
+
+        width
+    }
+}
+
+/// Computes the indentation level of a line starting with the given token.
+fn depth(token: &latex::SyntaxToken, offset: TextSize) -> usize {
+    token
+        .parent_ancestors()
+        .filter(|node| match node.kind() {
+            latex::ENVIRONMENT => {
+                let environment = latex::Environment::cast(node.clone()).unwrap();
+                environment_name(&environment).as_deref() != Some("document")
+                    && environment_body(&environment).contains(offset)
+            }
+            latex::ENUM_ITEM => node.text_range().start() < offset,
+            latex::CURLY_GROUP
+            | latex::CURLY_GROUP_WORD
+            | latex::CURLY_GROUP_WORD_LIST
+            | latex::CURLY_GROUP_COMMAND
+            | latex::CURLY_GROUP_KEY_VALUE
+            | latex::BRACK_GROUP
+            | latex::BRACK_GROUP_WORD
+            | latex::BRACK_GROUP_KEY_VALUE => group_body(node).contains(offset),
+            _ => false,
+        })
+        .count()
+}
+
+fn environment_name(environment: &latex::Environment) -> Option<String> {
+    let name = environment.begin()?.name()?.key()?;
+    Some(name.to_string())
+}
+
+/// The range between the end of `\begin{...}` and the start of `\end{...}`.
+fn environment_body(environment: &latex::Environment) -> TextRange {
+    let node = environment.syntax();
+    let start = environment
+        .begin()
+        .and_then(|begin| {
+            begin
+                .syntax()
+                .descendants_with_tokens()
+                .filter_map(|element| element.into_token())
+                .filter(|token| !is_trivia(token))
+                .last()
+        })
+        .map_or_else(
+            || node.text_range().start(),
+            |token| token.text_range().end(),
+        );
+
+    let end = environment.end().map_or_else(
+        || node.text_range().end(),
+        |end| end.syntax().text_range().start(),
+    );
+
+    TextRange::new(start, end.max(start))
+}
+
+/// The range between the opening and the closing delimiter of a group.
+fn group_body(node: &latex::SyntaxNode) -> TextRange {
+    let start = node.text_range().start() + TextSize::from(1);
+    let end = node
+        .children_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| !is_trivia(token))
+        .last()
+        .filter(|token| matches!(token.kind(), latex::R_CURLY | latex::R_BRACK))
+        .map_or_else(
+            || node.text_range().end(),
+            |token| token.text_range().start(),
+        );
+
+    TextRange::new(start, end.max(start))
+}
+
+fn is_trivia(token: &latex::SyntaxToken) -> bool {
+    matches!(
+        token.kind(),
+        latex::WHITESPACE | latex::LINE_BREAK | latex::COMMENT
+    )
+}
+
+/// Checks if the token is part of a formula, a math environment or verbatim text.
+fn is_protected(token: &latex::SyntaxToken, offset: TextSize) -> bool {
+    token.kind() == latex::VERBATIM
+        || token.parent_ancestors().any(|node| match node.kind() {
+            latex::FORMULA | latex::EQUATION => node.text_range().start() < offset,
+            latex::ENVIRONMENT => {
+                let environment = latex::Environment::cast(node).unwrap();
+                environment_name(&environment).map_or(false, |name| {
+                    LANGUAGE_DATA
+                        .math_environments
+                        .iter()
+                        .any(|env| env == &name)
+                }) && environment_body(&environment).contains(offset)
+            }
+            _ => false,
+        })
+}
+
+/// Checks if a line can be wrapped at the given whitespace.
+fn is_breakable(piece: &Piece) -> bool {
+    let parent = piece.token.parent().unwrap();
+    matches!(
+        parent.kind(),
+        latex::ROOT
+            | latex::PREAMBLE
+            | latex::TEXT
+            | latex::CURLY_GROUP
+            | latex::ENVIRONMENT
+            | latex::ENUM_ITEM
+            | latex::PART
+            | latex::CHAPTER
+            | latex::SECTION
+            | latex::SUBSECTION
+            | latex::SUBSUBSECTION
+            | latex::PARAGRAPH
+            | latex::SUBPARAGRAPH
+    ) && !is_protected(&piece.token, piece.range.start())
+}
+
+fn is_enum_item_command(token: &latex::SyntaxToken) -> bool {
+    token.kind() == latex::ENUM_ITEM_NAME
+        && token
+            .parent()
+            .and_then(|item| item.parent())
+            .map_or(false, |parent| parent.kind() == latex::ENVIRONMENT)
+        && !is_protected(token, token.text_range().start())
+}
+
+fn is_enum_item_label_end(token: &latex::SyntaxToken) -> bool {
+    token.kind() == latex::R_BRACK
+        && token
+            .parent()
+            .filter(|group| group.kind() == latex::BRACK_GROUP)
+            .and_then(|group| group.parent())
+            .map_or(false, |parent| parent.kind() == latex::ENUM_ITEM)
+}
+
+/// Commands like `\verb|...|` whose argument must not be split across lines.
+fn is_inline_verbatim(token: &latex::SyntaxToken) -> bool {
+    token.kind() == latex::GENERIC_COMMAND_NAME
+        && matches!(
+            token.text().trim_end_matches('*'),
+            "\\verb" | "\\lstinline" | "\\mintinline"
+        )
+}
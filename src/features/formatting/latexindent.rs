@@ -3,14 +3,16 @@ use std::{
     process::{Command, Stdio},
 };
 
-use lsp_types::{DocumentFormattingParams, TextEdit};
-use rowan::{TextLen, TextRange};
+use lsp_types::{Range, TextEdit};
 use tempfile::tempdir;
 
-use crate::{features::FeatureRequest, DocumentLanguage, LineIndexExt};
+use crate::{features::FeatureRequest, DocumentLanguage};
 
-pub fn format_with_latexindent(
-    request: &FeatureRequest<DocumentFormattingParams>,
+use super::diff_lines;
+
+pub fn format_with_latexindent<P>(
+    request: &FeatureRequest<P>,
+    range: Option<Range>,
 ) -> Option<Vec<TextEdit>> {
     let directory = tempdir().ok()?;
     let document = request.main_document();
@@ -68,6 +70,21 @@ pub fn format_with_latexindent(
         args.push("--modifylinebreaks");
     }
     args.push(&local);
+
+    let lines = range.map(|range| {
+        let start = range.start.line + 1;
+        let mut end = range.end.line + 1;
+        if range.end.character == 0 && range.end.line > range.start.line {
+            end -= 1;
+        }
+
+        format!("--lines={}-{}", start, end)
+    });
+
+    if let Some(lines) = &lines {
+        args.push(lines);
+    }
+
     args.push(name);
 
     let output = Command::new("latexindent")
@@ -84,11 +101,6 @@ pub fn format_with_latexindent(
     if new_text.is_empty() {
         None
     } else {
-        Some(vec![TextEdit {
-            range: document
-                .line_index
-                .line_col_lsp_range(TextRange::new(0.into(), document.text.text_len())),
-            new_text,
-        }])
+        Some(diff_lines(&document.line_index, &document.text, &new_text))
     }
 }
@@ -1,6 +1,6 @@
 use std::{
     io,
-    path::Path,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
 };
 
@@ -9,6 +9,8 @@ use lsp_types::TextDocumentPositionParams;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
+use crate::Document;
+
 use super::FeatureRequest;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize_repr, Deserialize_repr)]
@@ -36,34 +38,7 @@ pub fn execute_forward_search(
         });
     }
 
-    let root_document = request
-        .workspace
-        .documents_by_uri
-        .values()
-        .find(|document| {
-            if let Some(data) = document.data.as_latex() {
-                data.extras.has_document_environment
-                    && !data
-                        .extras
-                        .explicit_links
-                        .iter()
-                        .filter_map(|link| link.as_component_name())
-                        .any(|name| name == "subfiles.cls")
-            } else {
-                false
-            }
-        })
-        .filter(|document| document.uri.scheme() == "file")?;
-
-    let data = root_document.data.as_latex()?;
-    let pdf_path = data
-        .extras
-        .implicit_links
-        .pdf
-        .iter()
-        .filter_map(|uri| uri.to_file_path().ok())
-        .find(|path| path.exists())?;
-
+    let pdf_path = find_pdf_path(&request)?;
     let tex_path = request.main_document().uri.to_file_path().ok()?;
 
     let args: Vec<String> = options
@@ -86,6 +61,47 @@ pub fn execute_forward_search(
     Some(ForwardSearchResult { status })
 }
 
+/// Finds the PDF file that has been produced by compiling the root document
+/// of the project containing the requested document.
+pub(super) fn find_pdf_path<P>(request: &FeatureRequest<P>) -> Option<PathBuf> {
+    find_pdf(request).map(|(_, path)| path)
+}
+
+/// Finds the root document of the project containing the requested document
+/// together with the PDF file that has been produced by compiling it.
+pub(super) fn find_pdf<P>(request: &FeatureRequest<P>) -> Option<(&Document, PathBuf)> {
+    let workspace = &request.workspace;
+    let root_document = workspace
+        .find_magic_root(&request.uri)
+        .or_else(|| {
+            workspace.documents_by_uri.values().find(|document| {
+                if let Some(data) = document.data.as_latex() {
+                    data.extras.has_document_environment
+                        && !data
+                            .extras
+                            .explicit_links
+                            .iter()
+                            .filter_map(|link| link.as_component_name())
+                            .any(|name| name == "subfiles.cls")
+                } else {
+                    false
+                }
+            })
+        })
+        .filter(|document| document.uri.scheme() == "file")?;
+
+    let data = root_document.data.as_latex()?;
+    let pdf_path = data
+        .extras
+        .implicit_links
+        .pdf
+        .iter()
+        .filter_map(|uri| uri.to_file_path().ok())
+        .find(|path| path.exists())?;
+
+    Some((root_document, pdf_path))
+}
+
 /// Iterate overs chunks of a string. Either returns a slice of the
 /// original string, or the placeholder replacement.
 pub struct PlaceHolderIterator<'a> {
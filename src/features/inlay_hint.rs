@@ -0,0 +1,157 @@
+use lsp_types::{InlayHint, InlayHintLabel, InlayHintParams};
+use rowan::{ast::AstNode, TextSize};
+
+use crate::{
+    citation, find_label_number, render_label,
+    syntax::{bibtex, latex},
+    LineIndexExt,
+};
+
+use super::FeatureRequest;
+
+pub fn find_inlay_hints(request: FeatureRequest<InlayHintParams>) -> Option<Vec<InlayHint>> {
+    let document = request.main_document();
+    let data = document.data.as_latex()?;
+    let options = &request.workspace.environment.options.inlay_hints;
+    let range = document.line_index.offset_lsp_range(request.params.range);
+
+    let mut hints = Vec::new();
+    for node in latex::SyntaxNode::new_root(data.green.clone()).descendants() {
+        let hint = match node.kind() {
+            latex::LABEL_REFERENCE if options.label_references => {
+                find_label_reference_hint(&request, node)
+            }
+            latex::LABEL_REFERENCE_RANGE if options.label_references => {
+                find_label_reference_range_hint(&request, node)
+            }
+            latex::CITATION if options.citations => find_citation_hint(&request, node),
+            kind if options.section_numbers && latex::Section::can_cast(kind) => {
+                find_section_hint(&request, node)
+            }
+            _ => None,
+        };
+
+        let (offset, label) = match hint {
+            Some((offset, label)) if range.contains_inclusive(offset) => (offset, label),
+            _ => continue,
+        };
+
+        hints.push(InlayHint {
+            position: document.line_index.line_col_lsp(offset),
+            label: InlayHintLabel::String(label),
+            kind: None,
+            text_edits: None,
+            tooltip: None,
+            padding_left: Some(true),
+            padding_right: None,
+            data: None,
+        });
+    }
+
+    Some(hints)
+}
+
+fn find_label_reference_hint(
+    request: &FeatureRequest<InlayHintParams>,
+    node: latex::SyntaxNode,
+) -> Option<(TextSize, String)> {
+    let reference = latex::LabelReference::cast(node)?;
+    let labels: Vec<_> = reference
+        .name_list()?
+        .keys()
+        .filter_map(|name| render_label(&request.workspace, &name.to_string(), None))
+        .filter_map(|label| label.short_reference())
+        .collect();
+
+    if labels.is_empty() {
+        return None;
+    }
+
+    Some((latex::small_range(&reference).end(), labels.join(", ")))
+}
+
+fn find_label_reference_range_hint(
+    request: &FeatureRequest<InlayHintParams>,
+    node: latex::SyntaxNode,
+) -> Option<(TextSize, String)> {
+    let reference = latex::LabelReferenceRange::cast(node)?;
+    let render = |group: Option<latex::CurlyGroupWord>| {
+        let name = group?.key()?.to_string();
+        render_label(&request.workspace, &name, None)?.short_reference()
+    };
+
+    let label = match (render(reference.from()), render(reference.to())) {
+        (None, None) => return None,
+        (from, to) => format!(
+            "{} to {}",
+            from.as_deref().unwrap_or("?"),
+            to.as_deref().unwrap_or("?")
+        ),
+    };
+
+    Some((latex::small_range(&reference).end(), label))
+}
+
+fn find_citation_hint(
+    request: &FeatureRequest<InlayHintParams>,
+    node: latex::SyntaxNode,
+) -> Option<(TextSize, String)> {
+    let citation = latex::Citation::cast(node)?;
+    let entries: Vec<_> = citation
+        .key_list()?
+        .keys()
+        .filter_map(|key| {
+            let key = key.to_string();
+            request
+                .workspace
+                .documents_by_uri
+                .values()
+                .find_map(|document| {
+                    let data = document.data.as_bibtex()?;
+                    let root = bibtex::SyntaxNode::new_root(data.green.clone());
+                    let root = bibtex::Root::cast(root)?;
+                    let entry = root.find_entry(&key)?;
+                    citation::render(&entry)
+                })
+                .map(|text| truncate(text.strip_suffix('.').unwrap_or(&text), CITATION_LENGTH))
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return None;
+    }
+
+    Some((latex::small_range(&citation).end(), entries.join("; ")))
+}
+
+/// The maximum number of characters that are shown for each cited entry.
+const CITATION_LENGTH: usize = 32;
+
+fn truncate(text: &str, max_length: usize) -> String {
+    if text.chars().count() <= max_length {
+        return text.to_string();
+    }
+
+    let mut result: String = text.chars().take(max_length - 1).collect();
+    result.truncate(result.trim_end().len());
+    result.push('…');
+    result
+}
+
+fn find_section_hint(
+    request: &FeatureRequest<InlayHintParams>,
+    node: latex::SyntaxNode,
+) -> Option<(TextSize, String)> {
+    let section = latex::Section::cast(node)?;
+    let label = section
+        .syntax()
+        .children()
+        .find_map(latex::LabelDefinition::cast)?;
+
+    let name = label.name()?.key()?.to_string();
+    let number = find_label_number(&request.workspace, &name)?;
+    Some((
+        latex::small_range(&section.name()?).end(),
+        number.to_string(),
+    ))
+}
@@ -0,0 +1,70 @@
+use lsp_types::{LinkedEditingRangeParams, LinkedEditingRanges};
+use rowan::{ast::AstNode, TextRange};
+
+use crate::{syntax::latex, LineIndexExt};
+
+use super::{cursor::CursorContext, FeatureRequest};
+
+pub fn find_linked_editing_ranges(
+    request: FeatureRequest<LinkedEditingRangeParams>,
+) -> Option<LinkedEditingRanges> {
+    let context = CursorContext::new(request);
+    let ranges = find_environment_ranges(&context).or_else(|| find_equation_ranges(&context))?;
+
+    let line_index = &context.request.main_document().line_index;
+    Some(LinkedEditingRanges {
+        ranges: ranges
+            .into_iter()
+            .map(|range| line_index.line_col_lsp_range(range))
+            .collect(),
+        word_pattern: None,
+    })
+}
+
+fn find_environment_ranges(
+    context: &CursorContext<LinkedEditingRangeParams>,
+) -> Option<[TextRange; 2]> {
+    let (_, _, group) = context.find_curly_group_word()?;
+    let environment = group
+        .syntax()
+        .parent()
+        .filter(|node| matches!(node.kind(), latex::BEGIN | latex::END))?
+        .parent()
+        .and_then(latex::Environment::cast)?;
+
+    let begin = environment.begin()?.name()?.key()?;
+    let end = environment.end()?.name()?.key()?;
+    if begin.to_string() != end.to_string() {
+        return None;
+    }
+
+    Some([latex::small_range(&begin), latex::small_range(&end)])
+}
+
+fn find_equation_ranges(
+    context: &CursorContext<LinkedEditingRangeParams>,
+) -> Option<[TextRange; 2]> {
+    let token = context.cursor.as_latex()?;
+    if !matches!(
+        token.kind(),
+        latex::BEGIN_EQUATION_NAME | latex::END_EQUATION_NAME
+    ) {
+        return None;
+    }
+
+    let equation = token.parent().and_then(latex::Equation::cast)?;
+    let mut delimiters = equation
+        .syntax()
+        .children_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| {
+            matches!(
+                token.kind(),
+                latex::BEGIN_EQUATION_NAME | latex::END_EQUATION_NAME
+            )
+        });
+
+    let begin = delimiters.next()?;
+    let end = delimiters.next()?;
+    Some([begin.text_range(), end.text_range()])
+}
@@ -0,0 +1,152 @@
+use lsp_types::{SelectionRange, SelectionRangeParams};
+use rowan::{ast::AstNode, Language, TextRange, TextSize};
+
+use crate::{
+    syntax::{bibtex, latex},
+    DocumentData, LineIndexExt,
+};
+
+use super::FeatureRequest;
+
+pub fn find_selection_ranges(
+    request: FeatureRequest<SelectionRangeParams>,
+) -> Option<Vec<SelectionRange>> {
+    let document = request.main_document();
+
+    let mut results = Vec::new();
+    for position in &request.params.positions {
+        let offset = document.line_index.offset_lsp(*position);
+        let ranges = match &document.data {
+            DocumentData::Latex(data) => {
+                find_latex_ranges(&latex::SyntaxNode::new_root(data.green.clone()), offset)
+            }
+            DocumentData::Bibtex(data) => {
+                find_bibtex_ranges(&bibtex::SyntaxNode::new_root(data.green.clone()), offset)
+            }
+            DocumentData::BuildLog(_) => return None,
+        };
+
+        let mut result = None;
+        for range in ranges.into_iter().rev() {
+            result = Some(SelectionRange {
+                range: document.line_index.line_col_lsp_range(range),
+                parent: result.map(Box::new),
+            });
+        }
+
+        results.push(result?);
+    }
+
+    Some(results)
+}
+
+fn find_latex_ranges(root: &latex::SyntaxNode, offset: TextSize) -> Vec<TextRange> {
+    let is_trivia = |kind| matches!(kind, latex::WHITESPACE | latex::LINE_BREAK | latex::COMMENT);
+
+    let mut ranges = Vec::new();
+    let token = root
+        .token_at_offset(offset)
+        .right_biased()
+        .filter(|token| !is_trivia(token.kind()))
+        .or_else(|| root.token_at_offset(offset).left_biased());
+
+    if let Some(token) = token {
+        if !matches!(token.kind(), latex::WHITESPACE | latex::LINE_BREAK) {
+            push_range(&mut ranges, token.text_range());
+        }
+
+        for node in token.parent_ancestors() {
+            if let Some(body) = latex::Environment::cast(node.clone())
+                .and_then(|environment| environment_body(&environment, is_trivia))
+            {
+                push_range(&mut ranges, body);
+            }
+
+            push_range(&mut ranges, trimmed_range(&node, is_trivia));
+        }
+    }
+
+    if ranges.is_empty() {
+        ranges.push(root.text_range());
+    }
+
+    ranges
+}
+
+fn find_bibtex_ranges(root: &bibtex::SyntaxNode, offset: TextSize) -> Vec<TextRange> {
+    let is_trivia = |kind| matches!(kind, bibtex::WHITESPACE | bibtex::JUNK);
+
+    let mut ranges = Vec::new();
+    let token = root
+        .token_at_offset(offset)
+        .right_biased()
+        .filter(|token| !is_trivia(token.kind()))
+        .or_else(|| root.token_at_offset(offset).left_biased());
+
+    if let Some(token) = token {
+        if token.kind() != bibtex::WHITESPACE {
+            push_range(&mut ranges, token.text_range());
+        }
+
+        for node in token.parent_ancestors() {
+            push_range(&mut ranges, trimmed_range(&node, is_trivia));
+        }
+    }
+
+    if ranges.is_empty() {
+        ranges.push(root.text_range());
+    }
+
+    ranges
+}
+
+/// Adds a range if it extends the previous range.
+fn push_range(ranges: &mut Vec<TextRange>, range: TextRange) {
+    match ranges.last() {
+        Some(last) if *last == range || !range.contains_range(*last) => {}
+        _ => ranges.push(range),
+    };
+}
+
+/// Computes the range of the environment without the `\begin` and the `\end` command.
+fn environment_body(
+    environment: &latex::Environment,
+    is_trivia: impl Fn(latex::SyntaxKind) -> bool,
+) -> Option<TextRange> {
+    let start = environment.begin()?.syntax().text_range().end();
+    let end = environment.end()?.syntax().text_range().start();
+
+    let mut elements = environment
+        .syntax()
+        .children_with_tokens()
+        .filter(|element| {
+            let range = element.text_range();
+            start <= range.start() && range.end() <= end && !is_trivia(element.kind())
+        })
+        .map(|element| match element {
+            rowan::NodeOrToken::Node(node) => trimmed_range(&node, &is_trivia),
+            rowan::NodeOrToken::Token(token) => token.text_range(),
+        });
+
+    let first = elements.next()?;
+    Some(elements.fold(first, |range, next| range.cover(next)))
+}
+
+/// Computes the range of a node without leading and trailing trivia.
+fn trimmed_range<L: Language>(
+    node: &rowan::SyntaxNode<L>,
+    is_trivia: impl Fn(L::Kind) -> bool,
+) -> TextRange {
+    let mut tokens = node
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| !is_trivia(token.kind()));
+
+    match tokens.next() {
+        Some(first) => {
+            let last = tokens.last().unwrap_or_else(|| first.clone());
+            first.text_range().cover(last.text_range())
+        }
+        None => node.text_range(),
+    }
+}
@@ -0,0 +1,243 @@
+mod bibtex;
+mod latex;
+
+use lsp_types::{
+    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens, SemanticTokensLegend,
+    SemanticTokensParams, SemanticTokensRangeParams,
+};
+use rowan::{TextLen, TextRange};
+
+use crate::{Document, LineIndexExt};
+
+use self::{bibtex::find_bibtex_tokens, latex::find_latex_tokens};
+
+use super::FeatureRequest;
+
+/// The token types reported by the server.
+/// The position of a variant is its index in the legend,
+/// so new variants must only be appended at the end.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum TokenKind {
+    /// A command that is provided by LaTeX itself or by a package (`\textbf`).
+    /// Reported as `function`.
+    Command,
+    /// A command that is not known to the component database
+    /// and therefore most likely defined by the user (`\foo`).
+    /// Reported as `macro`.
+    UserCommand,
+    /// A structural command like `\begin`, `\section`, `\item` or `\newcommand`.
+    /// Reported as `keyword`.
+    Keyword,
+    /// A command that includes or imports another file (`\input`, `\usepackage`).
+    /// Reported as `namespace`.
+    Include,
+    /// The name of an environment inside `\begin{...}` and `\end{...}`.
+    /// Reported as `class`.
+    EnvironmentName,
+    /// A label name inside `\label`, `\ref` and friends.
+    /// Reported as `enumMember`.
+    Label,
+    /// A citation key inside `\cite` or the key of a BibTeX entry.
+    /// Reported as `parameter`.
+    CitationKey,
+    /// The content of inline and display math.
+    /// Reported as `math`.
+    Math,
+    /// The type of a BibTeX entry (`@article`).
+    /// Reported as `type`.
+    EntryType,
+    /// The name of a BibTeX field (`author`).
+    /// Reported as `property`.
+    FieldName,
+    /// The name of a BibTeX `@string` and its references.
+    /// Reported as `variable`.
+    StringReference,
+}
+
+impl TokenKind {
+    const ALL: [Self; 11] = [
+        Self::Command,
+        Self::UserCommand,
+        Self::Keyword,
+        Self::Include,
+        Self::EnvironmentName,
+        Self::Label,
+        Self::CitationKey,
+        Self::Math,
+        Self::EntryType,
+        Self::FieldName,
+        Self::StringReference,
+    ];
+
+    fn token_type(self) -> SemanticTokenType {
+        match self {
+            Self::Command => SemanticTokenType::FUNCTION,
+            Self::UserCommand => SemanticTokenType::MACRO,
+            Self::Keyword => SemanticTokenType::KEYWORD,
+            Self::Include => SemanticTokenType::NAMESPACE,
+            Self::EnvironmentName => SemanticTokenType::CLASS,
+            Self::Label => SemanticTokenType::ENUM_MEMBER,
+            Self::CitationKey => SemanticTokenType::PARAMETER,
+            Self::Math => SemanticTokenType::new("math"),
+            Self::EntryType => SemanticTokenType::TYPE,
+            Self::FieldName => SemanticTokenType::PROPERTY,
+            Self::StringReference => SemanticTokenType::VARIABLE,
+        }
+    }
+}
+
+/// The token modifiers reported by the server.
+/// The position of a variant is its bit in the modifier set.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum TokenModifier {
+    /// The token defines a label, a BibTeX entry or a BibTeX string.
+    Declaration,
+}
+
+impl TokenModifier {
+    const ALL: [Self; 1] = [Self::Declaration];
+
+    fn token_modifier(self) -> SemanticTokenModifier {
+        match self {
+            Self::Declaration => SemanticTokenModifier::DECLARATION,
+        }
+    }
+
+    fn bitset(self) -> u32 {
+        1 << (self as u32)
+    }
+}
+
+#[must_use]
+pub fn semantic_tokens_legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TokenKind::ALL
+            .into_iter()
+            .map(TokenKind::token_type)
+            .collect(),
+        token_modifiers: TokenModifier::ALL
+            .into_iter()
+            .map(TokenModifier::token_modifier)
+            .collect(),
+    }
+}
+
+pub fn find_semantic_tokens_full(
+    request: FeatureRequest<SemanticTokensParams>,
+) -> Option<SemanticTokens> {
+    let document = request.main_document();
+    let range = TextRange::up_to(document.text.text_len());
+    find_semantic_tokens(&request, range)
+}
+
+pub fn find_semantic_tokens_range(
+    request: FeatureRequest<SemanticTokensRangeParams>,
+) -> Option<SemanticTokens> {
+    let document = request.main_document();
+    let range = document.line_index.offset_lsp_range(request.params.range);
+    find_semantic_tokens(&request, range)
+}
+
+fn find_semantic_tokens<P>(
+    request: &FeatureRequest<P>,
+    range: TextRange,
+) -> Option<SemanticTokens> {
+    let document = request.main_document();
+    let mut builder = TokenBuilder::new(document, range);
+    find_latex_tokens(request, &mut builder)
+        .or_else(|| find_bibtex_tokens(request, &mut builder))?;
+    Some(builder.finish())
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Token {
+    range: TextRange,
+    kind: TokenKind,
+    modifiers: u32,
+}
+
+struct TokenBuilder<'a> {
+    document: &'a Document,
+    range: TextRange,
+    tokens: Vec<Token>,
+}
+
+impl<'a> TokenBuilder<'a> {
+    fn new(document: &'a Document, range: TextRange) -> Self {
+        Self {
+            document,
+            range,
+            tokens: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, range: TextRange, kind: TokenKind) {
+        self.push_with_modifiers(range, kind, &[]);
+    }
+
+    fn push_with_modifiers(
+        &mut self,
+        range: TextRange,
+        kind: TokenKind,
+        modifiers: &[TokenModifier],
+    ) {
+        if range.is_empty() || self.range.intersect(range).is_none() {
+            return;
+        }
+
+        let modifiers = modifiers
+            .iter()
+            .fold(0, |bitset, modifier| bitset | modifier.bitset());
+
+        self.tokens.push(Token {
+            range,
+            kind,
+            modifiers,
+        });
+    }
+
+    fn finish(mut self) -> SemanticTokens {
+        self.tokens.sort_by_key(|token| token.range.start());
+
+        let line_index = &self.document.line_index;
+        let mut data = Vec::new();
+        let mut last_line = 0;
+        let mut last_start = 0;
+        for token in self.tokens {
+            let range = line_index.line_col_lsp_range(token.range);
+
+            // Tokens are not allowed to span multiple lines,
+            // so we cut them off at the end of their first line.
+            let length = if range.start.line == range.end.line {
+                range.end.character - range.start.character
+            } else {
+                let text = &self.document.text[usize::from(token.range.start())..];
+                let end = text.find(['\r', '\n']).unwrap_or(text.len());
+                text[..end].encode_utf16().count() as u32
+            };
+
+            let delta_line = range.start.line - last_line;
+            let delta_start = if delta_line == 0 {
+                range.start.character - last_start
+            } else {
+                range.start.character
+            };
+
+            data.push(SemanticToken {
+                delta_line,
+                delta_start,
+                length,
+                token_type: token.kind as u32,
+                token_modifiers_bitset: token.modifiers,
+            });
+
+            last_line = range.start.line;
+            last_start = range.start.character;
+        }
+
+        SemanticTokens {
+            result_id: None,
+            data,
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use rowan::ast::AstNode;
+
+use crate::{
+    features::FeatureRequest,
+    syntax::bibtex::{self, HasName, HasType},
+};
+
+use super::{TokenBuilder, TokenKind, TokenModifier};
+
+pub(super) fn find_bibtex_tokens<P>(
+    request: &FeatureRequest<P>,
+    builder: &mut TokenBuilder,
+) -> Option<()> {
+    let data = request.main_document().data.as_bibtex()?;
+
+    let range = builder.range;
+    let root = bibtex::SyntaxNode::new_root(data.green.clone());
+    for node in root
+        .descendants()
+        .filter(|node| range.intersect(node.text_range()).is_some())
+    {
+        if let Some(entry) = bibtex::Entry::cast(node.clone()) {
+            if let Some(ty) = entry.type_token() {
+                builder.push(ty.text_range(), TokenKind::EntryType);
+            }
+
+            if let Some(key) = entry.name_token() {
+                builder.push_with_modifiers(
+                    key.text_range(),
+                    TokenKind::CitationKey,
+                    &[TokenModifier::Declaration],
+                );
+            }
+        } else if let Some(string) = bibtex::StringDef::cast(node.clone()) {
+            if let Some(ty) = string.type_token() {
+                builder.push(ty.text_range(), TokenKind::EntryType);
+            }
+
+            if let Some(name) = string.name_token() {
+                builder.push_with_modifiers(
+                    name.text_range(),
+                    TokenKind::StringReference,
+                    &[TokenModifier::Declaration],
+                );
+            }
+        } else if let Some(preamble) = bibtex::Preamble::cast(node.clone()) {
+            if let Some(ty) = preamble.type_token() {
+                builder.push(ty.text_range(), TokenKind::EntryType);
+            }
+        } else if let Some(field) = bibtex::Field::cast(node.clone()) {
+            if let Some(name) = field.name_token() {
+                builder.push(name.text_range(), TokenKind::FieldName);
+            }
+        } else if let Some(literal) = bibtex::Literal::cast(node) {
+            if let Some(name) = literal.name_token() {
+                builder.push(name.text_range(), TokenKind::StringReference);
+            }
+        }
+    }
+
+    Some(())
+}
@@ -0,0 +1,144 @@
+use rowan::ast::AstNode;
+use rustc_hash::FxHashSet;
+
+use crate::{
+    component_db::COMPONENT_DATABASE,
+    features::FeatureRequest,
+    syntax::latex::{self, SyntaxKind::*},
+    LANGUAGE_DATA,
+};
+
+use super::{TokenBuilder, TokenKind, TokenModifier};
+
+pub(super) fn find_latex_tokens<P>(
+    request: &FeatureRequest<P>,
+    builder: &mut TokenBuilder,
+) -> Option<()> {
+    let data = request.main_document().data.as_latex()?;
+
+    let component_commands: FxHashSet<_> = COMPONENT_DATABASE
+        .linked_components(&request.workspace)
+        .into_iter()
+        .flat_map(|component| component.commands.iter())
+        .map(|command| command.name.as_str())
+        .collect();
+
+    let user_commands: FxHashSet<_> = request
+        .workspace
+        .documents_by_uri
+        .values()
+        .filter_map(|document| document.data.as_latex())
+        .flat_map(|data| data.extras.command_names.iter())
+        .filter(|name| !component_commands.contains(&name[1..]))
+        .map(|name| name.as_str())
+        .collect();
+
+    let range = builder.range;
+    let root = latex::SyntaxNode::new_root(data.green.clone());
+    for token in root
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| range.intersect(token.text_range()).is_some())
+    {
+        let range = token.text_range();
+        match token.kind() {
+            GENERIC_COMMAND_NAME if user_commands.contains(token.text()) => {
+                builder.push(range, TokenKind::UserCommand);
+            }
+            kind if kind.is_command_name()
+                || matches!(
+                    kind,
+                    ENVIRONMENT_DEFINITION_NAME
+                        | BEGIN_BLOCK_COMMENT_NAME
+                        | END_BLOCK_COMMENT_NAME
+                        | GRAPHICS_PATH_NAME
+                ) =>
+            {
+                builder.push(range, command_kind(kind));
+            }
+            WORD => {
+                if let Some((kind, modifiers)) = key_kind(&token) {
+                    builder.push_with_modifiers(range, kind, modifiers);
+                } else if is_inside_math(&token) {
+                    builder.push(range, TokenKind::Math);
+                }
+            }
+            DOLLAR => builder.push(range, TokenKind::Math),
+            _ => {}
+        }
+    }
+
+    Some(())
+}
+
+fn command_kind(kind: latex::SyntaxKind) -> TokenKind {
+    match kind {
+        BEGIN_ENVIRONMENT_NAME
+        | END_ENVIRONMENT_NAME
+        | BEGIN_EQUATION_NAME
+        | END_EQUATION_NAME
+        | PART_NAME
+        | CHAPTER_NAME
+        | SECTION_NAME
+        | SUBSECTION_NAME
+        | SUBSUBSECTION_NAME
+        | PARAGRAPH_NAME
+        | SUBPARAGRAPH_NAME
+        | ENUM_ITEM_NAME
+        | CAPTION_NAME
+        | COMMAND_DEFINITION_NAME
+        | MATH_OPERATOR_NAME
+        | GLOSSARY_ENTRY_DEFINITION_NAME
+        | ACRONYM_DEFINITION_NAME
+        | ACRONYM_DECLARATION_NAME
+        | THEOREM_DEFINITION_NAME
+        | COLOR_DEFINITION_NAME
+        | COLOR_SET_DEFINITION_NAME
+        | ENVIRONMENT_DEFINITION_NAME
+        | BEGIN_BLOCK_COMMENT_NAME
+        | END_BLOCK_COMMENT_NAME => TokenKind::Keyword,
+        PACKAGE_INCLUDE_NAME
+        | CLASS_INCLUDE_NAME
+        | LATEX_INCLUDE_NAME
+        | BIBLATEX_INCLUDE_NAME
+        | BIBTEX_INCLUDE_NAME
+        | GRAPHICS_INCLUDE_NAME
+        | SVG_INCLUDE_NAME
+        | INKSCAPE_INCLUDE_NAME
+        | VERBATIM_INCLUDE_NAME
+        | IMPORT_NAME
+        | TIKZ_LIBRARY_IMPORT_NAME
+        | GRAPHICS_PATH_NAME => TokenKind::Include,
+        _ => TokenKind::Command,
+    }
+}
+
+fn key_kind(token: &latex::SyntaxToken) -> Option<(TokenKind, &'static [TokenModifier])> {
+    let key = latex::Key::cast(token.parent()?)?;
+    let group = key.syntax().parent()?;
+    if !matches!(group.kind(), CURLY_GROUP_WORD | CURLY_GROUP_WORD_LIST) {
+        return None;
+    }
+
+    match group.parent()?.kind() {
+        LABEL_DEFINITION => Some((TokenKind::Label, &[TokenModifier::Declaration])),
+        LABEL_REFERENCE | LABEL_REFERENCE_RANGE => Some((TokenKind::Label, &[])),
+        CITATION => Some((TokenKind::CitationKey, &[])),
+        BEGIN | END => Some((TokenKind::EnvironmentName, &[])),
+        _ => None,
+    }
+}
+
+fn is_inside_math(token: &latex::SyntaxToken) -> bool {
+    token.parent_ancestors().any(|node| match node.kind() {
+        FORMULA | EQUATION => true,
+        ENVIRONMENT => latex::Environment::cast(node)
+            .and_then(|env| env.begin())
+            .and_then(|begin| begin.name())
+            .and_then(|name| name.key())
+            .map_or(false, |name| {
+                LANGUAGE_DATA.math_environments.contains(&name.to_string())
+            }),
+        _ => false,
+    })
+}
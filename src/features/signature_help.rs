@@ -0,0 +1,236 @@
+use lsp_types::{
+    Documentation, ParameterInformation, ParameterLabel, SignatureHelp, SignatureHelpParams,
+    SignatureInformation,
+};
+use rowan::{ast::AstNode, TextSize};
+
+use crate::{
+    component_db::{ComponentCommand, COMPONENT_DATABASE},
+    syntax::latex::{self, HasBrack, HasCurly},
+    Workspace,
+};
+
+use super::{cursor::CursorContext, FeatureRequest};
+
+pub fn find_signature_help(request: FeatureRequest<SignatureHelpParams>) -> Option<SignatureHelp> {
+    let context = CursorContext::new(request);
+    let (command, group) = find_argument(&context)?;
+
+    let name = command.name()?;
+    let signature = find_user_signature(&context.request.workspace, name.text()).or_else(|| {
+        COMPONENT_DATABASE
+            .linked_components(&context.request.workspace)
+            .into_iter()
+            .flat_map(|component| component.commands.iter())
+            .find(|command| command.name == name.text()[1..] && !command.parameters.is_empty())
+            .map(|command| component_signature(name.text(), command))
+    })?;
+
+    let active_parameter = signature.parameter_index(&command, &group)?;
+    Some(SignatureHelp {
+        signatures: vec![signature.information],
+        active_signature: Some(0),
+        active_parameter: Some(active_parameter),
+    })
+}
+
+#[derive(Debug)]
+struct Signature {
+    information: SignatureInformation,
+    has_optional_parameter: bool,
+}
+
+impl Signature {
+    /// Finds the parameter that corresponds to an argument group of the command.
+    fn parameter_index(
+        &self,
+        command: &latex::GenericCommand,
+        group: &latex::SyntaxNode,
+    ) -> Option<u32> {
+        let groups: Vec<_> = command
+            .syntax()
+            .children()
+            .filter(|node| matches!(node.kind(), latex::CURLY_GROUP | latex::MIXED_GROUP))
+            .collect();
+
+        let position = groups.iter().position(|node| node == group)?;
+        let index = if group.kind() == latex::CURLY_GROUP {
+            let curly_groups = groups[..position]
+                .iter()
+                .filter(|node| node.kind() == latex::CURLY_GROUP)
+                .count();
+
+            curly_groups + usize::from(self.has_optional_parameter)
+        } else if self.has_optional_parameter && position == 0 {
+            0
+        } else {
+            return None;
+        };
+
+        let parameter_count = self.information.parameters.as_ref().map_or(0, Vec::len);
+        if index < parameter_count {
+            Some(index as u32)
+        } else {
+            None
+        }
+    }
+}
+
+/// Finds the argument of a command that contains the cursor.
+fn find_argument(
+    context: &CursorContext<SignatureHelpParams>,
+) -> Option<(latex::GenericCommand, latex::SyntaxNode)> {
+    let token = context.cursor.as_latex()?;
+    token.parent_ancestors().find_map(|node| {
+        let (left, right) = match node.kind() {
+            latex::CURLY_GROUP => {
+                let group = latex::CurlyGroup::cast(node.clone())?;
+                (group.left_curly()?, group.right_curly())
+            }
+            latex::MIXED_GROUP => {
+                let group = latex::MixedGroup::cast(node.clone())?;
+                (group.left_delim()?, group.right_delim())
+            }
+            _ => return None,
+        };
+
+        if left.kind() == latex::L_PAREN || !is_inside(context.offset, &left, right.as_ref()) {
+            return None;
+        }
+
+        let command = latex::GenericCommand::cast(node.parent()?)?;
+        Some((command, node))
+    })
+}
+
+fn is_inside(
+    offset: TextSize,
+    left: &latex::SyntaxToken,
+    right: Option<&latex::SyntaxToken>,
+) -> bool {
+    left.text_range().end() <= offset
+        && right.map_or(true, |right| offset <= right.text_range().start())
+}
+
+/// Derives the signature of a user-defined command from its definition,
+/// e.g. `\newcommand{\foo}[2][x]{...}`.
+fn find_user_signature(workspace: &Workspace, command_name: &str) -> Option<Signature> {
+    let definition = workspace
+        .documents_by_uri
+        .values()
+        .filter_map(|document| document.data.as_latex())
+        .find_map(|data| {
+            latex::SyntaxNode::new_root(data.green.clone())
+                .descendants()
+                .filter_map(latex::CommandDefinition::cast)
+                .find(|definition| {
+                    definition
+                        .name()
+                        .and_then(|name| name.command())
+                        .map_or(false, |name| name.text() == command_name)
+                })
+        })?;
+
+    let count = definition
+        .argument_count()
+        .and_then(|group| group.key())
+        .and_then(|key| key.to_string().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    let default_argument = definition
+        .default_argument()
+        .and_then(|group| group.content_text());
+
+    let parameters = (1..=count).map(|i| match &default_argument {
+        Some(default_argument) if i == 1 => (
+            format!("[#{}]", i),
+            Some(format!("Default value: {}", default_argument)),
+        ),
+        _ => (format!("{{#{}}}", i), None),
+    });
+
+    Some(Signature {
+        information: build_signature(command_name, parameters),
+        has_optional_parameter: count > 0 && default_argument.is_some(),
+    })
+}
+
+fn component_signature(command_name: &str, command: &ComponentCommand) -> Signature {
+    let parameters = command.parameters.iter().enumerate().map(|(i, parameter)| {
+        let values: Vec<_> = parameter.0.iter().map(|arg| arg.name.as_str()).collect();
+        let documentation = if values.is_empty() {
+            None
+        } else {
+            Some(values.join(", "))
+        };
+
+        (format!("{{#{}}}", i + 1), documentation)
+    });
+
+    Signature {
+        information: build_signature(command_name, parameters),
+        has_optional_parameter: false,
+    }
+}
+
+fn build_signature(
+    command_name: &str,
+    parameters: impl Iterator<Item = (String, Option<String>)>,
+) -> SignatureInformation {
+    let mut label = command_name.to_string();
+    let mut infos = Vec::new();
+    for (text, documentation) in parameters {
+        let start = label.encode_utf16().count() as u32;
+        label.push_str(&text);
+        let end = label.encode_utf16().count() as u32;
+        infos.push(ParameterInformation {
+            label: ParameterLabel::LabelOffsets([start, end]),
+            documentation: documentation.map(Documentation::String),
+        });
+    }
+
+    SignatureInformation {
+        label,
+        documentation: None,
+        parameters: Some(infos),
+        active_parameter: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smol_str::SmolStr;
+
+    use crate::component_db::{ComponentArgument, ComponentParameter};
+
+    use super::*;
+
+    #[test]
+    fn test_component_signature() {
+        let command = ComponentCommand {
+            name: SmolStr::new("pagestyle"),
+            image: None,
+            glyph: None,
+            parameters: vec![ComponentParameter(vec![
+                ComponentArgument {
+                    name: SmolStr::new("plain"),
+                    image: None,
+                },
+                ComponentArgument {
+                    name: SmolStr::new("empty"),
+                    image: None,
+                },
+            ])],
+        };
+
+        let signature = component_signature("\\pagestyle", &command);
+        assert_eq!(signature.information.label, "\\pagestyle{#1}");
+        assert_eq!(
+            signature.information.parameters,
+            Some(vec![ParameterInformation {
+                label: ParameterLabel::LabelOffsets([10, 14]),
+                documentation: Some(Documentation::String("plain, empty".into())),
+            }])
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    fs,
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use lsp_types::{Location, Position, Range, TextDocumentPositionParams, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    syntax::synctex::{self, SyncTex},
+    Environment, Workspace,
+};
+
+use super::{build::build_directory, forward_search::find_pdf, FeatureRequest};
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncTexForwardResult {
+    /// The PDF file that contains the requested position.
+    pub uri: Url,
+
+    /// The (one-based) page number.
+    pub page: u32,
+
+    /// The region of the page (in big points relative to the top left corner),
+    /// that corresponds to the requested line.
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncTexInverseParams {
+    /// The PDF file in which the user clicked.
+    pub uri: Url,
+
+    /// The (one-based) page number.
+    pub page: u32,
+
+    /// The position on the page (in big points relative to the top left corner).
+    pub x: f64,
+    pub y: f64,
+}
+
+pub fn execute_synctex_forward(
+    request: FeatureRequest<TextDocumentPositionParams>,
+) -> Option<SyncTexForwardResult> {
+    let (root_document, pdf_path) = find_pdf(&request)?;
+    let root_path = root_document.uri.to_file_path().ok()?;
+    let working_dir = working_directory(&request.workspace.environment, &root_path)?;
+    let tex_path = normalize(&request.main_document().uri.to_file_path().ok()?);
+    let synctex = load_synctex(&pdf_path)?;
+
+    let tags: Vec<u32> = synctex
+        .inputs
+        .iter()
+        .filter(|input| resolve_input(&working_dir, &input.path) == tex_path)
+        .map(|input| input.tag)
+        .collect();
+
+    let pdf_box = synctex.forward(&tags, request.params.position.line + 1)?;
+    Some(SyncTexForwardResult {
+        uri: Url::from_file_path(pdf_path).ok()?,
+        page: pdf_box.page,
+        x: pdf_box.x,
+        y: pdf_box.y,
+        width: pdf_box.width,
+        height: pdf_box.height,
+    })
+}
+
+pub fn execute_synctex_inverse(
+    workspace: &Workspace,
+    params: &SyncTexInverseParams,
+) -> Option<Location> {
+    let pdf_path = params.uri.to_file_path().ok()?;
+    let synctex = load_synctex(&pdf_path)?;
+    let source = synctex.inverse(params.page, params.x, params.y)?;
+    let input = synctex.find_input(source.tag)?;
+
+    // The root document is the one that produces the PDF file.
+    // Without it, the PDF file is assumed to be next to the root document.
+    let working_dir = workspace
+        .documents_by_uri
+        .values()
+        .filter_map(|document| Some((document, document.data.as_latex()?)))
+        .find(|(_, data)| {
+            data.extras
+                .implicit_links
+                .pdf
+                .iter()
+                .any(|uri| uri.as_ref() == &params.uri)
+        })
+        .and_then(|(document, _)| {
+            let root_path = document.uri.to_file_path().ok()?;
+            working_directory(workspace.environment_for(&document.uri), &root_path)
+        })
+        .or_else(|| pdf_path.parent().map(Path::to_path_buf))?;
+
+    let uri = Url::from_file_path(resolve_input(&working_dir, &input.path)).ok()?;
+    let position = Position::new(source.line.saturating_sub(1), 0);
+    Some(Location::new(uri, Range::new(position, position)))
+}
+
+/// Reads the SyncTeX file next to the given PDF file.
+/// Both the compressed (`-synctex=1`) and the uncompressed (`-synctex=-1`) variant are supported.
+fn load_synctex(pdf_path: &Path) -> Option<SyncTex> {
+    let mut text = String::new();
+    if let Ok(file) = fs::File::open(pdf_path.with_extension("synctex.gz")) {
+        GzDecoder::new(file).read_to_string(&mut text).ok()?;
+    } else {
+        text = fs::read_to_string(pdf_path.with_extension("synctex")).ok()?;
+    }
+
+    synctex::parse(&text)
+}
+
+/// The working directory of the TeX engine, which is the same one that is used for building.
+/// The output directory (e.g. `-outdir`) does not matter.
+fn working_directory(environment: &Environment, root_path: &Path) -> Option<PathBuf> {
+    let directory = build_directory(&environment.options, root_path)?;
+    Some(environment.current_directory.join(directory))
+}
+
+/// Input paths are either absolute or relative to the working directory of the TeX engine.
+fn resolve_input(working_dir: &Path, input_path: &Path) -> PathBuf {
+    normalize(&working_dir.join(input_path))
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            _ => result.push(component),
+        };
+    }
+
+    result
+}
@@ -0,0 +1,231 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use crossbeam_channel::Sender;
+use lsp_server::Message;
+use lsp_types::{
+    notification::Progress, request::WorkDoneProgressCreate, NumberOrString, ProgressParams,
+    ProgressParamsValue, WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressCreateParams,
+    WorkDoneProgressEnd, WorkDoneProgressReport,
+};
+use regex::Regex;
+use uuid::Uuid;
+
+use crate::{
+    client::{self, ReqQueue},
+    Document, DocumentLanguage, Environment,
+};
+
+/// Finds all LaTeX and BibTeX files below the given directory.
+/// Hidden directories (e.g. `.git`) and paths matching one of the ignored patterns are skipped.
+#[must_use]
+pub fn find_files(root: &Path, ignored_patterns: &[Regex]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending_dirs = vec![root.to_path_buf()];
+    while let Some(dir) = pending_dirs.pop() {
+        for entry in fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+        {
+            let path = entry.path();
+            let is_ignored = path
+                .strip_prefix(root)
+                .ok()
+                .and_then(Path::to_str)
+                .map(|relative_path| relative_path.replace('\\', "/"))
+                .map_or(false, |relative_path| {
+                    ignored_patterns
+                        .iter()
+                        .any(|pattern| pattern.is_match(&relative_path))
+                });
+
+            if is_ignored {
+                continue;
+            }
+
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => {
+                    let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+                    if !is_hidden {
+                        pending_dirs.push(path);
+                    }
+                }
+                Ok(file_type) if file_type.is_file() => {
+                    let extension = path
+                        .extension()
+                        .and_then(|extension| extension.to_str())
+                        .map(str::to_lowercase);
+
+                    if matches!(extension.as_deref(), Some("tex" | "bib")) {
+                        files.push(path);
+                    }
+                }
+                _ => {}
+            };
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Converts a glob pattern like `build/**` or `**/*.bak.tex` into a regular expression
+/// that matches paths relative to the indexed directory.
+#[must_use]
+pub fn glob_to_regex(glob: &str) -> Option<Regex> {
+    let (glob, any_suffix) = match glob.strip_suffix("/**") {
+        Some(glob) => (glob, true),
+        None => (glob, false),
+    };
+
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    pattern.push_str("(.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            _ => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        };
+    }
+
+    if any_suffix {
+        pattern.push_str("(/.*)?");
+    }
+
+    pattern.push('$');
+    Regex::new(&pattern).ok()
+}
+
+/// Reads and parses a file that has been found by the indexer.
+#[must_use]
+pub fn parse_file(environment: &Environment, path: &Path) -> Option<Document> {
+    let language = DocumentLanguage::by_path(path)?;
+    Document::load(environment, path, language).ok()
+}
+
+/// Reports the progress of the indexer using `$/progress` notifications.
+#[derive(Debug)]
+pub struct IndexProgress {
+    supports_progress: bool,
+    lsp_sender: Sender<Message>,
+    token: NumberOrString,
+    total: usize,
+    done: AtomicUsize,
+}
+
+impl IndexProgress {
+    pub fn start(
+        supports_progress: bool,
+        req_queue: &Mutex<ReqQueue>,
+        lsp_sender: Sender<Message>,
+        total: usize,
+    ) -> Self {
+        let progress = Self {
+            supports_progress,
+            lsp_sender,
+            token: NumberOrString::String(Uuid::new_v4().to_string()),
+            total,
+            done: AtomicUsize::new(0),
+        };
+
+        if progress.supports_progress {
+            let _ = client::send_request::<WorkDoneProgressCreate>(
+                req_queue,
+                &progress.lsp_sender,
+                WorkDoneProgressCreateParams {
+                    token: progress.token.clone(),
+                },
+            );
+
+            progress.send(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+                title: "Indexing".to_string(),
+                message: Some(format!("0/{}", total)),
+                cancellable: Some(false),
+                percentage: Some(0),
+            }));
+        }
+
+        progress
+    }
+
+    /// Marks one of the files as indexed.
+    /// Returns `true` if all files have been indexed.
+    pub fn advance(&self) -> bool {
+        let done = self.done.fetch_add(1, Ordering::SeqCst) + 1;
+        if !self.supports_progress {
+            return done == self.total;
+        }
+
+        if done < self.total {
+            self.send(WorkDoneProgress::Report(WorkDoneProgressReport {
+                message: Some(format!("{}/{}", done, self.total)),
+                cancellable: Some(false),
+                percentage: Some((done * 100 / self.total) as u32),
+            }));
+        }
+
+        done == self.total
+    }
+
+    /// Ends the progress once the indexed documents have been added to the workspace.
+    pub fn finish(&self) {
+        if self.supports_progress {
+            self.send(WorkDoneProgress::End(WorkDoneProgressEnd { message: None }));
+        }
+    }
+
+    fn send(&self, value: WorkDoneProgress) {
+        let _ = client::send_notification::<Progress>(
+            &self.lsp_sender,
+            ProgressParams {
+                token: self.token.clone(),
+                value: ProgressParamsValue::WorkDone(value),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_to_regex_directory() {
+        let regex = glob_to_regex("build/**").unwrap();
+        assert!(regex.is_match("build"));
+        assert!(regex.is_match("build/main.tex"));
+        assert!(!regex.is_match("chapters/build.tex"));
+    }
+
+    #[test]
+    fn test_glob_to_regex_any_directory() {
+        let regex = glob_to_regex("**/*.bak.tex").unwrap();
+        assert!(regex.is_match("main.bak.tex"));
+        assert!(regex.is_match("chapters/intro.bak.tex"));
+        assert!(!regex.is_match("main.tex"));
+    }
+
+    #[test]
+    fn test_glob_to_regex_single_segment() {
+        let regex = glob_to_regex("draft?/*.tex").unwrap();
+        assert!(regex.is_match("draft1/main.tex"));
+        assert!(!regex.is_match("draft1/chapters/main.tex"));
+        assert!(!regex.is_match("draft/main.tex"));
+    }
+}
@@ -103,6 +103,22 @@ impl RenderedLabel {
         }
     }
 
+    /// Renders the kind and the number of the object, e.g. `Figure 3`.
+    /// Returns `None` if the number is unknown.
+    #[must_use]
+    pub fn short_reference(&self) -> Option<String> {
+        let number = self.number.as_ref()?;
+        let result = match &self.object {
+            Section { prefix, .. } => format!("{} {}", prefix, number),
+            Float { kind, .. } => format!("{} {}", kind.as_str(), number),
+            Theorem { kind, .. } => format!("{} {}", kind, number),
+            Equation => format!("Equation ({})", number),
+            EnumItem => format!("Item {}", number),
+        };
+
+        Some(result)
+    }
+
     #[must_use]
     pub fn detail(&self) -> Option<String> {
         match &self.object {
@@ -1,4 +1,6 @@
+mod cache;
 mod capabilities;
+mod check;
 pub mod citation;
 mod client;
 pub mod component_db;
@@ -9,6 +11,7 @@ pub mod distro;
 mod document;
 mod environment;
 pub mod features;
+mod indexer;
 mod label;
 mod lang_data;
 mod language;
@@ -21,7 +24,9 @@ pub mod syntax;
 mod workspace;
 
 pub use self::{
+    cache::Cache,
     capabilities::ClientCapabilitiesExt,
+    check::{CheckReport, CheckedDocument},
     document::*,
     environment::Environment,
     label::*,
@@ -32,5 +37,5 @@ pub use self::{
     options::*,
     range::RangeExt,
     server::Server,
-    workspace::{Workspace, WorkspaceEvent},
+    workspace::{Folder, Workspace, WorkspaceEvent},
 };
@@ -1,10 +1,11 @@
-use std::{env, fs::OpenOptions, io, path::PathBuf};
+use std::{env, fs::OpenOptions, io, path::PathBuf, process, sync::Arc};
 
 use anyhow::Result;
-use clap::{ArgAction, Parser};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
 use log::LevelFilter;
 use lsp_server::Connection;
-use texlab::Server;
+use lsp_types::DiagnosticSeverity;
+use texlab::{CheckReport, Environment, Server};
 
 /// An implementation of the Language Server Protocol for LaTeX
 #[derive(Debug, Parser)]
@@ -25,15 +26,72 @@ struct Opts {
     /// Print version information and exit
     #[clap(short = 'V', long)]
     version: bool,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Report the diagnostics of a project without starting the server
+    Check(CheckOpts),
+}
+
+#[derive(Debug, Parser)]
+struct CheckOpts {
+    /// The root document of the project
+    #[clap(name = "FILE", value_parser)]
+    root_file: PathBuf,
+
+    /// The format of the diagnostics
+    #[clap(long, value_enum, default_value_t = CheckFormat::Human)]
+    format: CheckFormat,
+
+    /// Run ChkTeX on all LaTeX documents
+    #[clap(long)]
+    chktex: bool,
+
+    /// Exit with a nonzero status code if there are warnings
+    #[clap(long)]
+    deny_warnings: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, ValueEnum)]
+enum CheckFormat {
+    Human,
+    Json,
 }
 
 fn main() -> Result<()> {
-    let opts = Opts::parse();
+    let mut opts = Opts::parse();
+    let command = opts.command.take();
     setup_logger(opts);
 
-    let (connection, threads) = Connection::stdio();
-    Server::with_connection(connection, env::current_dir()?, true).run()?;
-    threads.join()?;
+    match command {
+        Some(Command::Check(opts)) => check(opts),
+        None => {
+            let (connection, threads) = Connection::stdio();
+            Server::with_connection(connection, env::current_dir()?, true).run()?;
+            threads.join()?;
+            Ok(())
+        }
+    }
+}
+
+fn check(opts: CheckOpts) -> Result<()> {
+    let environment = Environment::new(Arc::new(env::current_dir()?));
+    let report = CheckReport::new(environment, &opts.root_file, opts.chktex)?;
+    match opts.format {
+        CheckFormat::Human => println!("{}", report),
+        CheckFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    };
+
+    let has_errors = report.count(DiagnosticSeverity::ERROR) > 0
+        || (opts.deny_warnings && report.count(DiagnosticSeverity::WARNING) > 0);
+
+    if has_errors {
+        process::exit(1);
+    }
 
     Ok(())
 }
@@ -10,6 +10,7 @@ pub struct Options {
     pub root_directory: Option<PathBuf>,
     pub aux_directory: Option<PathBuf>,
     pub bibtex_formatter: BibtexFormatter,
+    pub bibtex_formatting: BibtexFormattingOptions,
     pub latex_formatter: LatexFormatter,
     pub formatter_line_length: Option<i32>,
     pub diagnostics: DiagnosticsOptions,
@@ -18,6 +19,8 @@ pub struct Options {
     pub chktex: ChktexOptions,
     pub latexindent: LatexindentOptions,
     pub forward_search: ForwardSearchOptions,
+    pub inlay_hints: InlayHintOptions,
+    pub indexing: IndexingOptions,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
@@ -42,6 +45,63 @@ impl Default for BibtexFormatter {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct BibtexFormattingOptions {
+    pub field_order: Vec<String>,
+    pub lowercase_entry_types: bool,
+    pub lowercase_field_names: bool,
+    pub delimiters: BibtexDelimiters,
+    pub trailing_comma: bool,
+    pub align_fields: bool,
+    pub blank_lines_between_entries: Option<u32>,
+    pub sort_entries: BibtexEntrySorting,
+}
+
+impl Default for BibtexFormattingOptions {
+    fn default() -> Self {
+        Self {
+            field_order: Vec::new(),
+            lowercase_entry_types: true,
+            lowercase_field_names: false,
+            delimiters: BibtexDelimiters::default(),
+            trailing_comma: true,
+            align_fields: false,
+            blank_lines_between_entries: None,
+            sort_entries: BibtexEntrySorting::default(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BibtexDelimiters {
+    Preserve,
+    Braces,
+    Quotes,
+}
+
+impl Default for BibtexDelimiters {
+    fn default() -> Self {
+        Self::Preserve
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BibtexEntrySorting {
+    None,
+    Key,
+    Year,
+}
+
+impl Default for BibtexEntrySorting {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum LatexFormatter {
@@ -69,8 +129,10 @@ pub struct LatexindentOptions {
 pub struct BuildOptions {
     pub executable: BuildExecutable,
     pub args: BuildArgs,
+    pub continuous_args: Option<ContinuousBuildArgs>,
     pub on_save: bool,
     pub forward_search_after: bool,
+    pub allow_program_override: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
@@ -98,6 +160,18 @@ impl Default for BuildArgs {
     }
 }
 
+/// The arguments that start a continuous build.
+/// The defaults only apply to `latexmk`.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ContinuousBuildArgs(pub Vec<String>);
+
+impl Default for ContinuousBuildArgs {
+    fn default() -> Self {
+        Self(vec!["-pvc".to_string(), "-view=none".to_string()])
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(default)]
@@ -114,6 +188,33 @@ pub struct ForwardSearchOptions {
     pub args: Option<Vec<String>>,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct InlayHintOptions {
+    pub label_references: bool,
+    pub citations: bool,
+    pub section_numbers: bool,
+}
+
+impl Default for InlayHintOptions {
+    fn default() -> Self {
+        Self {
+            label_references: true,
+            citations: true,
+            section_numbers: true,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct IndexingOptions {
+    pub enabled: bool,
+    pub ignored_patterns: Vec<String>,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(default)]
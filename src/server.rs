@@ -8,7 +8,8 @@ use crossbeam_channel::{Receiver, Sender};
 use log::{error, info, warn};
 use lsp_server::{Connection, Message, RequestId};
 use lsp_types::{notification::*, request::*, *};
-use rowan::ast::AstNode;
+use rowan::{ast::AstNode, TextRange};
+use rustc_hash::FxHashSet;
 use serde::Serialize;
 use threadpool::ThreadPool;
 
@@ -17,26 +18,35 @@ use crate::{
     client::{send_notification, send_request, ReqQueue},
     component_db::COMPONENT_DATABASE,
     debouncer,
-    diagnostics::DiagnosticManager,
+    diagnostics::{DiagnosticManager, DiagnosticReport},
     dispatch::{NotificationDispatcher, RequestDispatcher},
     distro::Distribution,
     features::{
-        execute_command, find_all_references, find_document_highlights, find_document_links,
-        find_document_symbols, find_foldings, find_hover, find_workspace_symbols,
-        format_source_code, goto_definition, prepare_rename_all, rename_all, BuildEngine,
-        BuildParams, BuildResult, BuildStatus, CompletionItemData, FeatureRequest,
-        ForwardSearchResult, ForwardSearchStatus,
+        execute_command, execute_synctex_forward, execute_synctex_inverse, find_all_references,
+        find_code_actions, find_color_presentations, find_document_colors,
+        find_document_highlights, find_document_links, find_document_symbols, find_foldings,
+        find_hover, find_inlay_hints, find_linked_editing_ranges, find_selection_ranges,
+        find_semantic_tokens_full, find_semantic_tokens_range, find_signature_help,
+        find_workspace_symbols, format_on_type, format_source_code, format_source_code_range,
+        goto_definition, prepare_rename_all, rename_all, semantic_tokens_legend, BuildEngine,
+        BuildParams, BuildResult, BuildStatus, CompletionItemData, ContinuousBuildStatus,
+        FeatureRequest, ForwardSearchResult, ForwardSearchStatus, SyncTexForwardResult,
+        SyncTexInverseParams,
     },
+    indexer::{self, IndexProgress},
     syntax::bibtex,
-    ClientCapabilitiesExt, Document, DocumentData, DocumentLanguage, Environment, LineIndex,
+    Cache, ClientCapabilitiesExt, Document, DocumentData, DocumentLanguage, Environment,
     LineIndexExt, Options, Workspace, WorkspaceEvent,
 };
 
 #[derive(Debug)]
 enum InternalMessage {
     SetDistro(Distribution),
-    SetOptions(Arc<Options>),
+    SetOptions(Arc<Options>, Vec<(Url, Arc<Options>)>),
     FileEvent(notify::Event),
+    ReloadBuildLogs(Vec<PathBuf>),
+    IndexDocument(Document),
+    IndexingFinished(Arc<IndexProgress>),
 }
 
 #[derive(Clone)]
@@ -49,6 +59,7 @@ pub struct Server {
     diagnostic_tx: debouncer::Sender<Workspace>,
     diagnostic_manager: DiagnosticManager,
     pool: Arc<Mutex<ThreadPool>>,
+    indexed_dirs: Arc<Mutex<FxHashSet<PathBuf>>>,
     load_resolver: bool,
     build_engine: Arc<BuildEngine>,
 }
@@ -59,11 +70,20 @@ impl Server {
         current_dir: PathBuf,
         load_resolver: bool,
     ) -> Self {
-        let req_queue = Arc::default();
-        let workspace = Workspace::new(Environment::new(Arc::new(current_dir)));
+        let req_queue: Arc<Mutex<ReqQueue>> = Arc::default();
+        let mut environment = Environment::new(Arc::new(current_dir));
+        if load_resolver {
+            environment.cache = Cache::user().map(Arc::new);
+        }
+
+        let workspace = Workspace::new(environment);
         let (internal_tx, internal_rx) = crossbeam_channel::unbounded();
         let diagnostic_manager = DiagnosticManager::default();
-        let diagnostic_tx = create_debouncer(connection.sender.clone(), diagnostic_manager.clone());
+        let diagnostic_tx = create_debouncer(
+            connection.sender.clone(),
+            Arc::clone(&req_queue),
+            diagnostic_manager.clone(),
+        );
         Self {
             connection: Arc::new(connection),
             internal_tx,
@@ -73,6 +93,7 @@ impl Server {
             diagnostic_tx,
             diagnostic_manager,
             pool: Arc::new(Mutex::new(threadpool::Builder::new().build())),
+            indexed_dirs: Arc::default(),
             load_resolver,
             build_engine: Arc::default(),
         }
@@ -124,6 +145,11 @@ impl Server {
             })),
             document_highlight_provider: Some(OneOf::Left(true)),
             document_formatting_provider: Some(OneOf::Left(true)),
+            document_range_formatting_provider: Some(OneOf::Left(true)),
+            document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+                first_trigger_character: "\n".into(),
+                more_trigger_character: Some(vec!["}".into(), " ".into()]),
+            }),
             execute_command_provider: Some(ExecuteCommandOptions {
                 commands: vec![
                     "texlab.cleanAuxiliary".into(),
@@ -131,6 +157,48 @@ impl Server {
                 ],
                 ..Default::default()
             }),
+            code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+                code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                resolve_provider: None,
+                work_done_progress_options: WorkDoneProgressOptions::default(),
+            })),
+            semantic_tokens_provider: Some(
+                SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
+                    legend: semantic_tokens_legend(),
+                    range: Some(true),
+                    full: Some(SemanticTokensFullOptions::Bool(true)),
+                    work_done_progress_options: WorkDoneProgressOptions::default(),
+                }),
+            ),
+            inlay_hint_provider: Some(OneOf::Left(true)),
+            color_provider: Some(ColorProviderCapability::Simple(true)),
+            signature_help_provider: Some(SignatureHelpOptions {
+                trigger_characters: Some(vec!["{".into(), "[".into()]),
+                retrigger_characters: None,
+                work_done_progress_options: WorkDoneProgressOptions::default(),
+            }),
+            selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+            linked_editing_range_provider: Some(LinkedEditingRangeServerCapabilities::Simple(true)),
+            workspace: Some(WorkspaceServerCapabilities {
+                workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                    supported: Some(true),
+                    change_notifications: Some(OneOf::Left(true)),
+                }),
+                file_operations: None,
+            }),
+            diagnostic_provider: self
+                .workspace
+                .environment
+                .client_capabilities
+                .has_pull_diagnostics_support()
+                .then(|| {
+                    DiagnosticServerCapabilities::Options(DiagnosticOptions {
+                        identifier: Some("texlab".into()),
+                        inter_file_dependencies: true,
+                        workspace_diagnostics: true,
+                        work_done_progress_options: WorkDoneProgressOptions::default(),
+                    })
+                }),
             ..ServerCapabilities::default()
         }
     }
@@ -141,6 +209,9 @@ impl Server {
 
         self.workspace.environment.client_capabilities = Arc::new(params.capabilities);
         self.workspace.environment.client_info = params.client_info.map(Arc::new);
+        for folder in params.workspace_folders.into_iter().flatten() {
+            self.workspace.add_folder(folder.uri);
+        }
 
         let result = InitializeResult {
             capabilities: self.capabilities(),
@@ -154,7 +225,7 @@ impl Server {
 
         if self.load_resolver {
             self.spawn(move |server| {
-                let distro = Distribution::detect();
+                let distro = Distribution::detect(server.workspace.environment.cache.as_deref());
                 info!("Detected distribution: {}", distro.kind);
 
                 server
@@ -169,6 +240,7 @@ impl Server {
 
         self.spawn(move |server| {
             server.register_config_capability();
+            server.register_watched_files_capability();
             let _ = server.pull_config();
         });
 
@@ -217,6 +289,42 @@ impl Server {
         }
     }
 
+    fn register_watched_files_capability(&self) {
+        if self
+            .workspace
+            .environment
+            .client_capabilities
+            .has_file_watching_support()
+        {
+            let options = DidChangeWatchedFilesRegistrationOptions {
+                watchers: vec![FileSystemWatcher {
+                    glob_pattern: GlobPattern::String("**/*.{tex,bib,sty,cls,log,aux}".into()),
+                    kind: None,
+                }],
+            };
+
+            let reg = Registration {
+                id: "watched-files".to_string(),
+                method: DidChangeWatchedFiles::METHOD.to_string(),
+                register_options: Some(serde_json::to_value(options).unwrap()),
+            };
+
+            let params = RegistrationParams {
+                registrations: vec![reg],
+            };
+
+            if let Err(why) =
+                send_request::<RegisterCapability>(&self.req_queue, &self.connection.sender, params)
+            {
+                error!(
+                    "Failed to register \"{}\" notification: {}",
+                    DidChangeWatchedFiles::METHOD,
+                    why
+                );
+            }
+        }
+    }
+
     fn register_diagnostics_handler(&mut self) {
         let (event_sender, event_receiver) = crossbeam_channel::unbounded();
         let diagnostic_tx = self.diagnostic_tx.clone();
@@ -229,6 +337,11 @@ impl Server {
                         let delay = workspace.environment.options.diagnostics_delay;
                         diagnostic_tx.send(workspace, delay.0).unwrap();
                     }
+                    WorkspaceEvent::Removed(workspace, uri) => {
+                        diagnostic_manager.remove(&workspace, &uri);
+                        let delay = workspace.environment.options.diagnostics_delay;
+                        diagnostic_tx.send(workspace, delay.0).unwrap();
+                    }
                 };
             }
         });
@@ -246,23 +359,40 @@ impl Server {
             return Ok(());
         }
 
-        let params = ConfigurationParams {
-            items: vec![ConfigurationItem {
+        // The first item holds the global settings, followed by the settings of each folder.
+        let folder_uris: Vec<_> = self
+            .workspace
+            .folders
+            .iter()
+            .map(|folder| folder.uri.clone())
+            .collect();
+
+        let items = std::iter::once(None)
+            .chain(folder_uris.iter().cloned().map(Some))
+            .map(|scope_uri| ConfigurationItem {
                 section: Some("texlab".to_string()),
-                scope_uri: None,
-            }],
-        };
+                scope_uri,
+            })
+            .collect();
 
+        let params = ConfigurationParams { items };
         match send_request::<WorkspaceConfiguration>(
             &self.req_queue,
             &self.connection.sender,
             params,
         ) {
-            Ok(mut json) => {
-                let value = json.pop().expect("invalid configuration request");
-                let options = self.parse_options(value)?;
+            Ok(json) => {
+                let mut values = json.into_iter();
+                let value = values.next().expect("invalid configuration request");
+                let options = Arc::new(self.parse_options(value)?);
+
+                let mut folder_options = Vec::new();
+                for (uri, value) in folder_uris.into_iter().zip(values) {
+                    folder_options.push((uri, Arc::new(self.parse_options(value)?)));
+                }
+
                 self.internal_tx
-                    .send(InternalMessage::SetOptions(Arc::new(options)))
+                    .send(InternalMessage::SetOptions(options, folder_options))
                     .unwrap();
             }
             Err(why) => {
@@ -294,11 +424,52 @@ impl Server {
         Ok(options.unwrap_or_default())
     }
 
-    fn cancel(&self, _params: CancelParams) -> Result<()> {
+    fn cancel(&self, params: CancelParams) -> Result<()> {
+        let id = match params.id {
+            NumberOrString::Number(id) => RequestId::from(id),
+            NumberOrString::String(id) => RequestId::from(id),
+        };
+
+        self.build_engine.cancel_request(&id);
+        Ok(())
+    }
+
+    fn work_done_progress_cancel(&self, params: WorkDoneProgressCancelParams) -> Result<()> {
+        self.build_engine.cancel_progress(&params.token);
         Ok(())
     }
 
-    fn did_change_watched_files(&mut self, _params: DidChangeWatchedFilesParams) -> Result<()> {
+    fn did_change_watched_files(&mut self, params: DidChangeWatchedFilesParams) -> Result<()> {
+        for change in params.changes {
+            if change.typ == FileChangeType::DELETED {
+                self.remove_document(&change.uri)?;
+            } else if let Ok(path) = change.uri.to_file_path() {
+                let _ = self.workspace.reload(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove_document(&mut self, uri: &Url) -> Result<()> {
+        if self.workspace.remove(uri)?.is_some()
+            && !self
+                .workspace
+                .environment
+                .client_capabilities
+                .has_pull_diagnostics_support()
+        {
+            // The document is no longer part of the workspace so it will not be published again.
+            send_notification::<PublishDiagnostics>(
+                &self.connection.sender,
+                PublishDiagnosticsParams {
+                    uri: uri.clone(),
+                    version: None,
+                    diagnostics: Vec::new(),
+                },
+            )?;
+        }
+
         Ok(())
     }
 
@@ -313,14 +484,45 @@ impl Server {
                 let _ = server.pull_config();
             });
         } else {
-            let options = self.parse_options(params.settings)?;
-            self.workspace.environment.options = Arc::new(options);
+            // Without scoped settings, all workspace folders share the same options.
+            let options = Arc::new(self.parse_options(params.settings)?);
+            for folder in &mut self.workspace.folders {
+                folder.environment.options = Arc::clone(&options);
+            }
+
+            self.workspace.environment.options = options;
             self.reparse_all()?;
+            self.index_workspace();
         }
 
         Ok(())
     }
 
+    fn did_change_workspace_folders(
+        &mut self,
+        params: DidChangeWorkspaceFoldersParams,
+    ) -> Result<()> {
+        for folder in params.event.removed {
+            if let Ok(path) = folder.uri.to_file_path() {
+                self.indexed_dirs.lock().unwrap().remove(&path);
+            }
+
+            self.workspace.remove_folder(&folder.uri);
+        }
+
+        for folder in params.event.added {
+            self.workspace.add_folder(folder.uri);
+        }
+
+        self.reparse_all()?;
+        self.index_workspace();
+        self.spawn(move |server| {
+            let _ = server.pull_config();
+        });
+
+        Ok(())
+    }
+
     fn did_open(&mut self, params: DidOpenTextDocumentParams) -> Result<()> {
         let language_id = &params.text_document.language_id;
         let language = DocumentLanguage::by_language_id(language_id);
@@ -332,7 +534,13 @@ impl Server {
 
         self.workspace.viewport.insert(Arc::clone(&document.uri));
 
-        if self.workspace.environment.options.chktex.on_open_and_save {
+        if self
+            .workspace
+            .environment_for(&document.uri)
+            .options
+            .chktex
+            .on_open_and_save
+        {
             self.run_chktex(document);
         }
 
@@ -343,12 +551,9 @@ impl Server {
         let uri = Arc::new(params.text_document.uri);
         match self.workspace.documents_by_uri.get(&uri).cloned() {
             Some(old_document) => {
-                let mut text = old_document.text.to_string();
-                apply_document_edit(&mut text, params.content_changes);
-                let language = old_document.data.language();
                 let new_document =
-                    self.workspace
-                        .open(Arc::clone(&uri), Arc::new(text), language)?;
+                    old_document.edit(self.workspace.environment_for(&uri), params.content_changes);
+                let new_document = self.workspace.insert(new_document)?;
                 self.workspace
                     .viewport
                     .insert(Arc::clone(&new_document.uri));
@@ -366,7 +571,7 @@ impl Server {
                     ),
                 );
 
-                if self.workspace.environment.options.chktex.on_edit {
+                if self.workspace.environment_for(&uri).options.chktex.on_edit {
                     self.run_chktex(new_document);
                 };
             }
@@ -388,7 +593,13 @@ impl Server {
             .workspace
             .documents_by_uri
             .get(&uri)
-            .filter(|_| self.workspace.environment.options.build.on_save)
+            .filter(|document| {
+                self.workspace
+                    .environment_for(&document.uri)
+                    .options
+                    .build
+                    .on_save
+            })
             .map(|document| {
                 self.feature_request(
                     Arc::clone(&document.uri),
@@ -398,10 +609,16 @@ impl Server {
                 )
             })
         {
+            let handle = self.build_engine.register(None);
             self.spawn(move |server| {
                 server
                     .build_engine
-                    .build(request, &server.req_queue, &server.connection.sender)
+                    .build(
+                        request,
+                        &handle,
+                        &server.req_queue,
+                        &server.connection.sender,
+                    )
                     .unwrap_or_else(|why| {
                         error!("Build failed: {}", why);
                         BuildResult {
@@ -415,7 +632,13 @@ impl Server {
             .workspace
             .documents_by_uri
             .get(&uri)
-            .filter(|_| self.workspace.environment.options.chktex.on_open_and_save)
+            .filter(|document| {
+                self.workspace
+                    .environment_for(&document.uri)
+                    .options
+                    .chktex
+                    .on_open_and_save
+            })
             .cloned()
         {
             self.run_chktex(document);
@@ -622,12 +845,94 @@ impl Server {
         Ok(())
     }
 
+    fn linked_editing_range(&self, id: RequestId, params: LinkedEditingRangeParams) -> Result<()> {
+        let uri = Arc::new(
+            params
+                .text_document_position_params
+                .text_document
+                .uri
+                .clone(),
+        );
+        self.handle_feature_request(id, params, uri, find_linked_editing_ranges)?;
+        Ok(())
+    }
+
+    fn document_color(&self, id: RequestId, params: DocumentColorParams) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone());
+        self.handle_feature_request(id, params, uri, find_document_colors)?;
+        Ok(())
+    }
+
+    fn color_presentation(&self, id: RequestId, params: ColorPresentationParams) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone());
+        self.handle_feature_request(id, params, uri, find_color_presentations)?;
+        Ok(())
+    }
+
+    fn signature_help(&self, id: RequestId, params: SignatureHelpParams) -> Result<()> {
+        let uri = Arc::new(
+            params
+                .text_document_position_params
+                .text_document
+                .uri
+                .clone(),
+        );
+        self.handle_feature_request(id, params, uri, find_signature_help)?;
+        Ok(())
+    }
+
+    fn selection_range(&self, id: RequestId, params: SelectionRangeParams) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone());
+        self.handle_feature_request(id, params, uri, find_selection_ranges)?;
+        Ok(())
+    }
+
     fn formatting(&self, id: RequestId, params: DocumentFormattingParams) -> Result<()> {
         let uri = Arc::new(params.text_document.uri.clone());
         self.handle_feature_request(id, params, uri, format_source_code)?;
         Ok(())
     }
 
+    fn range_formatting(&self, id: RequestId, params: DocumentRangeFormattingParams) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone());
+        self.handle_feature_request(id, params, uri, format_source_code_range)?;
+        Ok(())
+    }
+
+    fn on_type_formatting(
+        &self,
+        id: RequestId,
+        params: DocumentOnTypeFormattingParams,
+    ) -> Result<()> {
+        let uri = Arc::new(params.text_document_position.text_document.uri.clone());
+
+        // Spaces are typed all the time but only the one after `\item` re-indents the line.
+        if params.ch == " " {
+            let position = params.text_document_position.position;
+            let follows_item =
+                self.workspace
+                    .documents_by_uri
+                    .get(&uri)
+                    .map_or(false, |document| {
+                        let line_start = document
+                            .line_index
+                            .offset_lsp(Position::new(position.line, 0));
+                        let cursor = document.line_index.offset_lsp(position);
+                        line_start <= cursor
+                            && document.text[TextRange::new(line_start, cursor)].trim() == "\\item"
+                    });
+
+            if !follows_item {
+                let response = lsp_server::Response::new_ok(id, Option::<Vec<TextEdit>>::None);
+                self.connection.sender.send(response.into())?;
+                return Ok(());
+            }
+        }
+
+        self.handle_feature_request(id, params, uri, format_on_type)?;
+        Ok(())
+    }
+
     fn execute_command(&self, id: RequestId, params: ExecuteCommandParams) -> Result<()> {
         self.spawn(move |server| {
             let result = execute_command(&server.workspace, &params.command, params.arguments);
@@ -646,28 +951,239 @@ impl Server {
         Ok(())
     }
 
+    fn code_action(&self, id: RequestId, params: CodeActionParams) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone());
+        self.handle_feature_request(id, params, uri, find_code_actions)?;
+        Ok(())
+    }
+
+    fn semantic_tokens_full(&self, id: RequestId, params: SemanticTokensParams) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone());
+        self.handle_feature_request(id, params, uri, find_semantic_tokens_full)?;
+        Ok(())
+    }
+
     fn semantic_tokens_range(
         &self,
-        _id: RequestId,
-        _params: SemanticTokensRangeParams,
+        id: RequestId,
+        params: SemanticTokensRangeParams,
     ) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone());
+        self.handle_feature_request(id, params, uri, find_semantic_tokens_range)?;
+        Ok(())
+    }
+
+    fn document_diagnostic(&self, id: RequestId, params: DocumentDiagnosticParams) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri);
+        self.spawn(move |mut server| {
+            // The client may ask for the diagnostics of a document that has not been opened yet.
+            // The document is loaded here and handed to the main loop like an indexed document.
+            if !server.workspace.documents_by_uri.contains_key(&uri) {
+                if let Ok(path) = uri.to_file_path() {
+                    if let Ok(Some(document)) = server.workspace.load(path) {
+                        let _ = server
+                            .internal_tx
+                            .send(InternalMessage::IndexDocument(document));
+                    }
+                }
+            }
+
+            let response = match server.workspace.documents_by_uri.get(&uri) {
+                Some(document) => {
+                    server
+                        .diagnostic_manager
+                        .push_syntax(&server.workspace, &document.uri);
+
+                    let report = server.diagnostic_manager.report(
+                        &server.workspace,
+                        &uri,
+                        params.previous_result_id.as_deref(),
+                    );
+
+                    let report = match report {
+                        DiagnosticReport::Full { result_id, items } => {
+                            DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+                                related_documents: None,
+                                full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                                    result_id: Some(result_id),
+                                    items,
+                                },
+                            })
+                        }
+                        DiagnosticReport::Unchanged { result_id } => {
+                            DocumentDiagnosticReport::Unchanged(
+                                RelatedUnchangedDocumentDiagnosticReport {
+                                    related_documents: None,
+                                    unchanged_document_diagnostic_report:
+                                        UnchangedDocumentDiagnosticReport { result_id },
+                                },
+                            )
+                        }
+                    };
+
+                    lsp_server::Response::new_ok(id, DocumentDiagnosticReportResult::Report(report))
+                }
+                None => {
+                    let code = lsp_server::ErrorCode::InvalidRequest as i32;
+                    let message = "unknown document".to_string();
+                    lsp_server::Response::new_err(id, code, message)
+                }
+            };
+
+            server.connection.sender.send(response.into()).unwrap();
+        });
+
+        Ok(())
+    }
+
+    fn workspace_diagnostic(&self, id: RequestId, params: WorkspaceDiagnosticParams) -> Result<()> {
+        self.spawn(move |server| {
+            let workspace = &server.workspace;
+            server.diagnostic_manager.push_all(workspace);
+
+            let mut items = Vec::new();
+            for document in workspace.documents_by_uri.values() {
+                if matches!(document.data, DocumentData::BuildLog(_)) {
+                    continue;
+                }
+
+                let previous_result_id = params
+                    .previous_result_ids
+                    .iter()
+                    .find(|previous| previous.uri == *document.uri)
+                    .map(|previous| previous.value.as_str());
+
+                let uri = document.uri.as_ref().clone();
+                let report =
+                    server
+                        .diagnostic_manager
+                        .report(workspace, &document.uri, previous_result_id);
+
+                items.push(match report {
+                    DiagnosticReport::Full { result_id, items } => {
+                        WorkspaceDocumentDiagnosticReport::Full(
+                            WorkspaceFullDocumentDiagnosticReport {
+                                uri,
+                                version: None,
+                                full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                                    result_id: Some(result_id),
+                                    items,
+                                },
+                            },
+                        )
+                    }
+                    DiagnosticReport::Unchanged { result_id } => {
+                        WorkspaceDocumentDiagnosticReport::Unchanged(
+                            WorkspaceUnchangedDocumentDiagnosticReport {
+                                uri,
+                                version: None,
+                                unchanged_document_diagnostic_report:
+                                    UnchangedDocumentDiagnosticReport { result_id },
+                            },
+                        )
+                    }
+                });
+            }
+
+            let result =
+                WorkspaceDiagnosticReportResult::Report(WorkspaceDiagnosticReport { items });
+            server
+                .connection
+                .sender
+                .send(lsp_server::Response::new_ok(id, result).into())
+                .unwrap();
+        });
+
+        Ok(())
+    }
+
+    fn inlay_hints(&self, id: RequestId, params: InlayHintParams) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone());
+        self.handle_feature_request(id, params, uri, find_inlay_hints)?;
         Ok(())
     }
 
     fn build(&self, id: RequestId, params: BuildParams) -> Result<()> {
         let uri = Arc::new(params.text_document.uri.clone());
-        let lsp_sender = self.connection.sender.clone();
-        let req_queue = Arc::clone(&self.req_queue);
-        let build_engine = Arc::clone(&self.build_engine);
-        self.handle_feature_request(id, params, uri, move |request| {
-            build_engine
-                .build(request, &req_queue, &lsp_sender)
+        // The handle is registered right away, so that queued builds can be cancelled.
+        let handle = self.build_engine.register(Some(id.clone()));
+        self.spawn(move |server| {
+            let request = server.feature_request(uri, params);
+            if request.workspace.documents_by_uri.is_empty() {
+                server.build_engine.unregister(&handle);
+                let code = lsp_server::ErrorCode::InvalidRequest as i32;
+                let message = "unknown document".to_string();
+                let response = lsp_server::Response::new_err(id, code, message);
+                server.connection.sender.send(response.into()).unwrap();
+                return;
+            }
+
+            let result = server
+                .build_engine
+                .build(
+                    request,
+                    &handle,
+                    &server.req_queue,
+                    &server.connection.sender,
+                )
                 .unwrap_or_else(|why| {
                     error!("Build failed: {}", why);
                     BuildResult {
                         status: BuildStatus::FAILURE,
                     }
+                });
+
+            server
+                .connection
+                .sender
+                .send(lsp_server::Response::new_ok(id, result).into())
+                .unwrap();
+        });
+
+        Ok(())
+    }
+
+    fn start_continuous_build(&self, id: RequestId, params: BuildParams) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone());
+        let lsp_sender = self.connection.sender.clone();
+        let internal_tx = self.internal_tx.clone();
+        let build_engine = Arc::clone(&self.build_engine);
+        self.handle_feature_request(id, params, uri, move |request| {
+            let uri = request.uri.as_ref().clone();
+            build_engine
+                .start_continuous(request, &lsp_sender, move |paths| {
+                    let _ = internal_tx.send(InternalMessage::ReloadBuildLogs(paths));
                 })
+                .unwrap_or_else(|why| {
+                    error!("Continuous build failed: {}", why);
+                    let _ = send_notification::<ShowMessage>(
+                        &lsp_sender,
+                        ShowMessageParams {
+                            message: format!("Unable to start the continuous build: {why}"),
+                            typ: MessageType::ERROR,
+                        },
+                    );
+
+                    ContinuousBuildStatus::stopped(uri)
+                })
+        })?;
+        Ok(())
+    }
+
+    fn stop_continuous_build(&self, id: RequestId, params: BuildParams) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone());
+        let build_engine = Arc::clone(&self.build_engine);
+        self.handle_feature_request(id, params, uri, move |request| {
+            build_engine.stop_continuous(request)
+        })?;
+        Ok(())
+    }
+
+    fn continuous_build_status(&self, id: RequestId, params: BuildParams) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone());
+        let build_engine = Arc::clone(&self.build_engine);
+        self.handle_feature_request(id, params, uri, move |request| {
+            build_engine.continuous_status(request)
         })?;
         Ok(())
     }
@@ -682,6 +1198,24 @@ impl Server {
         Ok(())
     }
 
+    fn synctex_forward(&self, id: RequestId, params: TextDocumentPositionParams) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone());
+        self.handle_feature_request(id, params, uri, execute_synctex_forward)?;
+        Ok(())
+    }
+
+    fn synctex_inverse(&self, id: RequestId, params: SyncTexInverseParams) -> Result<()> {
+        self.spawn(move |server| {
+            let result = execute_synctex_inverse(&server.workspace, &params);
+            server
+                .connection
+                .sender
+                .send(lsp_server::Response::new_ok(id, result).into())
+                .unwrap();
+        });
+        Ok(())
+    }
+
     fn reparse_all(&mut self) -> Result<()> {
         for document in self
             .workspace
@@ -702,9 +1236,100 @@ impl Server {
             None => self.workspace.watch_dir(&PathBuf::from(".")),
         };
 
+        for folder in &self.workspace.folders {
+            let environment = &folder.environment;
+            let path = match &environment.options.aux_directory {
+                Some(path) => environment.current_directory.join(path),
+                None => environment.current_directory.to_path_buf(),
+            };
+
+            self.workspace.watch_dir(&path);
+        }
+
         Ok(())
     }
 
+    /// Loads all LaTeX and BibTeX documents of the workspace folders in the background
+    /// if indexing is enabled. Every folder is indexed only once.
+    fn index_workspace(&self) {
+        let environments = if self.workspace.folders.is_empty() {
+            vec![self.workspace.environment.clone()]
+        } else {
+            self.workspace
+                .folders
+                .iter()
+                .map(|folder| folder.environment.clone())
+                .collect()
+        };
+
+        for environment in environments
+            .into_iter()
+            .filter(|environment| environment.options.indexing.enabled)
+        {
+            if self
+                .indexed_dirs
+                .lock()
+                .unwrap()
+                .insert(environment.current_directory.to_path_buf())
+            {
+                self.spawn(move |server| server.index_directory(environment));
+            }
+        }
+    }
+
+    fn index_directory(&self, environment: Environment) {
+        let ignored_patterns: Vec<_> = environment
+            .options
+            .indexing
+            .ignored_patterns
+            .iter()
+            .filter_map(|glob| indexer::glob_to_regex(glob))
+            .collect();
+
+        let files = indexer::find_files(&environment.current_directory, &ignored_patterns);
+        info!(
+            "Indexing {} files in {}",
+            files.len(),
+            environment.current_directory.display()
+        );
+
+        if files.is_empty() {
+            return;
+        }
+
+        let progress = Arc::new(IndexProgress::start(
+            environment
+                .client_capabilities
+                .has_work_done_progress_support(),
+            &self.req_queue,
+            self.connection.sender.clone(),
+            files.len(),
+        ));
+
+        let environment = Arc::new(environment);
+        for path in files {
+            let environment = Arc::clone(&environment);
+            let progress = Arc::clone(&progress);
+            self.spawn(move |server| {
+                if let Some(document) = indexer::parse_file(&environment, &path) {
+                    let _ = server
+                        .internal_tx
+                        .send(InternalMessage::IndexDocument(document));
+                }
+
+                if progress.advance() {
+                    if let Some(cache) = &environment.cache {
+                        cache.flush();
+                    }
+
+                    let _ = server
+                        .internal_tx
+                        .send(InternalMessage::IndexingFinished(progress));
+                }
+            });
+        }
+    }
+
     fn process_messages(&mut self) -> Result<()> {
         loop {
             crossbeam_channel::select! {
@@ -723,7 +1348,7 @@ impl Server {
                                 .on::<DocumentSymbolRequest, _>(|id, params| {
                                     self.document_symbols(id, params)
                                 })?
-                                .on::<WorkspaceSymbol, _>(|id, params| self.workspace_symbols(id, params))?
+                                .on::<WorkspaceSymbolRequest, _>(|id, params| self.workspace_symbols(id, params))?
                                 .on::<Completion, _>(|id, params| {
                                     self.completion(id, params)?;
                                     Ok(())
@@ -740,15 +1365,64 @@ impl Server {
                                 .on::<DocumentHighlightRequest, _>(|id, params| {
                                     self.document_highlight(id, params)
                                 })?
+                                .on::<DocumentColor, _>(|id, params| {
+                                    self.document_color(id, params)
+                                })?
+                                .on::<ColorPresentationRequest, _>(|id, params| {
+                                    self.color_presentation(id, params)
+                                })?
+                                .on::<SignatureHelpRequest, _>(|id, params| {
+                                    self.signature_help(id, params)
+                                })?
+                                .on::<SelectionRangeRequest, _>(|id, params| {
+                                    self.selection_range(id, params)
+                                })?
+                                .on::<LinkedEditingRange, _>(|id, params| {
+                                    self.linked_editing_range(id, params)
+                                })?
                                 .on::<Formatting, _>(|id, params| self.formatting(id, params))?
+                                .on::<RangeFormatting, _>(|id, params| {
+                                    self.range_formatting(id, params)
+                                })?
+                                .on::<OnTypeFormatting, _>(|id, params| {
+                                    self.on_type_formatting(id, params)
+                                })?
                                 .on::<BuildRequest, _>(|id, params| self.build(id, params))?
+                                .on::<StartContinuousBuildRequest, _>(|id, params| {
+                                    self.start_continuous_build(id, params)
+                                })?
+                                .on::<StopContinuousBuildRequest, _>(|id, params| {
+                                    self.stop_continuous_build(id, params)
+                                })?
+                                .on::<ContinuousBuildStatusRequest, _>(|id, params| {
+                                    self.continuous_build_status(id, params)
+                                })?
                                 .on::<ForwardSearchRequest, _>(|id, params| {
                                     self.forward_search(id, params)
                                 })?
+                                .on::<SyncTexForwardRequest, _>(|id, params| {
+                                    self.synctex_forward(id, params)
+                                })?
+                                .on::<SyncTexInverseRequest, _>(|id, params| {
+                                    self.synctex_inverse(id, params)
+                                })?
                                 .on::<ExecuteCommand,_>(|id, params| self.execute_command(id, params))?
+                                .on::<CodeActionRequest, _>(|id, params| self.code_action(id, params))?
+                                .on::<SemanticTokensFullRequest, _>(|id, params| {
+                                    self.semantic_tokens_full(id, params)
+                                })?
                                 .on::<SemanticTokensRangeRequest, _>(|id, params| {
                                     self.semantic_tokens_range(id, params)
                                 })?
+                                .on::<InlayHintRequest, _>(|id, params| {
+                                    self.inlay_hints(id, params)
+                                })?
+                                .on::<DocumentDiagnosticRequest, _>(|id, params| {
+                                    self.document_diagnostic(id, params)
+                                })?
+                                .on::<WorkspaceDiagnosticRequest, _>(|id, params| {
+                                    self.workspace_diagnostic(id, params)
+                                })?
                                 .default()
                             {
                                 self.connection.sender.send(response.into())?;
@@ -757,12 +1431,18 @@ impl Server {
                         Message::Notification(notification) => {
                             NotificationDispatcher::new(notification)
                                 .on::<Cancel, _>(|params| self.cancel(params))?
+                                .on::<WorkDoneProgressCancel, _>(|params| {
+                                    self.work_done_progress_cancel(params)
+                                })?
                                 .on::<DidChangeConfiguration, _>(|params| {
                                     self.did_change_configuration(params)
                                 })?
                                 .on::<DidChangeWatchedFiles, _>(|params| {
                                     self.did_change_watched_files(params)
                                 })?
+                                .on::<DidChangeWorkspaceFolders, _>(|params| {
+                                    self.did_change_workspace_folders(params)
+                                })?
                                 .on::<DidOpenTextDocument, _>(|params| self.did_open(params))?
                                 .on::<DidChangeTextDocument, _>(|params| self.did_change(params))?
                                 .on::<DidSaveTextDocument, _>(|params| self.did_save(params))?
@@ -784,12 +1464,29 @@ impl Server {
                 recv(&self.internal_rx) -> msg => {
                     match msg? {
                         InternalMessage::SetDistro(distro) => {
-                            self.workspace.environment.resolver = Arc::new(distro.resolver);
+                            let resolver = Arc::new(distro.resolver);
+                            for folder in &mut self.workspace.folders {
+                                folder.environment.resolver = Arc::clone(&resolver);
+                            }
+
+                            self.workspace.environment.resolver = resolver;
                             self.reparse_all()?;
                         }
-                        InternalMessage::SetOptions(options) => {
+                        InternalMessage::SetOptions(options, folder_options) => {
+                            for (uri, options) in folder_options {
+                                if let Some(folder) = self
+                                    .workspace
+                                    .folders
+                                    .iter_mut()
+                                    .find(|folder| folder.uri == uri)
+                                {
+                                    folder.environment.options = options;
+                                }
+                            }
+
                             self.workspace.environment.options = options;
                             self.reparse_all()?;
+                            self.index_workspace();
                         }
                         InternalMessage::FileEvent(ev) => {
                             match ev.kind {
@@ -802,7 +1499,7 @@ impl Server {
                                     for uri in
                                         ev.paths.iter().flat_map(Url::from_file_path)
                                     {
-                                        self.workspace.documents_by_uri.remove(&uri);
+                                        self.remove_document(&uri)?;
                                     }
                                 }
                                 notify::EventKind::Any
@@ -810,6 +1507,30 @@ impl Server {
                                 | notify::EventKind::Other => {}
                             };
                         }
+                        InternalMessage::ReloadBuildLogs(paths) => {
+                            for path in paths {
+                                let _ = self.workspace.reload(path);
+                            }
+                        }
+                        InternalMessage::IndexDocument(document) => {
+                            // Documents that have been opened in the meantime are more recent.
+                            if !self.workspace.documents_by_uri.contains_key(&document.uri) {
+                                self.workspace.insert_quietly(document)?;
+                            }
+                        }
+                        InternalMessage::IndexingFinished(progress) => {
+                            progress.finish();
+
+                            // The diagnostics are updated once instead of after every indexed document.
+                            self.spawn(|server| {
+                                server.diagnostic_manager.push_all(&server.workspace);
+                                let delay = server.workspace.environment.options.diagnostics_delay;
+                                server
+                                    .diagnostic_tx
+                                    .send(server.workspace, delay.0)
+                                    .unwrap();
+                            });
+                        }
                     };
                 }
             };
@@ -819,19 +1540,27 @@ impl Server {
     pub fn run(mut self) -> Result<()> {
         self.initialize()?;
         self.process_messages()?;
+        self.build_engine.stop_all_continuous();
         self.pool.lock().unwrap().join();
+        if let Some(cache) = &self.workspace.environment.cache {
+            cache.flush();
+        }
+
         Ok(())
     }
 }
 
 fn create_debouncer(
     lsp_sender: Sender<Message>,
+    req_queue: Arc<Mutex<ReqQueue>>,
     diagnostic_manager: DiagnosticManager,
 ) -> debouncer::Sender<Workspace> {
     let (tx, rx) = debouncer::unbounded();
     std::thread::spawn(move || {
         while let Ok(workspace) = rx.recv() {
-            if let Err(why) = publish_diagnostics(&lsp_sender, &diagnostic_manager, &workspace) {
+            if let Err(why) =
+                publish_diagnostics(&lsp_sender, &req_queue, &diagnostic_manager, &workspace)
+            {
                 warn!("Failed to publish diagnostics: {}", why);
             }
         }
@@ -842,9 +1571,23 @@ fn create_debouncer(
 
 fn publish_diagnostics(
     lsp_sender: &Sender<lsp_server::Message>,
+    req_queue: &Mutex<ReqQueue>,
     diagnostic_manager: &DiagnosticManager,
     workspace: &Workspace,
 ) -> Result<()> {
+    let client_capabilities = &workspace.environment.client_capabilities;
+    if client_capabilities.has_pull_diagnostics_support() {
+        // The client pulls the diagnostics by itself but it does not know when the diagnostics
+        // of other documents change (e.g. references, ChkTeX or build logs).
+        if client_capabilities.has_diagnostic_refresh_support()
+            && diagnostic_manager.needs_refresh(workspace)
+        {
+            send_request::<WorkspaceDiagnosticRefresh>(req_queue, lsp_sender, ())?;
+        }
+
+        return Ok(());
+    }
+
     for document in workspace.documents_by_uri.values() {
         if matches!(document.data, DocumentData::BuildLog(_)) {
             continue;
@@ -864,21 +1607,6 @@ fn publish_diagnostics(
     Ok(())
 }
 
-fn apply_document_edit(old_text: &mut String, changes: Vec<TextDocumentContentChangeEvent>) {
-    for change in changes {
-        let line_index = LineIndex::new(old_text);
-        match change.range {
-            Some(range) => {
-                let range = std::ops::Range::<usize>::from(line_index.offset_lsp_range(range));
-                old_text.replace_range(range, &change.text);
-            }
-            None => {
-                *old_text = change.text;
-            }
-        };
-    }
-}
-
 struct BuildRequest;
 
 impl lsp_types::request::Request for BuildRequest {
@@ -889,6 +1617,36 @@ impl lsp_types::request::Request for BuildRequest {
     const METHOD: &'static str = "textDocument/build";
 }
 
+struct StartContinuousBuildRequest;
+
+impl lsp_types::request::Request for StartContinuousBuildRequest {
+    type Params = BuildParams;
+
+    type Result = ContinuousBuildStatus;
+
+    const METHOD: &'static str = "textDocument/startContinuousBuild";
+}
+
+struct StopContinuousBuildRequest;
+
+impl lsp_types::request::Request for StopContinuousBuildRequest {
+    type Params = BuildParams;
+
+    type Result = ContinuousBuildStatus;
+
+    const METHOD: &'static str = "textDocument/stopContinuousBuild";
+}
+
+struct ContinuousBuildStatusRequest;
+
+impl lsp_types::request::Request for ContinuousBuildStatusRequest {
+    type Params = BuildParams;
+
+    type Result = ContinuousBuildStatus;
+
+    const METHOD: &'static str = "textDocument/continuousBuildStatus";
+}
+
 struct ForwardSearchRequest;
 
 impl lsp_types::request::Request for ForwardSearchRequest {
@@ -898,3 +1656,23 @@ impl lsp_types::request::Request for ForwardSearchRequest {
 
     const METHOD: &'static str = "textDocument/forwardSearch";
 }
+
+struct SyncTexForwardRequest;
+
+impl lsp_types::request::Request for SyncTexForwardRequest {
+    type Params = TextDocumentPositionParams;
+
+    type Result = Option<SyncTexForwardResult>;
+
+    const METHOD: &'static str = "textDocument/synctexForward";
+}
+
+struct SyncTexInverseRequest;
+
+impl lsp_types::request::Request for SyncTexInverseRequest {
+    type Params = SyncTexInverseParams;
+
+    type Result = Option<Location>;
+
+    const METHOD: &'static str = "workspace/synctexInverse";
+}
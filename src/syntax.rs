@@ -1,3 +1,4 @@
 pub mod bibtex;
 pub mod build_log;
 pub mod latex;
+pub mod synctex;
@@ -8,7 +8,7 @@ pub use self::{
     analysis::*,
     cst::*,
     kind::SyntaxKind::{self, *},
-    parser::{parse, Parse},
+    parser::{parse, reparse, Parse},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
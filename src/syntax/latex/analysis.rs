@@ -6,6 +6,7 @@ mod graphics_path;
 mod implicit_link;
 mod label_name;
 mod label_number;
+mod magic_comment;
 mod theorem;
 mod types;
 
@@ -20,11 +21,13 @@ use self::{
     implicit_link::analyze_implicit_links,
     label_name::analyze_label_name,
     label_number::analyze_label_number,
+    magic_comment::analyze_magic_comments,
     theorem::analyze_theorem_definition,
 };
 
 pub fn analyze(context: &mut LatexAnalyzerContext, root: &latex::SyntaxNode) {
     analyze_implicit_links(context);
+    analyze_magic_comments(context, root);
     for node in root.descendants() {
         analyze_command(context, node.clone())
             .or_else(|| analyze_command_definition(context, node.clone()))
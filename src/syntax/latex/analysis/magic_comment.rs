@@ -0,0 +1,132 @@
+use std::{path::Path, sync::Arc};
+
+use crate::syntax::latex;
+
+use super::LatexAnalyzerContext;
+
+/// Magic comments are only recognized in the header of the document,
+/// i.e. before the first token that is not a comment.
+pub fn analyze_magic_comments(context: &mut LatexAnalyzerContext, root: &latex::SyntaxNode) {
+    for token in root
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .take_while(|token| {
+            matches!(
+                token.kind(),
+                latex::COMMENT | latex::WHITESPACE | latex::LINE_BREAK
+            )
+        })
+        .filter(|token| token.kind() == latex::COMMENT)
+    {
+        let (key, value) = match parse_magic_comment(token.text()) {
+            Some(comment) => comment,
+            None => continue,
+        };
+
+        let magic_comments = &mut context.extras.magic_comments;
+        if key.eq_ignore_ascii_case("root") {
+            if magic_comments.root.is_none() {
+                let mut path = value.to_string();
+                if Path::new(value).extension().is_none() {
+                    path.push_str(".tex");
+                }
+
+                magic_comments.root = context.document_uri.join(&path).ok().map(Arc::new);
+            }
+        } else if key.eq_ignore_ascii_case("program") || key.eq_ignore_ascii_case("TS-program") {
+            magic_comments
+                .program
+                .get_or_insert_with(|| value.to_string());
+        }
+    }
+}
+
+/// Parses comments of the form `% !TEX key = value`.
+fn parse_magic_comment(text: &str) -> Option<(&str, &str)> {
+    let text = text.strip_prefix('%')?.trim_start().strip_prefix('!')?;
+    let text = text.trim_start();
+    if !text.get(..3)?.eq_ignore_ascii_case("tex") {
+        return None;
+    }
+
+    let text = &text[3..];
+    if !text.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let (key, value) = text.split_once('=')?;
+    let (key, value) = (key.trim(), value.trim());
+    if key.is_empty() || value.is_empty() {
+        return None;
+    }
+
+    Some((key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Url;
+
+    use crate::{Document, DocumentLanguage, Environment};
+
+    use super::*;
+
+    fn find_program(text: &str) -> Option<String> {
+        let document = Document::parse(
+            &Environment::default(),
+            Arc::new(Url::parse("file:///main.tex").unwrap()),
+            Arc::new(text.to_string()),
+            DocumentLanguage::Latex,
+        );
+
+        let data = document.data.as_latex().unwrap();
+        data.extras.magic_comments.program.clone()
+    }
+
+    #[test]
+    fn test_header() {
+        assert_eq!(
+            find_program("% Foo\n\n% !TEX program = xelatex\n\\documentclass{article}"),
+            Some("xelatex".to_string())
+        );
+    }
+
+    #[test]
+    fn test_after_header() {
+        assert_eq!(
+            find_program("\\documentclass{article}\n% !TEX program = xelatex"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_root() {
+        assert_eq!(
+            parse_magic_comment("% !TEX root = ../main.tex"),
+            Some(("root", "../main.tex"))
+        );
+    }
+
+    #[test]
+    fn test_program_without_spaces() {
+        assert_eq!(
+            parse_magic_comment("%!TeX program=xelatex"),
+            Some(("program", "xelatex"))
+        );
+    }
+
+    #[test]
+    fn test_texshop_program() {
+        assert_eq!(
+            parse_magic_comment("% !TEX TS-program = lualatex "),
+            Some(("TS-program", "lualatex"))
+        );
+    }
+
+    #[test]
+    fn test_regular_comment() {
+        assert_eq!(parse_magic_comment("% TEX root = main.tex"), None);
+        assert_eq!(parse_magic_comment("% !TEXroot = main.tex"), None);
+        assert_eq!(parse_magic_comment("% !TEX root"), None);
+    }
+}
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use lsp_types::Url;
 use rowan::TextRange;
 use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 
 use crate::Environment;
@@ -15,7 +16,7 @@ pub struct LatexAnalyzerContext<'a> {
     pub extras: Extras,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Extras {
     pub implicit_links: ImplicitLinks,
     pub explicit_links: Vec<ExplicitLink>,
@@ -26,16 +27,24 @@ pub struct Extras {
     pub label_numbers_by_name: FxHashMap<String, String>,
     pub theorem_environments: Vec<TheoremEnvironment>,
     pub graphics_paths: FxHashSet<String>,
+    pub magic_comments: MagicComments,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Default, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Default, Hash, Serialize, Deserialize)]
 pub struct ImplicitLinks {
     pub aux: Vec<Arc<Url>>,
     pub log: Vec<Arc<Url>>,
     pub pdf: Vec<Arc<Url>>,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+/// The values of magic comments like `% !TEX root = main.tex`.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Hash, Serialize, Deserialize)]
+pub struct MagicComments {
+    pub root: Option<Arc<Url>>,
+    pub program: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ExplicitLinkKind {
     Package,
     Class,
@@ -43,7 +52,7 @@ pub enum ExplicitLinkKind {
     Bibtex,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExplicitLink {
     pub stem: SmolStr,
     pub stem_range: TextRange,
@@ -61,13 +70,13 @@ impl ExplicitLink {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Default, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Default, Hash, Serialize, Deserialize)]
 pub struct TheoremEnvironment {
     pub name: String,
     pub description: String,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Default, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Default, Hash, Serialize, Deserialize)]
 pub struct LabelName {
     pub text: SmolStr,
     pub range: TextRange,
@@ -555,6 +555,14 @@ impl CommandDefinition {
         self.syntax().children().find_map(CurlyGroupCommand::cast)
     }
 
+    pub fn argument_count(&self) -> Option<BrackGroupWord> {
+        self.syntax().children().find_map(BrackGroupWord::cast)
+    }
+
+    pub fn default_argument(&self) -> Option<BrackGroup> {
+        self.syntax().children().find_map(BrackGroup::cast)
+    }
+
     pub fn implementation(&self) -> Option<CurlyGroup> {
         self.syntax().children().find_map(CurlyGroup::cast)
     }
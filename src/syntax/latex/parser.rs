@@ -1,8 +1,9 @@
-use rowan::{GreenNode, GreenNodeBuilder};
+use rowan::{GreenNode, GreenNodeBuilder, NodeOrToken, TextRange};
 
 use super::{
     lexer::Lexer,
     SyntaxKind::{self, *},
+    SyntaxNode,
 };
 
 #[derive(Clone)]
@@ -1341,6 +1342,142 @@ pub fn parse(text: &str) -> Parse {
     Parser::new(text).parse()
 }
 
+/// Parses the document again after `range` has been replaced with `text`.
+/// Only the smallest group or environment that encloses the edit is parsed again,
+/// all other subtrees of `green` are reused.
+/// Returns `None` if the edit cannot be handled locally.
+/// In this case, the caller has to parse the whole document instead.
+pub fn reparse(green: &GreenNode, range: TextRange, text: &str) -> Option<Parse> {
+    let root = SyntaxNode::new_root(green.clone());
+    if !root.text_range().contains_range(range) {
+        return None;
+    }
+
+    // Changing the name of a command can change how the surrounding text is parsed.
+    if touches_command_name(&root, range, text) {
+        return None;
+    }
+
+    let node = match root.covering_element(range) {
+        NodeOrToken::Node(node) => node,
+        NodeOrToken::Token(token) => token.parent()?,
+    };
+
+    node.ancestors()
+        .find_map(|node| reparse_node(&node, range, text))
+}
+
+fn reparse_node(node: &SyntaxNode, range: TextRange, text: &str) -> Option<Parse> {
+    // The delimiters of the node must not be touched by the edit.
+    // Otherwise, the node might end somewhere else after the edit.
+    let inner_range = match node.kind() {
+        CURLY_GROUP => {
+            let l_curly = node.first_token().filter(|token| token.kind() == L_CURLY)?;
+            let r_curly = closing_curly(node)?;
+            TextRange::new(l_curly.text_range().end(), r_curly.text_range().start())
+        }
+        ENVIRONMENT => {
+            let begin = node
+                .first_child()
+                .filter(|child| child.kind() == BEGIN)
+                .and_then(|begin| begin.first_child())
+                .filter(|group| group.kind() == CURLY_GROUP_WORD)
+                .and_then(|group| closing_curly(&group))?;
+
+            let end = node
+                .last_child()
+                .filter(|child| child.kind() == END)
+                .and_then(|end| end.last_child())
+                .filter(|group| group.kind() == CURLY_GROUP_WORD)
+                .and_then(|group| closing_curly(&group))?;
+
+            TextRange::new(begin.text_range().end(), end.text_range().start())
+        }
+        _ => return None,
+    };
+
+    if !inner_range.contains_range(range) {
+        return None;
+    }
+
+    let mut new_text = node.text().to_string();
+    let local_range = range - node.text_range().start();
+    new_text.replace_range(std::ops::Range::<usize>::from(local_range), text);
+
+    let mut parser = Parser::new(&new_text);
+    match node.kind() {
+        CURLY_GROUP => parser.curly_group(),
+        ENVIRONMENT => parser.environment(),
+        _ => unreachable!(),
+    };
+
+    // The new node has to span the whole text,
+    // otherwise the delimiters were matched differently.
+    if parser.peek().is_some() {
+        return None;
+    }
+
+    let new_node = SyntaxNode::new_root(parser.builder.finish());
+    let is_complete = match new_node.kind() {
+        // Groups are parsed differently depending on whether they allow environments or not.
+        // We only know that all of them agree if the group does not contain any environments.
+        CURLY_GROUP => {
+            closing_curly(&new_node).is_some()
+                && !new_node.descendants_with_tokens().any(|element| {
+                    matches!(
+                        element.kind(),
+                        BEGIN_ENVIRONMENT_NAME | END_ENVIRONMENT_NAME
+                    )
+                })
+        }
+        ENVIRONMENT => new_node
+            .last_child()
+            .filter(|child| child.kind() == END)
+            .and_then(|end| end.last_child())
+            .filter(|group| group.kind() == CURLY_GROUP_WORD)
+            .and_then(|group| closing_curly(&group))
+            .is_some(),
+        _ => false,
+    };
+
+    if !is_complete {
+        return None;
+    }
+
+    let green = node.replace_with(new_node.green().into_owned());
+    Some(Parse { green })
+}
+
+/// Checks if the edit changes the name of a command, e.g. by appending to it
+/// or by inserting a backslash right in front of it.
+fn touches_command_name(root: &SyntaxNode, range: TextRange, text: &str) -> bool {
+    let is_touched = |token: &super::SyntaxToken| {
+        let token_range = token.text_range();
+        token.kind().is_command_name()
+            && ((token_range.start() < range.end() && range.start() <= token_range.end())
+                || (token_range.start() == range.end() && text.ends_with('\\')))
+    };
+
+    let is_touched_at = |offset| root.token_at_offset(offset).any(|token| is_touched(&token));
+    if is_touched_at(range.start()) || is_touched_at(range.end()) {
+        return true;
+    }
+
+    match root.covering_element(range) {
+        NodeOrToken::Node(node) => node
+            .descendants_with_tokens()
+            .filter_map(|element| element.into_token())
+            .any(|token| is_touched(&token)),
+        NodeOrToken::Token(token) => is_touched(&token),
+    }
+}
+
+fn closing_curly(node: &SyntaxNode) -> Option<super::SyntaxToken> {
+    node.children_with_tokens()
+        .filter_map(|element| element.into_token())
+        .find(|token| token.kind() == R_CURLY)
+}
+
 #[cfg(test)]
 mod tests {
     use insta::assert_debug_snapshot;
@@ -1948,4 +2085,102 @@ Baz"#
 \end{document}"#
         ));
     }
+
+    fn reparse_edit(text: &str, old: &str, new: &str) -> Option<GreenNode> {
+        let start = text.find(old).unwrap();
+        let range = TextRange::at((start as u32).into(), (old.len() as u32).into());
+        let green = parse(text).green;
+        let result = reparse(&green, range, new)?.green;
+
+        let new_text = text.replacen(old, new, 1);
+        assert_eq!(result, parse(&new_text).green);
+        Some(result)
+    }
+
+    #[test]
+    fn test_reparse_group() {
+        assert!(reparse_edit(r#"\section{Foo} \textbf{bar} baz"#, "bar", "qux quux").is_some());
+    }
+
+    #[test]
+    fn test_reparse_environment() {
+        assert!(reparse_edit(
+            "\\begin{itemize}\n\\item foo\n\\end{itemize}\nbar",
+            "foo",
+            "\\item baz"
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn test_reparse_environment_name() {
+        assert!(reparse_edit(r#"\begin{foo}bar\end{foo}"#, "{foo}bar", "{baz}bar").is_none());
+    }
+
+    #[test]
+    fn test_reparse_command_name() {
+        assert!(reparse_edit(r#"\begin{foo}bar\end{foo}"#, "{foo}", "x{foo}").is_none());
+        assert!(reparse_edit(r#"\foo{\bar baz}"#, " baz", "x baz").is_none());
+    }
+
+    #[test]
+    fn test_reparse_environment_boundaries() {
+        let text = r#"\begin{foo}[bar]{baz}qux\end{foo}"#;
+        let green = parse(text).green;
+        let mut reparsed = 0;
+        for offset in 0..=text.len() {
+            for insertion in ["x", " ", "{", "}", "[", "\\"] {
+                let range = TextRange::empty((offset as u32).into());
+                if let Some(result) = reparse(&green, range, insertion) {
+                    let mut new_text = text.to_string();
+                    new_text.insert_str(offset, insertion);
+                    assert_eq!(result.green, parse(&new_text).green, "{}", new_text);
+                    reparsed += 1;
+                }
+            }
+        }
+
+        assert!(reparsed > 0);
+        assert!(reparse(&green, TextRange::empty(6.into()), "x").is_none());
+    }
+
+    #[test]
+    fn test_reparse_nested_environment() {
+        assert!(reparse_edit(r#"\begin{a}\begin{b}x\end{b}\end{a}"#, "x", "y z").is_some());
+    }
+
+    #[test]
+    fn test_reparse_command_definition_with_environment() {
+        assert!(reparse_edit(r#"\newcommand{\foo}{\begin{x}a\end{x}}"#, "a", "b").is_none());
+    }
+
+    #[test]
+    fn test_reparse_group_with_environment() {
+        assert!(reparse_edit(r#"\foo{bar}"#, "bar", r#"\begin{baz}\end{baz}"#).is_none());
+    }
+
+    #[test]
+    fn test_reparse_unbalanced_group() {
+        assert!(reparse_edit(r#"\foo{bar} baz}"#, "bar", "bar{").is_none());
+    }
+
+    #[test]
+    fn test_reparse_delimiter() {
+        assert!(reparse_edit(r#"\foo{bar} baz}"#, "} baz", " baz").is_none());
+    }
+
+    #[test]
+    fn test_reparse_unbalanced_environment() {
+        assert!(reparse_edit(
+            r#"\begin{foo}bar\end{foo} baz \end{qux}"#,
+            "bar",
+            r#"\end{quux}"#
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_reparse_verbatim() {
+        assert!(reparse_edit(r#"\foo{bar} baz"#, "bar", r#"\begin{verbatim}"#).is_none());
+    }
 }
@@ -0,0 +1,500 @@
+use std::path::PathBuf;
+
+/// The number of TeX scaled points (`sp`) per PDF big point (`bp`).
+const SP_PER_BP: f64 = 65536.0 * 72.27 / 72.0;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum NodeKind {
+    VBox,
+    HBox,
+    VoidVBox,
+    VoidHBox,
+    Kern,
+    Glue,
+    Math,
+    Current,
+}
+
+impl NodeKind {
+    #[must_use]
+    pub fn is_hbox(self) -> bool {
+        matches!(self, Self::HBox | Self::VoidHBox)
+    }
+
+    #[must_use]
+    pub fn is_box(self) -> bool {
+        matches!(
+            self,
+            Self::VBox | Self::HBox | Self::VoidVBox | Self::VoidHBox
+        )
+    }
+}
+
+/// A record of the `Content` section. All dimensions are given in scaled points
+/// relative to the top left corner of the page, where `y` denotes the baseline.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub tag: u32,
+    pub line: u32,
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+    pub depth: i64,
+    pub parent: Option<usize>,
+}
+
+impl Node {
+    fn contains(&self, x: i64, y: i64) -> bool {
+        let (left, right) = (
+            self.x.min(self.x + self.width),
+            self.x.max(self.x + self.width),
+        );
+        left <= x && x <= right && self.y - self.height <= y && y <= self.y + self.depth
+    }
+
+    fn distance(&self, x: i64, y: i64) -> i64 {
+        let (left, right) = (
+            self.x.min(self.x + self.width),
+            self.x.max(self.x + self.width),
+        );
+        let dx = (left - x).max(x - right).max(0);
+        let dy = (self.y - self.height - y)
+            .max(y - self.y - self.depth)
+            .max(0);
+        dx + dy
+    }
+
+    fn area(&self) -> i64 {
+        self.width.abs() * (self.height + self.depth)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Page {
+    pub number: u32,
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Input {
+    pub tag: u32,
+    pub path: PathBuf,
+}
+
+/// A rectangle on a PDF page. The coordinates are given in big points
+/// relative to the top left corner of the page.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PdfBox {
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A (one-based) line of an input file.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct SourceLine {
+    pub tag: u32,
+    pub line: u32,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SyncTex {
+    pub inputs: Vec<Input>,
+    pub pages: Vec<Page>,
+    pub unit: f64,
+    pub magnification: f64,
+    pub x_offset: i64,
+    pub y_offset: i64,
+}
+
+impl SyncTex {
+    #[must_use]
+    pub fn find_input(&self, tag: u32) -> Option<&Input> {
+        self.inputs.iter().find(|input| input.tag == tag)
+    }
+
+    /// Finds the region of the output that corresponds to the given line
+    /// of the input files with the given tags.
+    /// If the line itself did not produce any output, the closest line is used instead.
+    #[must_use]
+    pub fn forward(&self, tags: &[u32], line: u32) -> Option<PdfBox> {
+        let is_candidate = |node: &&Node| {
+            tags.contains(&node.tag) && !matches!(node.kind, NodeKind::VBox | NodeKind::VoidVBox)
+        };
+
+        let best_line = self
+            .pages
+            .iter()
+            .flat_map(|page| page.nodes.iter())
+            .filter(is_candidate)
+            .map(|node| node.line)
+            .min_by_key(|node_line| {
+                ((i64::from(*node_line) - i64::from(line)).abs(), *node_line)
+            })?;
+
+        let page = self.pages.iter().find(|page| {
+            page.nodes
+                .iter()
+                .filter(is_candidate)
+                .any(|node| node.line == best_line)
+        })?;
+
+        let (left, top, right, bottom) = page
+            .nodes
+            .iter()
+            .filter(is_candidate)
+            .filter(|node| node.line == best_line)
+            .map(|node| match node.parent.map(|parent| &page.nodes[parent]) {
+                Some(parent) if !node.kind.is_box() && parent.kind.is_hbox() => parent,
+                _ => node,
+            })
+            .map(|node| {
+                let left = node.x.min(node.x + node.width);
+                let right = node.x.max(node.x + node.width);
+                (left, node.y - node.height, right, node.y + node.depth)
+            })
+            .reduce(|(l1, t1, r1, b1), (l2, t2, r2, b2)| {
+                (l1.min(l2), t1.min(t2), r1.max(r2), b1.max(b2))
+            })?;
+
+        Some(PdfBox {
+            page: page.number,
+            x: self.to_bp(left + self.x_offset),
+            y: self.to_bp(top + self.y_offset),
+            width: self.to_bp(right - left),
+            height: self.to_bp(bottom - top),
+        })
+    }
+
+    /// Finds the input line that produced the output at the given point of a page.
+    /// The point is given in big points relative to the top left corner of the page.
+    #[must_use]
+    pub fn inverse(&self, page: u32, x: f64, y: f64) -> Option<SourceLine> {
+        let page = self.pages.iter().find(|p| p.number == page)?;
+        let x = self.to_sp(x) - self.x_offset;
+        let y = self.to_sp(y) - self.y_offset;
+
+        let hboxes = page
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.kind.is_hbox());
+
+        let (index, hbox) = hboxes
+            .clone()
+            .filter(|(_, node)| node.contains(x, y))
+            .min_by_key(|(_, node)| node.area())
+            .or_else(|| hboxes.min_by_key(|(_, node)| node.distance(x, y)))?;
+
+        let node = page
+            .nodes
+            .iter()
+            .filter(|node| node.parent == Some(index) && !node.kind.is_box())
+            .min_by_key(|node| (node.x - x).abs())
+            .unwrap_or(hbox);
+
+        Some(SourceLine {
+            tag: node.tag,
+            line: node.line,
+        })
+    }
+
+    fn to_bp(&self, value: i64) -> f64 {
+        value as f64 * self.unit * self.magnification / 1000.0 / SP_PER_BP
+    }
+
+    fn to_sp(&self, value: f64) -> i64 {
+        (value * SP_PER_BP * 1000.0 / self.magnification / self.unit).round() as i64
+    }
+}
+
+/// Parses the (uncompressed) contents of a SyncTeX file.
+/// Returns `None` if the file does not start with a valid preamble.
+#[must_use]
+pub fn parse(text: &str) -> Option<SyncTex> {
+    let mut lines = text.lines();
+    lines.next()?.strip_prefix("SyncTeX Version:")?;
+
+    let mut synctex = SyncTex {
+        inputs: Vec::new(),
+        pages: Vec::new(),
+        unit: 1.0,
+        magnification: 1000.0,
+        x_offset: 0,
+        y_offset: 0,
+    };
+
+    for line in lines.by_ref() {
+        if line == "Content:" {
+            break;
+        }
+
+        let (key, value) = match line.split_once(':') {
+            Some(pair) => pair,
+            None => continue,
+        };
+
+        match key {
+            "Input" => synctex.inputs.extend(parse_input(value)),
+            "Unit" => synctex.unit = value.parse().ok().filter(|&unit| unit > 0.0)?,
+            "Magnification" => {
+                synctex.magnification = value.parse().ok().filter(|&mag| mag > 0.0)?;
+            }
+            "X Offset" => synctex.x_offset = value.parse().unwrap_or_default(),
+            "Y Offset" => synctex.y_offset = value.parse().unwrap_or_default(),
+            _ => {}
+        };
+    }
+
+    let mut page: Option<Page> = None;
+    let mut parents = Vec::new();
+    for line in lines {
+        if line == "Postamble:" {
+            break;
+        }
+
+        if let Some(value) = line.strip_prefix("Input:") {
+            synctex.inputs.extend(parse_input(value));
+            continue;
+        }
+
+        let (kind, rest) = match line.chars().next() {
+            Some('{') => {
+                page = line[1..].parse().ok().map(|number| Page {
+                    number,
+                    nodes: Vec::new(),
+                });
+                parents.clear();
+                continue;
+            }
+            Some('}') => {
+                synctex.pages.extend(page.take());
+                continue;
+            }
+            Some(']' | ')') => {
+                parents.pop();
+                continue;
+            }
+            Some('[') => (NodeKind::VBox, &line[1..]),
+            Some('(') => (NodeKind::HBox, &line[1..]),
+            Some('v') => (NodeKind::VoidVBox, &line[1..]),
+            Some('h') => (NodeKind::VoidHBox, &line[1..]),
+            Some('k') => (NodeKind::Kern, &line[1..]),
+            Some('g') => (NodeKind::Glue, &line[1..]),
+            Some('$') => (NodeKind::Math, &line[1..]),
+            Some('x') => (NodeKind::Current, &line[1..]),
+            _ => continue,
+        };
+
+        let page = match page.as_mut() {
+            Some(page) => page,
+            None => continue,
+        };
+
+        let node = parse_node(kind, rest, parents.last().copied().flatten());
+        let index = node.map(|node| {
+            page.nodes.push(node);
+            page.nodes.len() - 1
+        });
+
+        if matches!(kind, NodeKind::VBox | NodeKind::HBox) {
+            parents.push(index);
+        }
+    }
+
+    Some(synctex)
+}
+
+fn parse_input(value: &str) -> Option<Input> {
+    let (tag, path) = value.split_once(':')?;
+    Some(Input {
+        tag: tag.parse().ok()?,
+        path: PathBuf::from(path),
+    })
+}
+
+fn parse_node(kind: NodeKind, text: &str, parent: Option<usize>) -> Option<Node> {
+    let mut fields = text.split(':');
+    let mut link = fields.next()?.split(',');
+    let tag = link.next()?.parse().ok()?;
+    let line = link.next()?.parse().ok()?;
+
+    let (x, y) = fields.next()?.split_once(',')?;
+    let x = x.parse().ok()?;
+    let y = y.parse().ok()?;
+
+    let mut size = fields
+        .next()
+        .into_iter()
+        .flat_map(|size| size.split(','))
+        .map(|value| value.parse::<i64>().ok());
+
+    let mut next_size = || size.next().flatten().unwrap_or_default();
+    let (width, height, depth) = match kind {
+        NodeKind::VBox | NodeKind::HBox | NodeKind::VoidVBox | NodeKind::VoidHBox => {
+            (next_size(), next_size(), next_size())
+        }
+        NodeKind::Kern => (next_size(), 0, 0),
+        NodeKind::Glue | NodeKind::Math | NodeKind::Current => (0, 0, 0),
+    };
+
+    Some(Node {
+        kind,
+        tag,
+        line,
+        x,
+        y,
+        width,
+        height,
+        depth,
+        parent,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNCTEX: &str = r#"SyncTeX Version:1
+Input:1:/home/user/paper/./main.tex
+Input:2:/usr/share/texmf/tex/latex/base/article.cls
+Output:pdf
+Magnification:1000
+Unit:1
+X Offset:0
+Y Offset:0
+Content:
+!231
+{1
+[1,6:4736286,5500000:26851748,1000000,0
+(1,6:4736286,5000000:26851748,500000,100000
+g1,6:4736286,5000000
+k1,7:10000000,5000000:100000
+x1,7:12000000,5000000
+$1,7:14000000,5000000
+)
+(1,9:4736286,6000000:26851748,500000,100000
+g1,9:4736286,6000000
+)
+]
+}1
+!457
+{2
+Input:3:./chapter.tex
+[3,1:4736286,5500000:26851748,1000000,0
+(3,2:4736286,5000000:26851748,500000,100000
+g3,2:4736286,5000000
+)
+]
+}2
+Postamble:
+Count:12
+Post scriptum:
+"#;
+
+    fn setup() -> SyncTex {
+        parse(SYNCTEX).unwrap()
+    }
+
+    #[test]
+    fn test_inputs() {
+        let synctex = setup();
+        assert_eq!(
+            synctex.inputs,
+            vec![
+                Input {
+                    tag: 1,
+                    path: "/home/user/paper/./main.tex".into()
+                },
+                Input {
+                    tag: 2,
+                    path: "/usr/share/texmf/tex/latex/base/article.cls".into()
+                },
+                Input {
+                    tag: 3,
+                    path: "./chapter.tex".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pages() {
+        let synctex = setup();
+        assert_eq!(synctex.pages.len(), 2);
+        assert_eq!(synctex.pages[0].nodes.len(), 8);
+        assert_eq!(synctex.pages[0].nodes[1].kind, NodeKind::HBox);
+        assert_eq!(synctex.pages[0].nodes[1].parent, Some(0));
+        assert_eq!(synctex.pages[0].nodes[3].width, 100000);
+        assert_eq!(synctex.pages[0].nodes[3].parent, Some(1));
+        assert_eq!(synctex.pages[0].nodes[6].parent, Some(0));
+        assert_eq!(synctex.pages[1].number, 2);
+    }
+
+    #[test]
+    fn test_invalid_preamble() {
+        assert_eq!(parse("foo\nContent:\n"), None);
+    }
+
+    #[test]
+    fn test_forward_exact() {
+        let synctex = setup();
+        let pdf_box = synctex.forward(&[1], 7).unwrap();
+        assert_eq!(pdf_box.page, 1);
+        assert!((pdf_box.x - 72.0).abs() < 0.01);
+        assert!((pdf_box.width - 26851748.0 / SP_PER_BP).abs() < 0.01);
+        assert!((pdf_box.y - 4500000.0 / SP_PER_BP).abs() < 0.01);
+        assert!((pdf_box.height - 600000.0 / SP_PER_BP).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_forward_closest_line() {
+        let synctex = setup();
+        let pdf_box = synctex.forward(&[1], 10).unwrap();
+        assert_eq!(pdf_box.page, 1);
+        assert!((pdf_box.y - 5500000.0 / SP_PER_BP).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_forward_other_page() {
+        let synctex = setup();
+        assert_eq!(synctex.forward(&[3], 2).unwrap().page, 2);
+    }
+
+    #[test]
+    fn test_forward_unknown_input() {
+        let synctex = setup();
+        assert_eq!(synctex.forward(&[4], 1), None);
+    }
+
+    #[test]
+    fn test_inverse_leaf() {
+        let synctex = setup();
+        let x = 12100000.0 / SP_PER_BP;
+        let y = 4900000.0 / SP_PER_BP;
+        assert_eq!(
+            synctex.inverse(1, x, y),
+            Some(SourceLine { tag: 1, line: 7 })
+        );
+    }
+
+    #[test]
+    fn test_inverse_outside() {
+        let synctex = setup();
+        let y = 6000000.0 / SP_PER_BP;
+        assert_eq!(
+            synctex.inverse(1, 0.0, y),
+            Some(SourceLine { tag: 1, line: 9 })
+        );
+    }
+
+    #[test]
+    fn test_inverse_unknown_page() {
+        let synctex = setup();
+        assert_eq!(synctex.inverse(3, 0.0, 0.0), None);
+    }
+}
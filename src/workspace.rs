@@ -19,6 +19,17 @@ use crate::{
 #[derive(Debug, Clone)]
 pub enum WorkspaceEvent {
     Changed(Workspace, Document),
+    Removed(Workspace, Arc<Url>),
+}
+
+/// The extensions of the files that are reloaded when they change on disk.
+const RELOADABLE_EXTENSIONS: &[&str] = &["tex", "bib", "sty", "cls", "log", "aux"];
+
+/// A workspace folder of the client with its own settings.
+#[derive(Debug, Clone)]
+pub struct Folder {
+    pub uri: Url,
+    pub environment: Environment,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -27,6 +38,7 @@ pub struct Workspace {
     pub viewport: FxHashSet<Arc<Url>>,
     pub listeners: Vec<Sender<WorkspaceEvent>>,
     pub environment: Environment,
+    pub folders: Vec<Folder>,
     watcher: Option<Arc<Mutex<notify::RecommendedWatcher>>>,
     watched_dirs: Arc<Mutex<FxHashSet<PathBuf>>>,
 }
@@ -40,6 +52,36 @@ impl Workspace {
         }
     }
 
+    /// Adds a workspace folder whose environment starts as a copy of the global environment.
+    pub fn add_folder(&mut self, uri: Url) {
+        if let Ok(path) = uri.to_file_path() {
+            let mut environment = self.environment.clone();
+            environment.current_directory = Arc::new(path);
+            self.folders.push(Folder { uri, environment });
+        }
+    }
+
+    pub fn remove_folder(&mut self, uri: &Url) {
+        self.folders.retain(|folder| folder.uri != *uri);
+    }
+
+    /// Finds the environment of the innermost workspace folder that contains the document.
+    /// Documents outside of all workspace folders use the global environment.
+    #[must_use]
+    pub fn environment_for(&self, uri: &Url) -> &Environment {
+        let path = match uri.to_file_path() {
+            Ok(path) => path,
+            Err(()) => return &self.environment,
+        };
+
+        self.folders
+            .iter()
+            .map(|folder| &folder.environment)
+            .filter(|environment| path.starts_with(environment.current_directory.as_path()))
+            .max_by_key(|environment| environment.current_directory.components().count())
+            .unwrap_or(&self.environment)
+    }
+
     pub fn register_watcher(&mut self, watcher: notify::RecommendedWatcher) {
         self.watcher = Some(Arc::new(Mutex::new(watcher)));
     }
@@ -61,6 +103,13 @@ impl Workspace {
         text: Arc<String>,
         language: DocumentLanguage,
     ) -> Result<Document> {
+        log::debug!("(Re)Loading document: {}", uri);
+        let document = Document::parse(self.environment_for(&uri), uri, text, language);
+        self.insert(document)
+    }
+
+    pub fn insert(&mut self, document: Document) -> Result<Document> {
+        let uri = Arc::clone(&document.uri);
         if uri.scheme() == "file" {
             if let Ok(mut path) = uri.to_file_path() {
                 path.pop();
@@ -68,9 +117,6 @@ impl Workspace {
             }
         }
 
-        log::debug!("(Re)Loading document: {}", uri);
-        let document = Document::parse(&self.environment, Arc::clone(&uri), text, language);
-
         self.documents_by_uri
             .insert(Arc::clone(&uri), document.clone());
 
@@ -83,22 +129,70 @@ impl Workspace {
         Ok(document)
     }
 
+    /// Inserts a document without notifying the listeners, e.g. while indexing the workspace.
+    /// The documents that are loaded because the document refers to them are not reported either.
+    pub fn insert_quietly(&mut self, document: Document) -> Result<Document> {
+        let listeners = std::mem::take(&mut self.listeners);
+        let result = self.insert(document);
+        self.listeners = listeners;
+        result
+    }
+
     pub fn reload(&mut self, path: PathBuf) -> Result<Option<Document>> {
         let uri = Arc::new(Url::from_file_path(path.clone()).unwrap());
-        if self.is_open(&uri) || !(uri.as_str().ends_with(".log") || uri.as_str().ends_with(".aux"))
-        {
+        let is_reloadable = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map_or(false, |extension| {
+                RELOADABLE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+            });
+
+        if self.is_open(&uri) || !is_reloadable {
             return Ok(self.documents_by_uri.get(&uri).cloned());
         }
 
         if let Some(language) = DocumentLanguage::by_path(&path) {
             let data = fs::read(&path)?;
             let text = Arc::new(String::from_utf8_lossy(&data).into_owned());
+
+            // Avoid reanalyzing the workspace if only the timestamp of the file has changed.
+            if let Some(document) = self
+                .documents_by_uri
+                .get(&uri)
+                .filter(|document| document.text == text)
+            {
+                return Ok(Some(document.clone()));
+            }
+
             Ok(Some(self.open(uri, text, language)?))
         } else {
             Ok(None)
         }
     }
 
+    /// Removes a document that has been deleted from disk.
+    /// Documents that are opened by the client are managed by the client instead.
+    pub fn remove(&mut self, uri: &Url) -> Result<Option<Document>> {
+        if self.is_open(uri) {
+            return Ok(None);
+        }
+
+        let document = match self.documents_by_uri.remove(uri) {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+
+        log::debug!("Removing document: {}", uri);
+        for listener in &self.listeners {
+            listener.send(WorkspaceEvent::Removed(
+                self.clone(),
+                Arc::clone(&document.uri),
+            ))?;
+        }
+
+        Ok(Some(document))
+    }
+
     pub fn load(&mut self, path: PathBuf) -> Result<Option<Document>> {
         let uri = Arc::new(Url::from_file_path(path.clone()).unwrap());
 
@@ -106,12 +200,17 @@ impl Workspace {
             return Ok(Some(document));
         }
 
-        let data = fs::read(&path)?;
-        let text = Arc::new(String::from_utf8_lossy(&data).into_owned());
-        if let Some(language) = DocumentLanguage::by_path(&path) {
-            Ok(Some(self.open(uri, text, language)?))
-        } else {
-            Ok(None)
+        match DocumentLanguage::by_path(&path) {
+            Some(language) => {
+                log::debug!("Loading document: {}", uri);
+                let document = Document::load(self.environment_for(&uri), &path, language)?;
+                Ok(Some(self.insert(document)?))
+            }
+            None => {
+                // Missing files are errors, so that the next candidate of a link is tried.
+                fs::metadata(&path)?;
+                Ok(None)
+            }
         }
     }
 
@@ -138,8 +237,13 @@ impl Workspace {
                         .and_then(|document| document.data.as_latex())
                     {
                         let extras = &data.extras;
-                        let mut all_targets =
-                            vec![&extras.implicit_links.aux, &extras.implicit_links.log];
+                        let magic_root: Vec<_> =
+                            extras.magic_comments.root.iter().cloned().collect();
+                        let mut all_targets = vec![
+                            &extras.implicit_links.aux,
+                            &extras.implicit_links.log,
+                            &magic_root,
+                        ];
                         for link in &extras.explicit_links {
                             all_targets.push(&link.targets);
                         }
@@ -164,6 +268,7 @@ impl Workspace {
                 }
 
                 let mut slice = self.clone();
+                slice.environment = self.environment_for(uri).clone();
                 slice.documents_by_uri = FxHashMap::default();
                 let graph = DiGraphMap::from_edges(edges);
                 let mut dfs = Dfs::new(&graph, start);
@@ -178,9 +283,22 @@ impl Workspace {
             .unwrap_or_default()
     }
 
+    /// Finds the root document that has been specified using `% !TEX root`.
+    #[must_use]
+    pub fn find_magic_root(&self, uri: &Url) -> Option<&Document> {
+        let data = self.documents_by_uri.get(uri)?.data.as_latex()?;
+        let root = data.extras.magic_comments.root.as_ref()?;
+        self.documents_by_uri.get(root)
+    }
+
     #[must_use]
     pub fn find_parent(&self, uri: &Url) -> Option<Document> {
-        self.slice(uri)
+        let slice = self.slice(uri);
+        if let Some(document) = slice.find_magic_root(uri) {
+            return Some(document.clone());
+        }
+
+        slice
             .documents_by_uri
             .values()
             .find(|document| {
@@ -231,7 +349,12 @@ impl Workspace {
     fn expand_children(&mut self, document: &Document) {
         if let Some(data) = document.data.as_latex() {
             let extras = &data.extras;
-            let mut all_targets = vec![&extras.implicit_links.aux, &extras.implicit_links.log];
+            let magic_root: Vec<_> = extras.magic_comments.root.iter().cloned().collect();
+            let mut all_targets = vec![
+                &extras.implicit_links.aux,
+                &extras.implicit_links.log,
+                &magic_root,
+            ];
             for link in &extras.explicit_links {
                 if link
                     .as_component_name()
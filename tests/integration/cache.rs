@@ -0,0 +1,79 @@
+use std::{fs, path::Path, sync::Arc};
+
+use anyhow::Result;
+use tempfile::tempdir;
+use texlab::{Cache, Environment, Workspace};
+
+fn load_project(directory: &Path) -> Result<Workspace> {
+    let mut environment = Environment::new(Arc::new(directory.to_path_buf()));
+    let cache = Arc::new(Cache::open(directory.join("cache")));
+    environment.cache = Some(Arc::clone(&cache));
+
+    let mut workspace = Workspace::new(environment);
+    workspace.load(directory.join("main.tex"))?;
+    cache.flush();
+    Ok(workspace)
+}
+
+fn find_labels(workspace: &Workspace, name: &str) -> Vec<String> {
+    let document = workspace
+        .documents_by_uri
+        .values()
+        .find(|document| document.uri.as_str().ends_with(name))
+        .unwrap();
+
+    let mut labels: Vec<_> = document
+        .data
+        .as_latex()
+        .unwrap()
+        .extras
+        .label_names
+        .iter()
+        .map(|label| label.text.to_string())
+        .collect();
+
+    labels.sort();
+    labels
+}
+
+#[test]
+fn load_from_cache() -> Result<()> {
+    let directory = tempdir()?;
+    fs::write(
+        directory.path().join("main.tex"),
+        "\\begin{document}\n\\input{chapter}\n\\label{foo}\n\\end{document}",
+    )?;
+    fs::write(directory.path().join("chapter.tex"), "\\label{bar}")?;
+
+    let workspace = load_project(directory.path())?;
+    assert_eq!(workspace.documents_by_uri.len(), 2);
+    assert!(directory.path().join("cache/extras.json.gz").exists());
+
+    let workspace = load_project(directory.path())?;
+    assert_eq!(workspace.documents_by_uri.len(), 2);
+    assert_eq!(find_labels(&workspace, "main.tex"), vec!["foo"]);
+    assert_eq!(find_labels(&workspace, "chapter.tex"), vec!["bar"]);
+    Ok(())
+}
+
+#[test]
+fn load_modified_file() -> Result<()> {
+    let directory = tempdir()?;
+    fs::write(
+        directory.path().join("main.tex"),
+        "\\begin{document}\n\\input{chapter}\n\\end{document}",
+    )?;
+    fs::write(directory.path().join("chapter.tex"), "\\label{bar}")?;
+    fs::write(directory.path().join("appendix.tex"), "\\label{baz}")?;
+    load_project(directory.path())?;
+
+    fs::write(
+        directory.path().join("chapter.tex"),
+        "\\label{qux}\n\\input{appendix}",
+    )?;
+
+    let workspace = load_project(directory.path())?;
+    assert_eq!(workspace.documents_by_uri.len(), 3);
+    assert_eq!(find_labels(&workspace, "chapter.tex"), vec!["qux"]);
+    Ok(())
+}
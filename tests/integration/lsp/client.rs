@@ -1,11 +1,11 @@
 use anyhow::{bail, Result};
 use crossbeam_channel::{Receiver, Sender};
-use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
 use lsp_types::{
     notification::{Exit, Initialized},
     request::{Initialize, Shutdown},
     ClientCapabilities, ClientInfo, DidOpenTextDocumentParams, InitializeParams, InitializeResult,
-    InitializedParams, Url,
+    InitializedParams, Url, WorkspaceFolder,
 };
 use tempfile::{tempdir, TempDir};
 use texlab::Server;
@@ -77,11 +77,20 @@ impl Client {
         })
     }
 
-    #[allow(deprecated)]
     pub fn initialize(
         &mut self,
         client_capabilities: ClientCapabilities,
         client_info: Option<ClientInfo>,
+    ) -> Result<InitializeResult> {
+        self.initialize_with_folders(client_capabilities, client_info, None)
+    }
+
+    #[allow(deprecated)]
+    pub fn initialize_with_folders(
+        &mut self,
+        client_capabilities: ClientCapabilities,
+        client_info: Option<ClientInfo>,
+        workspace_folders: Option<Vec<WorkspaceFolder>>,
     ) -> Result<InitializeResult> {
         let result = self.request::<Initialize>(InitializeParams {
             process_id: None,
@@ -90,7 +99,7 @@ impl Client {
             initialization_options: None,
             capabilities: client_capabilities,
             trace: None,
-            workspace_folders: None,
+            workspace_folders,
             client_info,
             locale: None,
         })?;
@@ -103,13 +112,28 @@ impl Client {
         &mut self,
         params: R::Params,
     ) -> Result<R::Result> {
-        self.request_id += 1;
+        let id = self.send_request::<R>(params)?;
+        self.receive_response::<R>(id)
+    }
 
+    pub fn send_request<R: lsp_types::request::Request>(
+        &mut self,
+        params: R::Params,
+    ) -> Result<RequestId> {
+        self.request_id += 1;
+        let id = RequestId::from(self.request_id);
         self.outgoing
-            .send(Request::new(self.request_id.into(), R::METHOD.into(), params).into())?;
+            .send(Request::new(id.clone(), R::METHOD.into(), params).into())?;
 
+        Ok(id)
+    }
+
+    pub fn receive_response<R: lsp_types::request::Request>(
+        &mut self,
+        id: RequestId,
+    ) -> Result<R::Result> {
         let response = self.incoming.responses.recv()?;
-        assert_eq!(response.id, self.request_id.into());
+        assert_eq!(response.id, id);
 
         let result = match response.result {
             Some(result) => result,
@@ -119,6 +143,11 @@ impl Client {
         Ok(serde_json::from_value(result)?)
     }
 
+    pub fn respond(&mut self, response: Response) -> Result<()> {
+        self.outgoing.send(response.into())?;
+        Ok(())
+    }
+
     pub fn notify<N: lsp_types::notification::Notification>(
         &mut self,
         params: N::Params,
@@ -149,6 +178,11 @@ impl Client {
         Ok(())
     }
 
+    pub fn remove_from_disk(&mut self, name: &str) -> Result<()> {
+        std::fs::remove_file(self.directory.path().join(name))?;
+        Ok(())
+    }
+
     pub fn shutdown(mut self) -> Result<ClientResult> {
         self.request::<Shutdown>(())?;
         self.notify::<Exit>(())?;
@@ -158,6 +192,10 @@ impl Client {
         })
     }
 
+    pub fn incoming(&self) -> &IncomingHandler {
+        &self.incoming
+    }
+
     pub fn uri(&self, name: &str) -> Result<Url> {
         Url::from_file_path(self.directory.path().join(name))
             .map_err(|()| anyhow::anyhow!("failed to create uri"))
@@ -1,12 +1,22 @@
+mod build;
+mod code_action;
 mod completion;
 mod definition;
+mod diagnostic;
 mod did_change_configuration;
+mod document_color;
 mod document_highlight;
 mod document_link;
 mod document_symbol;
 mod folding_range;
 mod formatting;
 mod hover;
+mod inlay_hint;
+mod linked_editing_range;
 mod publish_diagnostics;
 mod references;
 mod rename;
+mod selection_range;
+mod semantic_tokens;
+mod signature_help;
+mod synctex;
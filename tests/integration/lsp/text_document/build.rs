@@ -0,0 +1,306 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use lsp_types::{
+    notification::{
+        Cancel, DidChangeConfiguration, LogMessage, Notification, PublishDiagnostics, ShowMessage,
+    },
+    CancelParams, ClientCapabilities, DidChangeConfigurationParams, LogMessageParams, MessageType,
+    NumberOrString, PublishDiagnosticsParams, ShowMessageParams, TextDocumentIdentifier,
+};
+use texlab::features::{BuildParams, BuildResult, BuildStatus, ContinuousBuildStatus};
+
+use crate::lsp::client::Client;
+
+struct BuildRequest;
+
+impl lsp_types::request::Request for BuildRequest {
+    type Params = BuildParams;
+
+    type Result = BuildResult;
+
+    const METHOD: &'static str = "textDocument/build";
+}
+
+struct StartContinuousBuildRequest;
+
+impl lsp_types::request::Request for StartContinuousBuildRequest {
+    type Params = BuildParams;
+
+    type Result = ContinuousBuildStatus;
+
+    const METHOD: &'static str = "textDocument/startContinuousBuild";
+}
+
+struct StopContinuousBuildRequest;
+
+impl lsp_types::request::Request for StopContinuousBuildRequest {
+    type Params = BuildParams;
+
+    type Result = ContinuousBuildStatus;
+
+    const METHOD: &'static str = "textDocument/stopContinuousBuild";
+}
+
+struct ContinuousBuildStatusRequest;
+
+impl lsp_types::request::Request for ContinuousBuildStatusRequest {
+    type Params = BuildParams;
+
+    type Result = ContinuousBuildStatus;
+
+    const METHOD: &'static str = "textDocument/continuousBuildStatus";
+}
+
+fn configure(executable: &str, script: &str) -> Result<Client> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+    client.notify::<DidChangeConfiguration>(DidChangeConfigurationParams {
+        settings: serde_json::json!({
+            "build": {
+                "executable": executable,
+                "args": ["-c", script],
+                "continuousArgs": []
+            }
+        }),
+    })?;
+
+    Ok(client)
+}
+
+fn spawn(script: &str) -> Result<Client> {
+    let mut client = configure("sh", script)?;
+    client.open(
+        "main.tex",
+        "latex",
+        "\\documentclass{article}\n\\begin{document}\n\\end{document}\n".to_string(),
+    )?;
+
+    Ok(client)
+}
+
+fn wait_for_output(client: &Client, line: &str) {
+    for notification in &client.incoming().notifications {
+        if let Ok(params) = notification.extract::<LogMessageParams>(LogMessage::METHOD) {
+            if params.message == line {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn success() -> Result<()> {
+    let mut client = spawn("exit 0")?;
+    let result = client.request::<BuildRequest>(BuildParams {
+        text_document: TextDocumentIdentifier::new(client.uri("main.tex")?),
+    })?;
+
+    assert_eq!(result.status, BuildStatus::SUCCESS);
+    client.shutdown()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn cancel_request() -> Result<()> {
+    let mut client = spawn("echo started; sleep 60 & wait")?;
+    let start = Instant::now();
+    let id = client.send_request::<BuildRequest>(BuildParams {
+        text_document: TextDocumentIdentifier::new(client.uri("main.tex")?),
+    })?;
+
+    wait_for_output(&client, "started");
+    client.notify::<Cancel>(CancelParams {
+        id: NumberOrString::Number(id.to_string().parse()?),
+    })?;
+
+    let result = client.receive_response::<BuildRequest>(id)?;
+    assert_eq!(result.status, BuildStatus::CANCELLED);
+    assert!(start.elapsed() < Duration::from_secs(30));
+
+    client.shutdown()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn cancel_queued_build() -> Result<()> {
+    let mut client = spawn("echo started; sleep 60 & wait")?;
+    let start = Instant::now();
+    let first_id = client.send_request::<BuildRequest>(BuildParams {
+        text_document: TextDocumentIdentifier::new(client.uri("main.tex")?),
+    })?;
+
+    wait_for_output(&client, "started");
+    let second_id = client.send_request::<BuildRequest>(BuildParams {
+        text_document: TextDocumentIdentifier::new(client.uri("main.tex")?),
+    })?;
+
+    for id in [&second_id, &first_id] {
+        client.notify::<Cancel>(CancelParams {
+            id: NumberOrString::Number(id.to_string().parse()?),
+        })?;
+    }
+
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let response = client.incoming().responses.recv()?;
+        let result: BuildResult = serde_json::from_value(response.result.unwrap())?;
+        assert_eq!(result.status, BuildStatus::CANCELLED);
+        ids.push(response.id);
+    }
+
+    assert!(ids.contains(&first_id) && ids.contains(&second_id));
+    assert!(start.elapsed() < Duration::from_secs(30));
+
+    client.shutdown()?;
+    Ok(())
+}
+
+const CONTINUOUS_SCRIPT: &str = r#"
+printf '(./main.tex\n! Undefined control sequence.\nl.2 \\foo\n' > main.log
+echo "=== Watching for updated files. Use ctrl/C to stop ..."
+sleep 60 & wait
+"#;
+
+#[test]
+#[cfg(unix)]
+fn continuous_build() -> Result<()> {
+    let mut client = spawn(CONTINUOUS_SCRIPT)?;
+    let main_uri = client.uri("main.tex")?;
+    let params = || BuildParams {
+        text_document: TextDocumentIdentifier::new(main_uri.clone()),
+    };
+
+    let status = client.request::<ContinuousBuildStatusRequest>(params())?;
+    assert_eq!(status, ContinuousBuildStatus::stopped(main_uri.clone()));
+
+    let status = client.request::<StartContinuousBuildRequest>(params())?;
+    assert!(status.running);
+    assert_eq!(status.root_uri, main_uri);
+
+    let diagnostics = client
+        .incoming()
+        .notifications
+        .iter()
+        .filter_map(|notification| {
+            notification
+                .extract::<PublishDiagnosticsParams>(PublishDiagnostics::METHOD)
+                .ok()
+        })
+        .find(|params| params.uri == main_uri && !params.diagnostics.is_empty())
+        .unwrap()
+        .diagnostics;
+
+    assert_eq!(diagnostics[0].message, "Undefined control sequence.");
+    assert_eq!(diagnostics[0].range.start.line, 1);
+
+    let status = client.request::<ContinuousBuildStatusRequest>(params())?;
+    assert!(status.running);
+    assert_eq!(status.passes, 1);
+
+    let status = client.request::<StartContinuousBuildRequest>(params())?;
+    assert!(status.running);
+    assert_eq!(status.passes, 1);
+
+    let status = client.request::<StopContinuousBuildRequest>(params())?;
+    assert!(!status.running);
+    assert_eq!(status.passes, 1);
+
+    let status = client.request::<ContinuousBuildStatusRequest>(params())?;
+    assert_eq!(status, ContinuousBuildStatus::stopped(main_uri));
+
+    client.shutdown()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn continuous_build_without_latexmk() -> Result<()> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+    client.notify::<DidChangeConfiguration>(DidChangeConfigurationParams {
+        settings: serde_json::json!({
+            "build": {
+                "executable": "sh",
+                "args": ["-c", CONTINUOUS_SCRIPT]
+            }
+        }),
+    })?;
+
+    client.open(
+        "main.tex",
+        "latex",
+        "\\documentclass{article}\n\\begin{document}\n\\end{document}\n".to_string(),
+    )?;
+
+    let main_uri = client.uri("main.tex")?;
+    let status = client.request::<StartContinuousBuildRequest>(BuildParams {
+        text_document: TextDocumentIdentifier::new(main_uri.clone()),
+    })?;
+
+    assert_eq!(status, ContinuousBuildStatus::stopped(main_uri));
+
+    let params = client
+        .incoming()
+        .notifications
+        .iter()
+        .find_map(|notification| {
+            notification
+                .extract::<ShowMessageParams>(ShowMessage::METHOD)
+                .ok()
+        })
+        .unwrap();
+
+    assert_eq!(params.typ, MessageType::ERROR);
+    assert!(params.message.contains("continuousArgs"));
+
+    client.shutdown()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn magic_root() -> Result<()> {
+    let mut client = configure("sh", "case %f in */main.tex) exit 0;; *) exit 1;; esac")?;
+    client.store_on_disk(
+        "main.tex",
+        "\\documentclass{article}\n\\begin{document}\n\\end{document}\n",
+    )?;
+
+    client.open(
+        "chapter.tex",
+        "latex",
+        "% !TEX root = main.tex\n\\section{Foo}\n".to_string(),
+    )?;
+
+    let result = client.request::<BuildRequest>(BuildParams {
+        text_document: TextDocumentIdentifier::new(client.uri("chapter.tex")?),
+    })?;
+
+    assert_eq!(result.status, BuildStatus::SUCCESS);
+    client.shutdown()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn magic_program_unsupported() -> Result<()> {
+    let mut client = configure("sh", "exit 0")?;
+    client.open(
+        "main.tex",
+        "latex",
+        "% !TEX program = false\n\\documentclass{article}\n\\begin{document}\n\\end{document}\n"
+            .to_string(),
+    )?;
+
+    let result = client.request::<BuildRequest>(BuildParams {
+        text_document: TextDocumentIdentifier::new(client.uri("main.tex")?),
+    })?;
+
+    assert_eq!(result.status, BuildStatus::SUCCESS);
+    client.shutdown()?;
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use anyhow::Result;
+use lsp_types::{
+    request::CodeActionRequest, ClientCapabilities, CodeActionContext, CodeActionKind,
+    CodeActionOrCommand, CodeActionParams, Diagnostic, NumberOrString, Position, Range,
+    TextDocumentIdentifier,
+};
+use texlab::{LineIndex, LineIndexExt};
+
+use crate::lsp::{client::Client, fixture};
+
+fn find_code_actions(
+    fixture: &str,
+    code: i32,
+    range: Range,
+    only: Option<Vec<CodeActionKind>>,
+) -> Result<Vec<(String, String)>> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+
+    let fixture = fixture::parse(fixture);
+    let file = fixture.files.into_iter().next().unwrap();
+    let old_text = file.text.clone();
+    client.open(file.name, file.lang, file.text)?;
+
+    let diagnostic = Diagnostic {
+        range,
+        code: Some(NumberOrString::Number(code)),
+        source: Some("texlab".into()),
+        ..Diagnostic::default()
+    };
+
+    let actions = client
+        .request::<CodeActionRequest>(CodeActionParams {
+            text_document: TextDocumentIdentifier::new(client.uri(file.name)?),
+            range,
+            context: CodeActionContext {
+                diagnostics: vec![diagnostic],
+                only,
+                trigger_kind: None,
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })?
+        .unwrap_or_default();
+
+    client.shutdown()?;
+
+    let line_index = LineIndex::new(&old_text);
+    let mut results = Vec::new();
+    for action in actions {
+        let action = match action {
+            CodeActionOrCommand::CodeAction(action) => action,
+            CodeActionOrCommand::Command(_) => unreachable!(),
+        };
+
+        assert_eq!(action.kind, Some(CodeActionKind::QUICKFIX));
+        let mut edits: Vec<_> = action
+            .edit
+            .unwrap()
+            .changes
+            .unwrap()
+            .into_values()
+            .flatten()
+            .collect();
+
+        edits.sort_by_key(|edit| edit.range.start);
+        let mut text = old_text.clone();
+        for edit in edits.into_iter().rev() {
+            let range = line_index.offset_lsp_range(edit.range);
+            text.replace_range::<std::ops::Range<usize>>(range.into(), &edit.new_text);
+        }
+
+        results.push((action.title, text));
+    }
+
+    Ok(results)
+}
+
+#[test]
+fn latex_r_curly_inserted() -> Result<()> {
+    let actions = find_code_actions(
+        r#"
+%TEX main.tex
+%SRC \foo{bar"#,
+        2,
+        Range::new(Position::new(0, 8), Position::new(0, 8)),
+        None,
+    )?;
+
+    assert_eq!(
+        actions,
+        vec![("Insert missing \"}\"".into(), "\\foo{bar}\n".into())]
+    );
+    Ok(())
+}
+
+#[test]
+fn latex_mismatched_environment() -> Result<()> {
+    let actions = find_code_actions(
+        r#"
+%TEX main.tex
+%SRC \begin{foo}
+%SRC \end{bar}"#,
+        3,
+        Range::new(Position::new(0, 7), Position::new(0, 10)),
+        None,
+    )?;
+
+    assert_eq!(
+        actions,
+        vec![(
+            "Change \\end{bar} to \\end{foo}".into(),
+            "\\begin{foo}\n\\end{foo}\n".into()
+        )]
+    );
+    Ok(())
+}
+
+#[test]
+fn latex_no_quick_fix() -> Result<()> {
+    let actions = find_code_actions(
+        r#"
+%TEX main.tex
+%SRC }"#,
+        1,
+        Range::new(Position::new(0, 0), Position::new(0, 1)),
+        None,
+    )?;
+
+    assert_eq!(actions, Vec::new());
+    Ok(())
+}
+
+#[test]
+fn bibtex_expecting_eq() -> Result<()> {
+    let actions = find_code_actions(
+        r#"
+%BIB main.bib
+%SRC @article{foo, title {bar}}"#,
+        7,
+        Range::new(Position::new(0, 14), Position::new(0, 19)),
+        None,
+    )?;
+
+    assert_eq!(
+        actions,
+        vec![(
+            "Insert missing \"=\"".into(),
+            "@article{foo, title = {bar}}\n".into()
+        )]
+    );
+    Ok(())
+}
+
+#[test]
+fn bibtex_expecting_r_curly() -> Result<()> {
+    let actions = find_code_actions(
+        r#"
+%BIB main.bib
+%SRC @article{foo, title = {bar},"#,
+        6,
+        Range::new(Position::new(0, 28), Position::new(0, 28)),
+        None,
+    )?;
+
+    assert_eq!(
+        actions,
+        vec![(
+            "Insert missing \"}\"".into(),
+            "@article{foo, title = {bar},}\n".into()
+        )]
+    );
+    Ok(())
+}
+
+#[test]
+fn only_refactorings() -> Result<()> {
+    let actions = find_code_actions(
+        r#"
+%TEX main.tex
+%SRC \foo{bar"#,
+        2,
+        Range::new(Position::new(0, 8), Position::new(0, 8)),
+        Some(vec![CodeActionKind::REFACTOR]),
+    )?;
+
+    assert_eq!(actions, Vec::new());
+    Ok(())
+}
@@ -0,0 +1,267 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use lsp_server::Response;
+use lsp_types::{
+    request::{
+        DocumentDiagnosticRequest, Request, WorkspaceDiagnosticRefresh, WorkspaceDiagnosticRequest,
+    },
+    ClientCapabilities, DiagnosticClientCapabilities, DiagnosticWorkspaceClientCapabilities,
+    DocumentDiagnosticParams, DocumentDiagnosticReport, DocumentDiagnosticReportResult,
+    NumberOrString, PreviousResultId, TextDocumentClientCapabilities, TextDocumentIdentifier,
+    WorkspaceClientCapabilities, WorkspaceDiagnosticParams, WorkspaceDiagnosticReportResult,
+    WorkspaceDocumentDiagnosticReport,
+};
+
+use crate::lsp::{client::Client, fixture};
+
+fn create_client(fixture: &str) -> Result<Client> {
+    let mut client = Client::spawn()?;
+    client.initialize(
+        ClientCapabilities {
+            text_document: Some(TextDocumentClientCapabilities {
+                diagnostic: Some(DiagnosticClientCapabilities::default()),
+                ..TextDocumentClientCapabilities::default()
+            }),
+            ..ClientCapabilities::default()
+        },
+        None,
+    )?;
+
+    let fixture = fixture::parse(fixture);
+    for file in fixture.files {
+        client.open(file.name, file.lang, file.text)?;
+    }
+
+    Ok(client)
+}
+
+fn pull_document(
+    client: &mut Client,
+    name: &str,
+    previous_result_id: Option<String>,
+) -> Result<DocumentDiagnosticReport> {
+    let result = client.request::<DocumentDiagnosticRequest>(DocumentDiagnosticParams {
+        text_document: TextDocumentIdentifier::new(client.uri(name)?),
+        identifier: None,
+        previous_result_id,
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    })?;
+
+    match result {
+        DocumentDiagnosticReportResult::Report(report) => Ok(report),
+        DocumentDiagnosticReportResult::Partial(_) => unreachable!(),
+    }
+}
+
+#[test]
+fn document_full() -> Result<()> {
+    let mut client = create_client(
+        r#"
+%TEX main.tex
+%SRC \ref{foo}
+"#,
+    )?;
+
+    let report = pull_document(&mut client, "main.tex", None)?;
+    client.shutdown()?;
+
+    match report {
+        DocumentDiagnosticReport::Full(report) => {
+            let report = report.full_document_diagnostic_report;
+            assert!(report.result_id.is_some());
+            assert_eq!(report.items.len(), 1);
+            assert_eq!(report.items[0].code, Some(NumberOrString::Number(9)));
+        }
+        DocumentDiagnosticReport::Unchanged(_) => panic!("expected a full report"),
+    };
+
+    Ok(())
+}
+
+#[test]
+fn document_unchanged() -> Result<()> {
+    let mut client = create_client(
+        r#"
+%TEX main.tex
+%SRC \ref{foo}
+"#,
+    )?;
+
+    let result_id = match pull_document(&mut client, "main.tex", None)? {
+        DocumentDiagnosticReport::Full(report) => report.full_document_diagnostic_report.result_id,
+        DocumentDiagnosticReport::Unchanged(_) => panic!("expected a full report"),
+    };
+
+    let report = pull_document(&mut client, "main.tex", result_id.clone())?;
+    client.shutdown()?;
+
+    match report {
+        DocumentDiagnosticReport::Unchanged(report) => {
+            assert_eq!(
+                Some(report.unchanged_document_diagnostic_report.result_id),
+                result_id
+            );
+        }
+        DocumentDiagnosticReport::Full(_) => panic!("expected an unchanged report"),
+    };
+
+    Ok(())
+}
+
+#[test]
+fn document_closed() -> Result<()> {
+    let mut client = create_client("")?;
+    client.store_on_disk("main.tex", "\\begin{foo}\n\\end{bar}")?;
+
+    let report = pull_document(&mut client, "main.tex", None)?;
+    client.shutdown()?;
+
+    match report {
+        DocumentDiagnosticReport::Full(report) => {
+            let items = report.full_document_diagnostic_report.items;
+            assert_eq!(items.len(), 1);
+            assert_eq!(items[0].code, Some(NumberOrString::Number(3)));
+        }
+        DocumentDiagnosticReport::Unchanged(_) => panic!("expected a full report"),
+    };
+
+    Ok(())
+}
+
+#[test]
+fn workspace() -> Result<()> {
+    let mut client = create_client(
+        r#"
+%TEX main.tex
+%SRC \addbibresource{main.bib}
+%SRC \cite{foo}
+
+%BIB main.bib
+%SRC @article{bar,}
+"#,
+    )?;
+
+    let params = |previous_result_ids| WorkspaceDiagnosticParams {
+        identifier: None,
+        previous_result_ids,
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+
+    let items = match client.request::<WorkspaceDiagnosticRequest>(params(Vec::new()))? {
+        WorkspaceDiagnosticReportResult::Report(report) => report.items,
+        WorkspaceDiagnosticReportResult::Partial(_) => unreachable!(),
+    };
+
+    let mut previous_result_ids = Vec::new();
+    let mut codes = Vec::new();
+    for item in items {
+        match item {
+            WorkspaceDocumentDiagnosticReport::Full(report) => {
+                let report_uri = report.uri;
+                let report = report.full_document_diagnostic_report;
+                for item in report.items {
+                    codes.push((report_uri.clone(), item.code.unwrap()));
+                }
+
+                previous_result_ids.push(PreviousResultId {
+                    uri: report_uri,
+                    value: report.result_id.unwrap(),
+                });
+            }
+            WorkspaceDocumentDiagnosticReport::Unchanged(_) => panic!("expected a full report"),
+        };
+    }
+
+    codes.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        codes,
+        vec![
+            (client.uri("main.bib")?, NumberOrString::Number(12)),
+            (client.uri("main.tex")?, NumberOrString::Number(10)),
+        ]
+    );
+
+    let items = match client.request::<WorkspaceDiagnosticRequest>(params(previous_result_ids))? {
+        WorkspaceDiagnosticReportResult::Report(report) => report.items,
+        WorkspaceDiagnosticReportResult::Partial(_) => unreachable!(),
+    };
+
+    client.shutdown()?;
+
+    assert_eq!(items.len(), 2);
+    assert!(items
+        .iter()
+        .all(|item| matches!(item, WorkspaceDocumentDiagnosticReport::Unchanged(_))));
+
+    Ok(())
+}
+
+#[test]
+fn provider_requires_pull_support() -> Result<()> {
+    let mut client = Client::spawn()?;
+    let result = client.initialize(ClientCapabilities::default(), None)?;
+    client.shutdown()?;
+    assert_eq!(result.capabilities.diagnostic_provider, None);
+    Ok(())
+}
+
+#[test]
+fn refresh() -> Result<()> {
+    let mut client = Client::spawn()?;
+    let result = client.initialize(
+        ClientCapabilities {
+            text_document: Some(TextDocumentClientCapabilities {
+                diagnostic: Some(DiagnosticClientCapabilities::default()),
+                ..TextDocumentClientCapabilities::default()
+            }),
+            workspace: Some(WorkspaceClientCapabilities {
+                diagnostic: Some(DiagnosticWorkspaceClientCapabilities {
+                    refresh_support: Some(true),
+                }),
+                ..WorkspaceClientCapabilities::default()
+            }),
+            ..ClientCapabilities::default()
+        },
+        None,
+    )?;
+
+    assert!(result.capabilities.diagnostic_provider.is_some());
+
+    client.store_on_disk("chapter.tex", "\\label{foo}")?;
+    client.open(
+        "main.tex",
+        "latex",
+        "\\input{chapter}\n\\ref{foo}".to_string(),
+    )?;
+
+    // Removing the label changes the diagnostics of main.tex, which the client has not pulled yet.
+    client.open("chapter.tex", "latex", String::new())?;
+    let request = client
+        .incoming()
+        .requests
+        .recv_timeout(Duration::from_secs(10))?;
+
+    assert_eq!(request.method, WorkspaceDiagnosticRefresh::METHOD);
+    client.respond(Response::new_ok(request.id, ()))?;
+
+    match pull_document(&mut client, "main.tex", None)? {
+        DocumentDiagnosticReport::Full(report) => {
+            assert_eq!(report.full_document_diagnostic_report.items.len(), 1);
+        }
+        DocumentDiagnosticReport::Unchanged(_) => panic!("expected a full report"),
+    };
+
+    // The client already knows all diagnostics.
+    client.open("chapter.tex", "latex", "% foo".to_string())?;
+    assert!(client
+        .incoming()
+        .requests
+        .recv_timeout(Duration::from_secs(1))
+        .is_err());
+
+    client.shutdown()?;
+    Ok(())
+}
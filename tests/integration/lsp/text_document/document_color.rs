@@ -0,0 +1,190 @@
+use anyhow::Result;
+use lsp_types::{
+    request::{ColorPresentationRequest, DocumentColor},
+    ClientCapabilities, Color, ColorPresentationParams, DocumentColorParams, Position, Range,
+    TextDocumentIdentifier,
+};
+
+use crate::lsp::{client::Client, fixture};
+
+/// The line, the start and end character and the RGB value of a color.
+type ColorSpan = (u32, u32, u32, [u8; 3]);
+
+fn find_colors(fixture: &str) -> Result<Vec<ColorSpan>> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+
+    let fixture = fixture::parse(fixture);
+    for file in fixture.files {
+        client.open(file.name, file.lang, file.text)?;
+    }
+
+    let colors = client.request::<DocumentColor>(DocumentColorParams {
+        text_document: TextDocumentIdentifier::new(client.uri(fixture.cursor.unwrap().name)?),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    })?;
+
+    client.shutdown()?;
+
+    Ok(colors
+        .into_iter()
+        .map(|info| {
+            let color = [info.color.red, info.color.green, info.color.blue]
+                .map(|value| (value * 255.0).round() as u8);
+
+            assert_eq!(info.range.start.line, info.range.end.line);
+            (
+                info.range.start.line,
+                info.range.start.character,
+                info.range.end.character,
+                color,
+            )
+        })
+        .collect())
+}
+
+fn find_presentations(fixture: &str, range: Range, color: [f32; 3]) -> Result<Vec<String>> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+
+    let fixture = fixture::parse(fixture);
+    for file in fixture.files {
+        client.open(file.name, file.lang, file.text)?;
+    }
+
+    let presentations = client.request::<ColorPresentationRequest>(ColorPresentationParams {
+        text_document: TextDocumentIdentifier::new(client.uri(fixture.cursor.unwrap().name)?),
+        color: Color {
+            red: color[0],
+            green: color[1],
+            blue: color[2],
+            alpha: 1.0,
+        },
+        range,
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    })?;
+
+    client.shutdown()?;
+
+    Ok(presentations
+        .into_iter()
+        .map(|presentation| {
+            let edit = presentation.text_edit.unwrap();
+            assert_eq!(edit.range, range);
+            edit.new_text
+        })
+        .collect())
+}
+
+#[test]
+fn definitions() -> Result<()> {
+    let colors = find_colors(
+        r#"
+%TEX main.tex
+%SRC \definecolor{foo}{rgb}{1,0.5,0}
+%CUR ^
+%SRC \definecolor{bar}{RGB}{0,128,255}
+%SRC \definecolor{baz}{HTML}{FF0000}
+%SRC \definecolor{qux}{cmyk}{0,1,1,0.5}
+%SRC \definecolor{quux}{gray}{0.5}
+%SRC \definecolor{corge}{hsb}{0.5,1,1}
+"#,
+    )?;
+
+    assert_eq!(
+        colors,
+        vec![
+            (0, 23, 30, [255, 128, 0]),
+            (1, 23, 32, [0, 128, 255]),
+            (2, 24, 30, [255, 0, 0]),
+            (3, 24, 33, [128, 0, 0]),
+            (4, 25, 28, [128, 128, 128]),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn references() -> Result<()> {
+    let colors = find_colors(
+        r#"
+%TEX main.tex
+%SRC \input{colors}
+%SRC \color{red}
+%CUR ^
+%SRC \color{Maroon}
+%SRC \color{foo}
+%SRC \color{red!50}
+%SRC \color{unknown}
+
+%TEX colors.tex
+%SRC \definecolor{foo}{gray}{0.2}
+"#,
+    )?;
+
+    assert_eq!(
+        colors,
+        vec![
+            (1, 7, 10, [255, 0, 0]),
+            (2, 7, 13, [173, 0, 0]),
+            (3, 7, 10, [51, 51, 51]),
+            (4, 7, 13, [255, 128, 128]),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn presentation_rgb() -> Result<()> {
+    let range = Range::new(Position::new(0, 23), Position::new(0, 30));
+    let presentations = find_presentations(
+        r#"
+%TEX main.tex
+%SRC \definecolor{foo}{rgb}{1,0.5,0}
+%CUR ^
+"#,
+        range,
+        [0.0, 0.25, 1.0],
+    )?;
+
+    assert_eq!(presentations, vec!["0,0.25,1"]);
+    Ok(())
+}
+
+#[test]
+fn presentation_html() -> Result<()> {
+    let range = Range::new(Position::new(0, 24), Position::new(0, 30));
+    let presentations = find_presentations(
+        r#"
+%TEX main.tex
+%SRC \definecolor{foo}{HTML}{FF0000}
+%CUR ^
+"#,
+        range,
+        [0.0, 0.5, 1.0],
+    )?;
+
+    assert_eq!(presentations, vec!["0080FF"]);
+    Ok(())
+}
+
+#[test]
+fn presentation_reference() -> Result<()> {
+    let range = Range::new(Position::new(0, 7), Position::new(0, 10));
+    let presentations = find_presentations(
+        r#"
+%TEX main.tex
+%SRC \color{red}
+%CUR ^
+"#,
+        range,
+        [0.0, 0.5, 1.0],
+    )?;
+
+    assert_eq!(presentations, Vec::<String>::new());
+    Ok(())
+}
@@ -1,44 +1,81 @@
 use anyhow::Result;
 use insta::assert_snapshot;
 use lsp_types::{
-    request::Formatting, ClientCapabilities, DocumentFormattingParams, FormattingOptions,
-    TextDocumentIdentifier,
+    notification::DidChangeConfiguration,
+    request::{Formatting, OnTypeFormatting, RangeFormatting, Request},
+    ClientCapabilities, DidChangeConfigurationParams, DocumentFormattingParams,
+    DocumentOnTypeFormattingParams, DocumentRangeFormattingParams, FormattingOptions, Position,
+    Range, TextDocumentIdentifier, TextDocumentPositionParams, TextEdit,
 };
 use texlab::{LineIndex, LineIndexExt};
 
 use crate::lsp::{client::Client, fixture};
 
 fn format(fixture: &str) -> Result<String> {
+    format_with_settings(fixture, serde_json::Value::Null)
+}
+
+fn format_with_settings(fixture: &str, settings: serde_json::Value) -> Result<String> {
+    apply_edits::<Formatting>(fixture, settings, |text_document, options| {
+        DocumentFormattingParams {
+            text_document,
+            options,
+            work_done_progress_params: Default::default(),
+        }
+    })
+}
+
+fn apply_edits<R>(
+    fixture: &str,
+    settings: serde_json::Value,
+    params: impl FnOnce(TextDocumentIdentifier, FormattingOptions) -> R::Params,
+) -> Result<String>
+where
+    R: Request<Result = Option<Vec<TextEdit>>>,
+{
+    let (old_text, actual_edits) = request_edits::<R>(fixture, settings, params)?;
+    let line_index = LineIndex::new(&old_text);
+    let mut actual_text = old_text;
+    for edit in actual_edits.into_iter().rev() {
+        let range = line_index.offset_lsp_range(edit.range);
+        actual_text.replace_range::<std::ops::Range<usize>>(range.into(), &edit.new_text);
+    }
+
+    Ok(actual_text)
+}
+
+fn request_edits<R>(
+    fixture: &str,
+    settings: serde_json::Value,
+    params: impl FnOnce(TextDocumentIdentifier, FormattingOptions) -> R::Params,
+) -> Result<(String, Vec<TextEdit>)>
+where
+    R: Request<Result = Option<Vec<TextEdit>>>,
+{
     let mut client = Client::spawn()?;
     client.initialize(ClientCapabilities::default(), None)?;
+    if !settings.is_null() {
+        client.notify::<DidChangeConfiguration>(DidChangeConfigurationParams { settings })?;
+    }
 
     let fixture = fixture::parse(fixture);
     let file = fixture.files.into_iter().next().unwrap();
     let old_text = file.text.clone();
     client.open(file.name, file.lang, file.text)?;
 
+    let options = FormattingOptions {
+        insert_spaces: true,
+        tab_size: 4,
+        ..Default::default()
+    };
+
+    let text_document = TextDocumentIdentifier::new(client.uri(file.name)?);
     let actual_edits = client
-        .request::<Formatting>(DocumentFormattingParams {
-            text_document: TextDocumentIdentifier::new(client.uri(file.name)?),
-            work_done_progress_params: Default::default(),
-            options: FormattingOptions {
-                insert_spaces: true,
-                tab_size: 4,
-                ..Default::default()
-            },
-        })?
+        .request::<R>(params(text_document, options))?
         .unwrap_or_default();
 
     client.shutdown()?;
-
-    let line_index = LineIndex::new(&old_text);
-    let mut actual_text = old_text;
-    for edit in actual_edits.into_iter().rev() {
-        let range = line_index.offset_lsp_range(edit.range);
-        actual_text.replace_range::<std::ops::Range<usize>>(range.into(), &edit.new_text);
-    }
-
-    Ok(actual_text)
+    Ok((old_text, actual_edits))
 }
 
 #[test]
@@ -124,6 +161,154 @@ fn bibtex_internal_parens() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn bibtex_internal_unchanged_entries() -> Result<()> {
+    let (_, actual_edits) = request_edits::<Formatting>(
+        r#"
+%BIB main.bib
+%SRC @article{foo,
+%SRC     author = {Foo},
+%SRC }
+%SRC @article{bar, author = {Bar}}
+%SRC @article{baz,
+%SRC     author = {Baz},
+%SRC }"#,
+        serde_json::Value::Null,
+        |text_document, options| DocumentFormattingParams {
+            text_document,
+            options,
+            work_done_progress_params: Default::default(),
+        },
+    )?;
+
+    assert_eq!(
+        actual_edits,
+        vec![TextEdit::new(
+            Range::new(Position::new(3, 0), Position::new(4, 0)),
+            "@article{bar,\n    author = {Bar},\n}\n".to_string(),
+        )]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bibtex_internal_style() -> Result<()> {
+    assert_eq!(
+        format_with_settings(
+            r#"
+%BIB main.bib
+%SRC @Article{foo, Title = "Foo", Author = {Bar}, YEAR = {2020}}"#,
+            serde_json::json!({
+                "bibtexFormatting": {
+                    "fieldOrder": ["author", "year"],
+                    "lowercaseEntryTypes": false,
+                    "lowercaseFieldNames": true,
+                    "delimiters": "braces",
+                    "trailingComma": false,
+                    "alignFields": true
+                }
+            }),
+        )?,
+        "@Article{foo,\n    author = {Bar},\n    year   = {2020},\n    title  = {Foo}\n}\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bibtex_internal_quotes() -> Result<()> {
+    assert_eq!(
+        format_with_settings(
+            r#"
+%BIB main.bib
+%SRC @article{foo, title = {Foo} # bar, author = {Foo "Bar" Baz}}"#,
+            serde_json::json!({ "bibtexFormatting": { "delimiters": "quotes" } }),
+        )?,
+        "@article{foo,\n    title = \"Foo\" # bar,\n    author = {Foo \"Bar\" Baz},\n}\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bibtex_internal_quotes_accent() -> Result<()> {
+    assert_eq!(
+        format_with_settings(
+            r#"
+%BIB main.bib
+%SRC @article{foo, title = {Schr\"odinger}, author = {Erwin {Schr\"odinger}}}"#,
+            serde_json::json!({ "bibtexFormatting": { "delimiters": "quotes" } }),
+        )?,
+        "@article{foo,\n    title = {Schr\\\"odinger},\n    author = \"Erwin {Schr\\\"odinger}\",\n}\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bibtex_internal_sort_by_key() -> Result<()> {
+    assert_eq!(
+        format_with_settings(
+            r#"
+%BIB main.bib
+%SRC @article{b,}
+%SRC @article{a,}
+%SRC @string{foo = "foo"}
+%SRC 
+%SRC 
+%SRC @article{d,}
+%SRC @article{c,}"#,
+            serde_json::json!({
+                "bibtexFormatting": {
+                    "sortEntries": "key",
+                    "blankLinesBetweenEntries": 1
+                }
+            }),
+        )?,
+        "@article{a,\n}\n\n@article{b,\n}\n\n@string{foo = \"foo\"}\n\n@article{c,\n}\n\n@article{d,\n}\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bibtex_internal_sort_with_comments() -> Result<()> {
+    assert_eq!(
+        format_with_settings(
+            r#"
+%BIB main.bib
+%SRC % Header
+%SRC 
+%SRC % About b
+%SRC @article{b,}
+%SRC @comment{About a}
+%SRC @article{a,}"#,
+            serde_json::json!({ "bibtexFormatting": { "sortEntries": "key" } }),
+        )?,
+        "% Header\n\n@comment{About a}\n@article{a,\n}\n% About b\n@article{b,\n}\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bibtex_internal_sort_by_year() -> Result<()> {
+    assert_eq!(
+        format_with_settings(
+            r#"
+%BIB main.bib
+%SRC @article{a, year = {2021}}
+%SRC @article{b}
+%SRC @article{c, date = {2019-01-01}}"#,
+            serde_json::json!({ "bibtexFormatting": { "sortEntries": "year" } }),
+        )?,
+        "@article{c,\n    date = {2019-01-01},\n}\n@article{a,\n    year = {2021},\n}\n@article{b,\n}\n"
+    );
+
+    Ok(())
+}
+
 #[test]
 fn bibtex_internal_string() -> Result<()> {
     assert_snapshot!(format(
@@ -146,3 +331,312 @@ fn bibtex_internal_preamble() -> Result<()> {
 
     Ok(())
 }
+
+fn format_latex(fixture: &str) -> Result<String> {
+    format_with_settings(
+        fixture,
+        serde_json::json!({
+            "latexFormatter": "texlab",
+            "formatterLineLength": 40
+        }),
+    )
+}
+
+#[test]
+fn latex_internal_environment() -> Result<()> {
+    assert_eq!(
+        format_latex(
+            r#"
+%TEX main.tex
+%SRC \begin{document}
+%SRC \begin{figure}
+%SRC       \centering
+%SRC \begin{tabular}{cc}
+%SRC a & b \\
+%SRC \end{tabular}
+%SRC   \end{figure}
+%SRC \end{document}"#,
+        )?,
+        r#"\begin{document}
+\begin{figure}
+    \centering
+    \begin{tabular}{cc}
+        a & b \\
+    \end{tabular}
+\end{figure}
+\end{document}
+"#
+    );
+
+    Ok(())
+}
+
+#[test]
+fn latex_internal_enum_item() -> Result<()> {
+    assert_eq!(
+        format_latex(
+            r#"
+%TEX main.tex
+%SRC \begin{itemize}
+%SRC \item    foo
+%SRC bar \item[a]  baz
+%SRC \end{itemize}"#,
+        )?,
+        r#"\begin{itemize}
+    \item foo
+        bar
+    \item[a] baz
+\end{itemize}
+"#
+    );
+
+    Ok(())
+}
+
+#[test]
+fn latex_internal_wrap_prose() -> Result<()> {
+    assert_eq!(
+        format_latex(
+            r#"
+%TEX main.tex
+%SRC Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor.   "#,
+        )?,
+        r#"Lorem ipsum dolor sit amet, consectetur
+adipiscing elit, sed do eiusmod tempor.
+"#
+    );
+
+    Ok(())
+}
+
+#[test]
+fn latex_internal_group() -> Result<()> {
+    assert_eq!(
+        format_latex(
+            r#"
+%TEX main.tex
+%SRC \newcommand{\foo}{%
+%SRC bar
+%SRC }"#,
+        )?,
+        r#"\newcommand{\foo}{%
+    bar
+}
+"#
+    );
+
+    Ok(())
+}
+
+#[test]
+fn latex_internal_keep_verbatim_math_and_comments() -> Result<()> {
+    let text = r#"\begin{itemize}
+    \item
+        \begin{verbatim}
+  foo    bar
+        \end{verbatim}
+    \item $a    +   b + c + d + e + f + g + h + i + j$
+    % Lorem ipsum dolor sit amet, consectetur adipiscing elit
+\end{itemize}
+\begin{equation}
+ x =
+   y
+\end{equation}
+\[
+  z
+\]
+"#;
+
+    let fixture: String = text
+        .lines()
+        .map(|line| format!("%SRC {}\n", line))
+        .collect();
+
+    assert_eq!(
+        format_latex(&format!("\n%TEX main.tex\n{}", fixture))?,
+        text
+    );
+    Ok(())
+}
+
+fn format_range(fixture: &str, settings: serde_json::Value, range: Range) -> Result<String> {
+    apply_edits::<RangeFormatting>(fixture, settings, |text_document, options| {
+        DocumentRangeFormattingParams {
+            text_document,
+            range,
+            options,
+            work_done_progress_params: Default::default(),
+        }
+    })
+}
+
+#[test]
+fn bibtex_internal_range() -> Result<()> {
+    assert_eq!(
+        format_range(
+            r#"
+%BIB main.bib
+%SRC @article{foo, author = {Foo}}
+%SRC @article{bar, author = {Bar}}"#,
+            serde_json::Value::Null,
+            Range::new(Position::new(1, 0), Position::new(1, 0)),
+        )?,
+        "@article{foo, author = {Foo}}\n@article{bar,\n    author = {Bar},\n}\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn latex_internal_range() -> Result<()> {
+    assert_eq!(
+        format_range(
+            r#"
+%TEX main.tex
+%SRC \begin{itemize}
+%SRC \item foo
+%SRC \item bar
+%SRC \end{itemize}"#,
+            serde_json::json!({ "latexFormatter": "texlab" }),
+            Range::new(Position::new(1, 0), Position::new(2, 0)),
+        )?,
+        "\\begin{itemize}\n    \\item foo\n\\item bar\n\\end{itemize}\n"
+    );
+
+    Ok(())
+}
+
+fn format_on_type(fixture: &str, position: Position, ch: &str) -> Result<String> {
+    format_on_type_with_settings(
+        fixture,
+        serde_json::json!({ "latexFormatter": "texlab" }),
+        position,
+        ch,
+    )
+}
+
+fn format_on_type_with_settings(
+    fixture: &str,
+    settings: serde_json::Value,
+    position: Position,
+    ch: &str,
+) -> Result<String> {
+    apply_edits::<OnTypeFormatting>(fixture, settings, |text_document, options| {
+        DocumentOnTypeFormattingParams {
+            text_document_position: TextDocumentPositionParams::new(text_document, position),
+            ch: ch.to_string(),
+            options,
+        }
+    })
+}
+
+#[test]
+fn on_type_line_break() -> Result<()> {
+    assert_eq!(
+        format_on_type(
+            r#"
+%TEX main.tex
+%SRC \begin{document}
+%SRC \begin{center}
+%SRC 
+%SRC \end{center}
+%SRC \end{document}"#,
+            Position::new(2, 0),
+            "\n",
+        )?,
+        "\\begin{document}\n\\begin{center}\n    \n\\end{center}\n\\end{document}\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn on_type_item() -> Result<()> {
+    assert_eq!(
+        format_on_type(
+            r#"
+%TEX main.tex
+%SRC \begin{itemize}
+%SRC     \item foo
+%SRC         \item "#,
+            Position::new(2, 14),
+            " ",
+        )?,
+        "\\begin{itemize}\n    \\item foo\n    \\item \n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn on_type_end() -> Result<()> {
+    assert_eq!(
+        format_on_type(
+            r#"
+%TEX main.tex
+%SRC \begin{itemize}
+%SRC     \item foo
+%SRC     \end{itemize}"#,
+            Position::new(2, 17),
+            "}",
+        )?,
+        "\\begin{itemize}\n    \\item foo\n\\end{itemize}\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn on_type_ignore_other_characters() -> Result<()> {
+    assert_eq!(
+        format_on_type(
+            r#"
+%TEX main.tex
+%SRC \begin{itemize}
+%SRC \item foo
+%SRC \end{itemize}"#,
+            Position::new(1, 9),
+            "o",
+        )?,
+        "\\begin{itemize}\n\\item foo\n\\end{itemize}\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn on_type_ignore_other_spaces() -> Result<()> {
+    assert_eq!(
+        format_on_type(
+            r#"
+%TEX main.tex
+%SRC \begin{itemize}
+%SRC         \item foo bar
+%SRC \end{itemize}"#,
+            Position::new(1, 18),
+            " ",
+        )?,
+        "\\begin{itemize}\n        \\item foo bar\n\\end{itemize}\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn on_type_latexindent() -> Result<()> {
+    assert_eq!(
+        format_on_type_with_settings(
+            r#"
+%TEX main.tex
+%SRC \begin{itemize}
+%SRC     \item foo
+%SRC     \end{itemize}"#,
+            serde_json::json!({ "latexFormatter": "latexindent" }),
+            Position::new(2, 17),
+            "}",
+        )?,
+        "\\begin{itemize}\n    \\item foo\n    \\end{itemize}\n"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use anyhow::Result;
+use lsp_types::{
+    notification::DidChangeConfiguration, request::InlayHintRequest, ClientCapabilities,
+    DidChangeConfigurationParams, InlayHintLabel, InlayHintParams, Position, Range,
+    TextDocumentIdentifier,
+};
+
+use crate::lsp::{client::Client, fixture};
+
+fn find_hints(
+    fixture: &str,
+    settings: serde_json::Value,
+    range: Range,
+) -> Result<Vec<(u32, u32, String)>> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+    client.notify::<DidChangeConfiguration>(DidChangeConfigurationParams { settings })?;
+
+    let fixture = fixture::parse(fixture);
+    for file in fixture.files {
+        client.open(file.name, file.lang, file.text)?;
+    }
+
+    let hints = client
+        .request::<InlayHintRequest>(InlayHintParams {
+            text_document: TextDocumentIdentifier::new(client.uri(fixture.cursor.unwrap().name)?),
+            range,
+            work_done_progress_params: Default::default(),
+        })?
+        .unwrap_or_default();
+
+    client.shutdown()?;
+
+    Ok(hints
+        .into_iter()
+        .map(|hint| {
+            let label = match hint.label {
+                InlayHintLabel::String(label) => label,
+                InlayHintLabel::LabelParts(parts) => {
+                    parts.into_iter().map(|part| part.value).collect()
+                }
+            };
+
+            (hint.position.line, hint.position.character, label)
+        })
+        .collect())
+}
+
+const FIXTURE: &str = r#"
+%TEX main.tex
+%SRC \documentclass{article}
+%SRC \begin{document}
+%SRC \section{Foo}\label{sec:foo}
+%SRC \begin{figure}
+%SRC     \caption{Bar}\label{fig:bar}
+%SRC \end{figure}
+%SRC See \ref{sec:foo} and \cref{fig:bar,fig:baz}.
+%SRC \cite{foo,bar}
+%SRC \bibliography{main}
+%SRC \end{document}
+%CUR ^
+
+%TEX main.aux
+%SRC \relax
+%SRC \newlabel{sec:foo}{{1}{1}}
+%SRC \newlabel{fig:bar}{{2}{1}}
+
+%BIB main.bib
+%SRC @article{foo, author = {Donald E. Knuth}, year = {1984}}
+%SRC @article{bar, author = {Foo Bar and Baz Qux}, title = {Qux}, date = {2020-01-01}}"#;
+
+fn full_range() -> Range {
+    Range::new(Position::new(0, 0), Position::new(9, 14))
+}
+
+#[test]
+fn all() -> Result<()> {
+    let hints = find_hints(FIXTURE, serde_json::json!({}), full_range())?;
+    assert_eq!(
+        hints,
+        vec![
+            (2, 13, "1".to_string()),
+            (6, 17, "Section 1".to_string()),
+            (6, 44, "Figure 2".to_string()),
+            (
+                7,
+                14,
+                "D. Knuth: (1984); F. Bar, B. Qux: \"Qux\". (Jan. 20…".to_string()
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn range() -> Result<()> {
+    let range = Range::new(Position::new(7, 0), Position::new(8, 0));
+    let hints = find_hints(FIXTURE, serde_json::json!({}), range)?;
+    assert_eq!(
+        hints,
+        vec![(
+            7,
+            14,
+            "D. Knuth: (1984); F. Bar, B. Qux: \"Qux\". (Jan. 20…".to_string()
+        )]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn label_reference_range() -> Result<()> {
+    let hints = find_hints(
+        r#"
+%TEX main.tex
+%SRC \crefrange{fig:foo}{fig:bar}
+%SRC \Crefrange{fig:foo}{fig:baz}
+%SRC \begin{figure}\caption{Foo}\label{fig:foo}\end{figure}
+%SRC \begin{figure}\caption{Bar}\label{fig:bar}\end{figure}
+%CUR ^
+
+%TEX main.aux
+%SRC \newlabel{fig:foo}{{1}{1}}
+%SRC \newlabel{fig:bar}{{3}{1}}"#,
+        serde_json::json!({}),
+        Range::new(Position::new(0, 0), Position::new(2, 0)),
+    )?;
+
+    assert_eq!(
+        hints,
+        vec![
+            (0, 28, "Figure 1 to Figure 3".to_string()),
+            (1, 28, "Figure 1 to ?".to_string()),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn disabled() -> Result<()> {
+    let settings = serde_json::json!({
+        "inlayHints": {
+            "labelReferences": false,
+            "citations": false,
+            "sectionNumbers": false
+        }
+    });
+
+    let hints = find_hints(FIXTURE, settings, full_range())?;
+    assert_eq!(hints, Vec::new());
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use lsp_types::{request::LinkedEditingRange, ClientCapabilities, LinkedEditingRangeParams};
+
+use crate::lsp::{client::Client, fixture};
+
+fn check(fixture: &str) -> Result<()> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+
+    let fixture = fixture::parse(fixture);
+    for file in fixture.files {
+        client.open(file.name, file.lang, file.text)?;
+    }
+
+    let mut expected_ranges = Vec::new();
+    for ranges in fixture.ranges.values() {
+        expected_ranges.push(ranges[&1].range);
+        expected_ranges.push(ranges[&2].range);
+    }
+
+    let actual_ranges = client
+        .request::<LinkedEditingRange>(LinkedEditingRangeParams {
+            text_document_position_params: fixture.cursor.unwrap().into_params(&client)?,
+            work_done_progress_params: Default::default(),
+        })?
+        .map(|result| result.ranges)
+        .unwrap_or_default();
+
+    client.shutdown()?;
+
+    assert_eq!(actual_ranges, expected_ranges);
+    Ok(())
+}
+
+#[test]
+fn environment_begin() -> Result<()> {
+    check(
+        r#"
+%TEX main.tex
+%SRC \begin{itemize}
+%CUR          ^
+%1.1        ^^^^^^^
+%SRC     \item Foo
+%SRC \end{itemize}
+%1.2      ^^^^^^^
+"#,
+    )
+}
+
+#[test]
+fn environment_end() -> Result<()> {
+    check(
+        r#"
+%TEX main.tex
+%SRC \begin{foo}
+%1.1        ^^^
+%SRC     \begin{bar}
+%SRC     \end{bar}
+%SRC \end{foo}
+%CUR         ^
+%1.2      ^^^
+"#,
+    )
+}
+
+#[test]
+fn environment_mismatched() -> Result<()> {
+    check(
+        r#"
+%TEX main.tex
+%SRC \begin{foo}
+%CUR         ^
+%SRC \end{bar}
+"#,
+    )
+}
+
+#[test]
+fn equation() -> Result<()> {
+    check(
+        r#"
+%TEX main.tex
+%SRC \[
+%CUR  ^
+%1.1 ^^
+%SRC     x = 1
+%SRC \]
+%1.2 ^^
+"#,
+    )
+}
+
+#[test]
+fn text() -> Result<()> {
+    check(
+        r#"
+%TEX main.tex
+%SRC \begin{foo}
+%SRC     Bar
+%CUR      ^
+%SRC \end{foo}
+"#,
+    )
+}
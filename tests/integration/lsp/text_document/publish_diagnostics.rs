@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use anyhow::Result;
 use insta::{assert_json_snapshot, internals::Redaction};
 use lsp_types::{
@@ -7,13 +9,19 @@ use lsp_types::{
     ClientCapabilities, Diagnostic, DidChangeConfigurationParams, DidChangeWatchedFilesParams,
     FileChangeType, FileEvent, PublishDiagnosticsParams, Url,
 };
-use rustc_hash::FxHashMap;
 
 use crate::lsp::{client::Client, fixture};
 
 struct DiagnosticResult {
-    all_diagnostics: FxHashMap<Url, Vec<Diagnostic>>,
+    all_diagnostics: BTreeMap<Url, Vec<Diagnostic>>,
     uri_redaction: Redaction,
+    related_uri_redaction: Redaction,
+}
+
+fn redact_uri(uri: Url) -> Redaction {
+    insta::dynamic_redaction(move |content, _path| {
+        content.as_str().unwrap().replace(uri.as_str(), "[tmp]/")
+    })
 }
 
 fn find_diagnostics(fixture: &str, settings: serde_json::Value) -> Result<DiagnosticResult> {
@@ -43,9 +51,8 @@ fn find_diagnostics(fixture: &str, settings: serde_json::Value) -> Result<Diagno
     let result = client.shutdown()?;
 
     let uri = Url::from_directory_path(result.directory.path()).unwrap();
-    let uri_redaction = insta::dynamic_redaction(move |content, _path| {
-        content.as_str().unwrap().replace(uri.as_str(), "[tmp]/")
-    });
+    let uri_redaction = redact_uri(uri.clone());
+    let related_uri_redaction = redact_uri(uri);
 
     let all_diagnostics = result
         .incoming
@@ -62,6 +69,7 @@ fn find_diagnostics(fixture: &str, settings: serde_json::Value) -> Result<Diagno
     Ok(DiagnosticResult {
         all_diagnostics,
         uri_redaction,
+        related_uri_redaction,
     })
 }
 
@@ -69,7 +77,8 @@ macro_rules! assert_symbols {
     ($result:expr) => {
         let result = $result;
         assert_json_snapshot!(result.all_diagnostics, {
-            ".$key" => result.uri_redaction
+            ".$key" => result.uri_redaction,
+            ".*[].relatedInformation[].location.uri" => result.related_uri_redaction
         });
     };
 }
@@ -262,3 +271,183 @@ fn build_log_filter_ignored() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn undefined_label() -> Result<()> {
+    assert_symbols!(find_diagnostics(
+        r#"
+%TEX main.tex
+%SRC \documentclass{article}
+%SRC \begin{document}
+%SRC \section{Foo}\label{sec:foo}
+%SRC \include{bar}
+%SRC \end{document}
+
+%TEX bar.tex
+%SRC \ref{sec:foo} \eqref{sec:fooo} \crefrange{sec:foo}{sec:baz}"#,
+        serde_json::json!({})
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn undefined_label_aux() -> Result<()> {
+    assert_symbols!(find_diagnostics(
+        r#"
+%TEX main.tex
+%SRC \documentclass{article}
+%SRC \begin{document}
+%SRC \ref{foo}
+%SRC \end{document}
+
+%TEX main.aux
+%SRC \newlabel{foo}{{1}{1}}"#,
+        serde_json::json!({})
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn undefined_citation() -> Result<()> {
+    assert_symbols!(find_diagnostics(
+        r#"
+%TEX main.tex
+%SRC \addbibresource{main.bib}
+%SRC \cite{foo, bar}
+
+%BIB main.bib
+%SRC @article{foo,}"#,
+        serde_json::json!({})
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn unused_entry() -> Result<()> {
+    assert_symbols!(find_diagnostics(
+        r#"
+%TEX main.tex
+%SRC \addbibresource{main.bib}
+%SRC \cite{foo}
+
+%BIB main.bib
+%SRC @article{foo,}
+%SRC @article{bar,}"#,
+        serde_json::json!({})
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn unused_entry_nocite() -> Result<()> {
+    assert_symbols!(find_diagnostics(
+        r#"
+%TEX main.tex
+%SRC \addbibresource{main.bib}
+%SRC \nocite{*}
+
+%BIB main.bib
+%SRC @article{foo,}
+%SRC @article{bar,}"#,
+        serde_json::json!({})
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn duplicate_label() -> Result<()> {
+    assert_symbols!(find_diagnostics(
+        r#"
+%TEX main.tex
+%SRC \label{foo}
+%SRC \include{bar}
+%SRC \ref{foo}
+
+%TEX bar.tex
+%SRC \label{foo}"#,
+        serde_json::json!({})
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn duplicate_entry() -> Result<()> {
+    assert_symbols!(find_diagnostics(
+        r#"
+%TEX main.tex
+%SRC \addbibresource{foo.bib}
+%SRC \addbibresource{bar.bib}
+%SRC \nocite{*}
+
+%BIB foo.bib
+%SRC @article{foo,}
+
+%BIB bar.bib
+%SRC @article{foo,}
+%SRC @article{bar,}"#,
+        serde_json::json!({})
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn undefined_label_ltx() -> Result<()> {
+    assert_symbols!(find_diagnostics(
+        r#"
+%TEX main.ltx
+%SRC \label{foo}
+%SRC \ref{bar}"#,
+        serde_json::json!({})
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn undefined_citation_without_bibliography() -> Result<()> {
+    assert_symbols!(find_diagnostics(
+        r#"
+%TEX main.tex
+%SRC \cite{foo}"#,
+        serde_json::json!({})
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn undefined_citation_bibitem() -> Result<()> {
+    assert_symbols!(find_diagnostics(
+        r#"
+%TEX main.tex
+%SRC \cite{foo, bar}
+%SRC \begin{thebibliography}{9}
+%SRC \bibitem[Foo]{foo} Foo
+%SRC \end{thebibliography}"#,
+        serde_json::json!({})
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn bibtex_expecting_eq() -> Result<()> {
+    assert_symbols!(find_diagnostics(
+        r#"
+%BIB main.bib
+%SRC @article{foo,
+%SRC     author = {Bar},
+%SRC     title "Foo"
+%SRC }"#,
+        serde_json::json!({})
+    )?);
+
+    Ok(())
+}
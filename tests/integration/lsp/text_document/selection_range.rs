@@ -0,0 +1,123 @@
+use anyhow::Result;
+use lsp_types::{
+    request::SelectionRangeRequest, ClientCapabilities, SelectionRangeParams,
+    TextDocumentIdentifier,
+};
+
+use crate::lsp::{client::Client, fixture};
+
+fn find_ranges(fixture: &str) -> Result<Vec<(u32, u32, u32, u32)>> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+
+    let fixture = fixture::parse(fixture);
+    for file in fixture.files {
+        client.open(file.name, file.lang, file.text)?;
+    }
+
+    let cursor = fixture.cursor.unwrap();
+    let result = client
+        .request::<SelectionRangeRequest>(SelectionRangeParams {
+            text_document: TextDocumentIdentifier::new(client.uri(cursor.name)?),
+            positions: vec![cursor.position],
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })?
+        .unwrap_or_default();
+
+    client.shutdown()?;
+
+    let mut ranges = Vec::new();
+    let mut selection = result.into_iter().next();
+    while let Some(current) = selection {
+        let range = current.range;
+        ranges.push((
+            range.start.line,
+            range.start.character,
+            range.end.line,
+            range.end.character,
+        ));
+
+        selection = current.parent.map(|parent| *parent);
+    }
+
+    Ok(ranges)
+}
+
+#[test]
+fn latex_environment() -> Result<()> {
+    let ranges = find_ranges(
+        r#"
+%TEX main.tex
+%SRC \section{Foo}
+%SRC \begin{center}
+%SRC     Foo \textbf{Bar Baz}
+%CUR                  ^
+%SRC     Qux
+%SRC \end{center}
+%SRC Bar
+"#,
+    )?;
+
+    assert_eq!(
+        ranges,
+        vec![
+            (2, 16, 2, 19),
+            (2, 16, 2, 23),
+            (2, 15, 2, 24),
+            (2, 8, 2, 24),
+            (2, 4, 3, 7),
+            (1, 0, 4, 12),
+            (0, 0, 5, 3),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn latex_environment_name() -> Result<()> {
+    let ranges = find_ranges(
+        r#"
+%TEX main.tex
+%SRC \begin{foo}
+%CUR         ^
+%SRC \end{foo}
+"#,
+    )?;
+
+    assert_eq!(
+        ranges,
+        vec![(0, 7, 0, 10), (0, 6, 0, 11), (0, 0, 0, 11), (0, 0, 1, 9),]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bibtex_field() -> Result<()> {
+    let ranges = find_ranges(
+        r#"
+%BIB main.bib
+%SRC @article{foo,
+%SRC     title = {Foo Bar},
+%CUR               ^
+%SRC }
+%SRC 
+%SRC @article{bar,}
+"#,
+    )?;
+
+    assert_eq!(
+        ranges,
+        vec![
+            (1, 13, 1, 16),
+            (1, 12, 1, 21),
+            (1, 4, 1, 22),
+            (0, 0, 2, 1),
+            (0, 0, 4, 14),
+        ]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,194 @@
+use anyhow::Result;
+use lsp_types::{
+    request::{SemanticTokensFullRequest, SemanticTokensRangeRequest},
+    ClientCapabilities, Range, SemanticTokensLegend, SemanticTokensParams,
+    SemanticTokensRangeParams, SemanticTokensRangeResult, SemanticTokensResult,
+    SemanticTokensServerCapabilities, TextDocumentIdentifier,
+};
+
+use crate::lsp::{client::Client, fixture};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Token {
+    line: u32,
+    start: u32,
+    length: u32,
+    kind: String,
+    modifiers: u32,
+}
+
+fn decode(legend: &SemanticTokensLegend, data: Vec<lsp_types::SemanticToken>) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut line = 0;
+    let mut start = 0;
+    for token in data {
+        if token.delta_line == 0 {
+            start += token.delta_start;
+        } else {
+            line += token.delta_line;
+            start = token.delta_start;
+        }
+
+        tokens.push(Token {
+            line,
+            start,
+            length: token.length,
+            kind: legend.token_types[token.token_type as usize]
+                .as_str()
+                .to_string(),
+            modifiers: token.token_modifiers_bitset,
+        });
+    }
+
+    tokens
+}
+
+fn check(
+    fixture: &str,
+    range: Option<Range>,
+    expected: Vec<(u32, u32, u32, &str, u32)>,
+) -> Result<()> {
+    let mut client = Client::spawn()?;
+    let result = client.initialize(ClientCapabilities::default(), None)?;
+    let legend = match result.capabilities.semantic_tokens_provider.unwrap() {
+        SemanticTokensServerCapabilities::SemanticTokensOptions(options) => options.legend,
+        SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(options) => {
+            options.semantic_tokens_options.legend
+        }
+    };
+
+    let fixture = fixture::parse(fixture);
+    for file in fixture.files {
+        client.open(file.name, file.lang, file.text)?;
+    }
+
+    let text_document = TextDocumentIdentifier::new(client.uri(fixture.cursor.unwrap().name)?);
+    let data = match range {
+        Some(range) => {
+            match client.request::<SemanticTokensRangeRequest>(SemanticTokensRangeParams {
+                text_document,
+                range,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })? {
+                Some(SemanticTokensRangeResult::Tokens(tokens)) => tokens.data,
+                Some(SemanticTokensRangeResult::Partial(tokens)) => tokens.data,
+                None => Vec::new(),
+            }
+        }
+        None => match client.request::<SemanticTokensFullRequest>(SemanticTokensParams {
+            text_document,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })? {
+            Some(SemanticTokensResult::Tokens(tokens)) => tokens.data,
+            Some(SemanticTokensResult::Partial(tokens)) => tokens.data,
+            None => Vec::new(),
+        },
+    };
+
+    client.shutdown()?;
+
+    let expected: Vec<_> = expected
+        .into_iter()
+        .map(|(line, start, length, kind, modifiers)| Token {
+            line,
+            start,
+            length,
+            kind: kind.to_string(),
+            modifiers,
+        })
+        .collect();
+
+    assert_eq!(decode(&legend, data), expected);
+    Ok(())
+}
+
+#[test]
+fn latex_label() -> Result<()> {
+    check(
+        r#"
+%TEX main.tex
+%SRC \section{Foo}\label{sec:foo}
+%SRC See \ref{sec:foo}.
+%CUR ^
+"#,
+        None,
+        vec![
+            (0, 0, 8, "keyword", 0),
+            (0, 13, 6, "function", 0),
+            (0, 20, 7, "enumMember", 1),
+            (1, 4, 4, "function", 0),
+            (1, 9, 7, "enumMember", 0),
+        ],
+    )
+}
+
+#[test]
+fn latex_environment_and_math() -> Result<()> {
+    check(
+        r#"
+%TEX main.tex
+%SRC \begin{equation}
+%SRC     x = \foo{y}
+%SRC \end{equation}
+%SRC $z$ \cite{bar}
+%CUR ^
+"#,
+        None,
+        vec![
+            (0, 0, 6, "keyword", 0),
+            (0, 7, 8, "class", 0),
+            (1, 4, 1, "math", 0),
+            (1, 8, 4, "macro", 0),
+            (1, 13, 1, "math", 0),
+            (2, 0, 4, "keyword", 0),
+            (2, 5, 8, "class", 0),
+            (3, 0, 1, "math", 0),
+            (3, 1, 1, "math", 0),
+            (3, 2, 1, "math", 0),
+            (3, 4, 5, "function", 0),
+            (3, 10, 3, "parameter", 0),
+        ],
+    )
+}
+
+#[test]
+fn latex_include() -> Result<()> {
+    check(
+        r#"
+%TEX main.tex
+%SRC \input{foo}
+%SRC \newcommand{\foo}{bar}
+%CUR ^
+"#,
+        Some(Range::new(
+            lsp_types::Position::new(1, 0),
+            lsp_types::Position::new(1, 3),
+        )),
+        vec![(1, 0, 11, "keyword", 0)],
+    )
+}
+
+#[test]
+fn bibtex() -> Result<()> {
+    check(
+        r#"
+%BIB main.bib
+%SRC @string{foo = "bar"}
+%SRC @article{baz,
+%SRC     month = foo,
+%SRC }
+%CUR ^
+"#,
+        None,
+        vec![
+            (0, 0, 7, "type", 0),
+            (0, 8, 3, "variable", 1),
+            (1, 0, 8, "type", 0),
+            (1, 9, 3, "parameter", 1),
+            (2, 4, 5, "property", 0),
+            (2, 12, 3, "variable", 0),
+        ],
+    )
+}
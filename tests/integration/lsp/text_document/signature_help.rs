@@ -0,0 +1,154 @@
+use anyhow::Result;
+use lsp_types::{
+    request::SignatureHelpRequest, ClientCapabilities, ParameterLabel, SignatureHelpParams,
+};
+
+use crate::lsp::{client::Client, fixture};
+
+/// Returns the label of the signature and the label of the active parameter.
+fn find_signature(fixture: &str) -> Result<Option<(String, String)>> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+
+    let fixture = fixture::parse(fixture);
+    for file in fixture.files {
+        client.open(file.name, file.lang, file.text)?;
+    }
+
+    let result = client.request::<SignatureHelpRequest>(SignatureHelpParams {
+        context: None,
+        text_document_position_params: fixture.cursor.unwrap().into_params(&client)?,
+        work_done_progress_params: Default::default(),
+    })?;
+
+    client.shutdown()?;
+
+    Ok(result.map(|help| {
+        let signature = &help.signatures[help.active_signature.unwrap() as usize];
+        let parameters = signature.parameters.as_ref().unwrap();
+        let parameter = match parameters[help.active_parameter.unwrap() as usize].label {
+            ParameterLabel::LabelOffsets([start, end]) => {
+                signature.label[start as usize..end as usize].to_string()
+            }
+            ParameterLabel::Simple(ref label) => label.clone(),
+        };
+
+        (signature.label.clone(), parameter)
+    }))
+}
+
+#[test]
+fn user_command() -> Result<()> {
+    let result = find_signature(
+        r#"
+%TEX main.tex
+%SRC \newcommand{\foo}[2]{#1 #2}
+%SRC \foo{bar}{}
+%CUR           ^
+"#,
+    )?;
+
+    assert_eq!(
+        result,
+        Some(("\\foo{#1}{#2}".to_string(), "{#2}".to_string()))
+    );
+    Ok(())
+}
+
+#[test]
+fn user_command_optional() -> Result<()> {
+    let result = find_signature(
+        r#"
+%TEX main.tex
+%SRC \newcommand{\foo}[3][x]{#1 #2 #3}
+%SRC \foo[]{}{}
+%CUR      ^
+"#,
+    )?;
+
+    assert_eq!(
+        result,
+        Some(("\\foo[#1]{#2}{#3}".to_string(), "[#1]".to_string()))
+    );
+    Ok(())
+}
+
+#[test]
+fn user_command_optional_omitted() -> Result<()> {
+    let result = find_signature(
+        r#"
+%TEX main.tex
+%SRC \newcommand{\foo}[3][x]{#1 #2 #3}
+%SRC \foo{}{}
+%CUR        ^
+"#,
+    )?;
+
+    assert_eq!(
+        result,
+        Some(("\\foo[#1]{#2}{#3}".to_string(), "{#3}".to_string()))
+    );
+    Ok(())
+}
+
+#[test]
+fn user_command_other_document() -> Result<()> {
+    let result = find_signature(
+        r#"
+%TEX main.tex
+%SRC \input{defs}
+%SRC \foo{
+%CUR      ^
+
+%TEX defs.tex
+%SRC \newcommand{\foo}[1]{#1}
+"#,
+    )?;
+
+    assert_eq!(result, Some(("\\foo{#1}".to_string(), "{#1}".to_string())));
+    Ok(())
+}
+
+#[test]
+fn too_many_arguments() -> Result<()> {
+    let result = find_signature(
+        r#"
+%TEX main.tex
+%SRC \newcommand{\foo}[1]{#1}
+%SRC \foo{}{}
+%CUR        ^
+"#,
+    )?;
+
+    assert_eq!(result, None);
+    Ok(())
+}
+
+#[test]
+fn outside_of_argument() -> Result<()> {
+    let result = find_signature(
+        r#"
+%TEX main.tex
+%SRC \newcommand{\foo}[1]{#1}
+%SRC \foo{} bar
+%CUR         ^
+"#,
+    )?;
+
+    assert_eq!(result, None);
+    Ok(())
+}
+
+#[test]
+fn unknown_command() -> Result<()> {
+    let result = find_signature(
+        r#"
+%TEX main.tex
+%SRC \foo{}
+%CUR      ^
+"#,
+    )?;
+
+    assert_eq!(result, None);
+    Ok(())
+}
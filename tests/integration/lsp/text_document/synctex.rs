@@ -0,0 +1,183 @@
+use anyhow::Result;
+use lsp_types::{
+    notification::DidChangeConfiguration, ClientCapabilities, DidChangeConfigurationParams,
+    Location, Position, Range, TextDocumentIdentifier, TextDocumentPositionParams,
+};
+use serde_json::json;
+use texlab::features::{SyncTexForwardResult, SyncTexInverseParams};
+
+use crate::lsp::client::Client;
+
+struct SyncTexForwardRequest;
+
+impl lsp_types::request::Request for SyncTexForwardRequest {
+    type Params = TextDocumentPositionParams;
+
+    type Result = Option<SyncTexForwardResult>;
+
+    const METHOD: &'static str = "textDocument/synctexForward";
+}
+
+struct SyncTexInverseRequest;
+
+impl lsp_types::request::Request for SyncTexInverseRequest {
+    type Params = SyncTexInverseParams;
+
+    type Result = Option<Location>;
+
+    const METHOD: &'static str = "workspace/synctexInverse";
+}
+
+const MAIN: &str = r#"\documentclass{article}
+\begin{document}
+Foo
+\input{chapter}
+\end{document}
+"#;
+
+const SYNCTEX: &str = r#"SyncTeX Version:1
+Input:1:./main.tex
+Input:2:./chapter.tex
+Output:pdf
+Magnification:1000
+Unit:1
+X Offset:0
+Y Offset:0
+Content:
+{1
+[1,3:4736286,5500000:26851748,1000000,0
+(1,3:4736286,5000000:26851748,500000,100000
+g1,3:4736286,5000000
+)
+(2,2:4736286,6000000:26851748,500000,100000
+g2,2:4736286,6000000
+)
+]
+}1
+Postamble:
+"#;
+
+fn spawn() -> Result<Client> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+    client.store_on_disk("main.pdf", "")?;
+    client.store_on_disk("main.synctex", SYNCTEX)?;
+    client.open("main.tex", "latex", MAIN.to_string())?;
+    client.open("chapter.tex", "latex", "Bar\nBaz\n".to_string())?;
+    Ok(client)
+}
+
+#[test]
+fn forward() -> Result<()> {
+    let mut client = spawn()?;
+    let result = client
+        .request::<SyncTexForwardRequest>(TextDocumentPositionParams::new(
+            TextDocumentIdentifier::new(client.uri("chapter.tex")?),
+            Position::new(1, 0),
+        ))?
+        .unwrap();
+
+    assert_eq!(result.uri, client.uri("main.pdf")?);
+    assert_eq!(result.page, 1);
+    assert!((result.x - 72.0).abs() < 0.01);
+    assert!((result.y - 83.61).abs() < 0.01);
+    assert!((result.width - 408.19).abs() < 0.01);
+    assert!((result.height - 9.12).abs() < 0.01);
+
+    client.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn inverse() -> Result<()> {
+    let mut client = spawn()?;
+    let result = client.request::<SyncTexInverseRequest>(SyncTexInverseParams {
+        uri: client.uri("main.pdf")?,
+        page: 1,
+        x: 100.0,
+        y: 75.0,
+    })?;
+
+    let position = Position::new(2, 0);
+    assert_eq!(
+        result,
+        Some(Location::new(
+            client.uri("main.tex")?,
+            Range::new(position, position)
+        ))
+    );
+
+    client.shutdown()?;
+    Ok(())
+}
+
+fn spawn_with_aux_directory() -> Result<Client> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+    let aux_directory = client.uri("build")?.to_file_path().unwrap();
+    client.notify::<DidChangeConfiguration>(DidChangeConfigurationParams {
+        settings: json!({ "auxDirectory": aux_directory }),
+    })?;
+
+    client.store_on_disk("build/main.pdf", "")?;
+    client.store_on_disk("build/main.synctex", SYNCTEX)?;
+    client.open("main.tex", "latex", MAIN.to_string())?;
+    client.open("chapter.tex", "latex", "Bar\nBaz\n".to_string())?;
+    Ok(client)
+}
+
+#[test]
+fn forward_aux_directory() -> Result<()> {
+    let mut client = spawn_with_aux_directory()?;
+    let result = client
+        .request::<SyncTexForwardRequest>(TextDocumentPositionParams::new(
+            TextDocumentIdentifier::new(client.uri("chapter.tex")?),
+            Position::new(1, 0),
+        ))?
+        .unwrap();
+
+    assert_eq!(result.uri, client.uri("build/main.pdf")?);
+    assert_eq!(result.page, 1);
+    assert!((result.y - 83.61).abs() < 0.01);
+
+    client.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn inverse_aux_directory() -> Result<()> {
+    let mut client = spawn_with_aux_directory()?;
+    let result = client.request::<SyncTexInverseRequest>(SyncTexInverseParams {
+        uri: client.uri("build/main.pdf")?,
+        page: 1,
+        x: 100.0,
+        y: 75.0,
+    })?;
+
+    let position = Position::new(2, 0);
+    assert_eq!(
+        result,
+        Some(Location::new(
+            client.uri("main.tex")?,
+            Range::new(position, position)
+        ))
+    );
+
+    client.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn missing_synctex() -> Result<()> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+    client.open("main.tex", "latex", MAIN.to_string())?;
+    let result = client.request::<SyncTexForwardRequest>(TextDocumentPositionParams::new(
+        TextDocumentIdentifier::new(client.uri("main.tex")?),
+        Position::new(2, 0),
+    ))?;
+
+    assert!(result.is_none());
+    client.shutdown()?;
+    Ok(())
+}
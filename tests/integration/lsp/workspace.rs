@@ -1 +1,4 @@
+mod did_change_watched_files;
+mod indexing;
 mod symbol;
+mod workspace_folders;
@@ -0,0 +1,89 @@
+use anyhow::Result;
+use lsp_types::{
+    notification::DidChangeWatchedFiles, request::DocumentDiagnosticRequest, ClientCapabilities,
+    DiagnosticClientCapabilities, DidChangeWatchedFilesParams, DocumentDiagnosticParams,
+    DocumentDiagnosticReport, DocumentDiagnosticReportResult, FileChangeType, FileEvent,
+    NumberOrString, TextDocumentClientCapabilities, TextDocumentIdentifier,
+};
+
+use crate::lsp::client::Client;
+
+fn create_client() -> Result<Client> {
+    let mut client = Client::spawn()?;
+    client.initialize(
+        ClientCapabilities {
+            text_document: Some(TextDocumentClientCapabilities {
+                diagnostic: Some(DiagnosticClientCapabilities::default()),
+                ..TextDocumentClientCapabilities::default()
+            }),
+            ..ClientCapabilities::default()
+        },
+        None,
+    )?;
+
+    client.store_on_disk("chapter.tex", "\\label{foo}")?;
+    client.open(
+        "main.tex",
+        "latex",
+        "\\input{chapter}\n\\ref{foo}".to_string(),
+    )?;
+
+    Ok(client)
+}
+
+fn notify_change(client: &mut Client, name: &str, typ: FileChangeType) -> Result<()> {
+    let uri = client.uri(name)?;
+    client.notify::<DidChangeWatchedFiles>(DidChangeWatchedFilesParams {
+        changes: vec![FileEvent::new(uri, typ)],
+    })
+}
+
+fn find_codes(client: &mut Client) -> Result<Vec<NumberOrString>> {
+    let result = client.request::<DocumentDiagnosticRequest>(DocumentDiagnosticParams {
+        text_document: TextDocumentIdentifier::new(client.uri("main.tex")?),
+        identifier: None,
+        previous_result_id: None,
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    })?;
+
+    match result {
+        DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Full(report)) => {
+            Ok(report
+                .full_document_diagnostic_report
+                .items
+                .into_iter()
+                .filter_map(|diagnostic| diagnostic.code)
+                .collect())
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn changed() -> Result<()> {
+    let mut client = create_client()?;
+    assert_eq!(find_codes(&mut client)?, Vec::new());
+
+    client.store_on_disk("chapter.tex", "\\label{bar}")?;
+    notify_change(&mut client, "chapter.tex", FileChangeType::CHANGED)?;
+    let codes = find_codes(&mut client)?;
+
+    client.shutdown()?;
+    assert_eq!(codes, vec![NumberOrString::Number(9)]);
+    Ok(())
+}
+
+#[test]
+fn deleted() -> Result<()> {
+    let mut client = create_client()?;
+    assert_eq!(find_codes(&mut client)?, Vec::new());
+
+    client.remove_from_disk("chapter.tex")?;
+    notify_change(&mut client, "chapter.tex", FileChangeType::DELETED)?;
+    let codes = find_codes(&mut client)?;
+
+    client.shutdown()?;
+    assert_eq!(codes, vec![NumberOrString::Number(9)]);
+    Ok(())
+}
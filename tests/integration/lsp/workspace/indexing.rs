@@ -0,0 +1,112 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use lsp_server::Response;
+use lsp_types::{
+    notification::{DidChangeConfiguration, Notification, Progress},
+    request::WorkspaceSymbolRequest,
+    ClientCapabilities, DidChangeConfigurationParams, ProgressParams, ProgressParamsValue,
+    WindowClientCapabilities, WorkDoneProgress, WorkspaceSymbolParams, WorkspaceSymbolResponse,
+};
+
+use crate::lsp::client::Client;
+
+fn find_symbol_names(client: &mut Client) -> Result<Vec<String>> {
+    let names = client
+        .request::<WorkspaceSymbolRequest>(WorkspaceSymbolParams {
+            query: String::new(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })?
+        .map(|response| match response {
+            WorkspaceSymbolResponse::Flat(symbols) => symbols,
+            WorkspaceSymbolResponse::Nested(_) => unreachable!(),
+        })
+        .unwrap_or_default()
+        .into_iter()
+        .map(|symbol| symbol.name)
+        .collect();
+
+    Ok(names)
+}
+
+/// Waits until the indexer reports that all documents have been added to the workspace.
+/// The server waits for the client to accept the progress token before it starts indexing.
+fn wait_for_indexing(client: &mut Client) -> Result<()> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        while let Ok(request) = client.incoming().requests.try_recv() {
+            client.respond(Response::new_ok(request.id, ()))?;
+        }
+
+        let notification = match client
+            .incoming()
+            .notifications
+            .recv_timeout(Duration::from_millis(50))
+        {
+            Ok(notification) if notification.method == Progress::METHOD => notification,
+            _ => continue,
+        };
+
+        let params: ProgressParams = serde_json::from_value(notification.params)?;
+        if matches!(
+            params.value,
+            ProgressParamsValue::WorkDone(WorkDoneProgress::End(_))
+        ) {
+            return Ok(());
+        }
+    }
+
+    bail!("indexing did not finish in time")
+}
+
+fn find_indexed_symbols(client: &mut Client) -> Result<Vec<String>> {
+    wait_for_indexing(client)?;
+    let mut names = find_symbol_names(client)?;
+    names.sort();
+    Ok(names)
+}
+
+fn create_client(settings: serde_json::Value) -> Result<Client> {
+    let mut client = Client::spawn()?;
+    let capabilities = ClientCapabilities {
+        window: Some(WindowClientCapabilities {
+            work_done_progress: Some(true),
+            ..WindowClientCapabilities::default()
+        }),
+        ..ClientCapabilities::default()
+    };
+
+    client.initialize(capabilities, None)?;
+    client.store_on_disk("chapters/one.tex", "\\section{Foo}")?;
+    client.store_on_disk("drafts/two.tex", "\\section{Bar}")?;
+    client.store_on_disk("references.bib", "@article{baz, title = {Baz}}")?;
+    client.notify::<DidChangeConfiguration>(DidChangeConfigurationParams { settings })?;
+    Ok(client)
+}
+
+#[test]
+fn enabled() -> Result<()> {
+    let mut client = create_client(serde_json::json!({
+        "indexing": { "enabled": true }
+    }))?;
+
+    let names = find_indexed_symbols(&mut client)?;
+    client.shutdown()?;
+
+    assert_eq!(names, vec!["Bar", "Foo", "baz"]);
+    Ok(())
+}
+
+#[test]
+fn ignored_patterns() -> Result<()> {
+    let mut client = create_client(serde_json::json!({
+        "indexing": { "enabled": true, "ignoredPatterns": ["drafts/**", "*.bib"] }
+    }))?;
+
+    let names = find_indexed_symbols(&mut client)?;
+    client.shutdown()?;
+
+    assert_eq!(names, vec!["Foo"]);
+    Ok(())
+}
@@ -1,7 +1,8 @@
 use anyhow::Result;
 use insta::{assert_json_snapshot, internals::Redaction};
 use lsp_types::{
-    request::WorkspaceSymbol, ClientCapabilities, SymbolInformation, Url, WorkspaceSymbolParams,
+    request::WorkspaceSymbolRequest, ClientCapabilities, SymbolInformation, Url,
+    WorkspaceSymbolParams, WorkspaceSymbolResponse,
 };
 
 use crate::lsp::{client::Client, fixture};
@@ -21,11 +22,15 @@ fn find_symbols(fixture: &str, query: &str) -> Result<SymbolResult> {
     }
 
     let actual_symbols = client
-        .request::<WorkspaceSymbol>(WorkspaceSymbolParams {
+        .request::<WorkspaceSymbolRequest>(WorkspaceSymbolParams {
             query: query.to_string(),
             work_done_progress_params: Default::default(),
             partial_result_params: Default::default(),
         })?
+        .map(|response| match response {
+            WorkspaceSymbolResponse::Flat(symbols) => symbols,
+            WorkspaceSymbolResponse::Nested(_) => unreachable!(),
+        })
         .unwrap_or_default();
 
     let result = client.shutdown()?;
@@ -0,0 +1,89 @@
+use anyhow::Result;
+use lsp_types::{
+    notification::{DidChangeConfiguration, DidChangeWorkspaceFolders},
+    request::DocumentLinkRequest,
+    ClientCapabilities, DidChangeConfigurationParams, DidChangeWorkspaceFoldersParams,
+    DocumentLinkParams, TextDocumentIdentifier, Url, WorkspaceFolder, WorkspaceFoldersChangeEvent,
+};
+
+use crate::lsp::client::Client;
+
+fn setup(client: &mut Client) -> Result<()> {
+    client.store_on_disk("chapter.tex", "")?;
+    client.store_on_disk("paper/chapter.tex", "")?;
+    client.notify::<DidChangeConfiguration>(DidChangeConfigurationParams {
+        settings: serde_json::json!({ "rootDirectory": "." }),
+    })?;
+
+    client.open(
+        "paper/sub/main.tex",
+        "latex",
+        "\\input{chapter}".to_string(),
+    )
+}
+
+fn find_link_targets(client: &mut Client) -> Result<Vec<Url>> {
+    let links = client.request::<DocumentLinkRequest>(DocumentLinkParams {
+        text_document: TextDocumentIdentifier::new(client.uri("paper/sub/main.tex")?),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    })?;
+
+    Ok(links
+        .into_iter()
+        .flatten()
+        .filter_map(|link| link.target)
+        .collect())
+}
+
+fn paper_folder(client: &Client) -> Result<WorkspaceFolder> {
+    Ok(WorkspaceFolder {
+        uri: client.uri("paper")?,
+        name: "paper".to_string(),
+    })
+}
+
+#[test]
+fn without_folders() -> Result<()> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+    setup(&mut client)?;
+
+    let targets = find_link_targets(&mut client)?;
+    assert_eq!(targets, vec![client.uri("chapter.tex")?]);
+    client.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn root_directory_of_folder() -> Result<()> {
+    let mut client = Client::spawn()?;
+    let folder = paper_folder(&client)?;
+    client.initialize_with_folders(ClientCapabilities::default(), None, Some(vec![folder]))?;
+    setup(&mut client)?;
+
+    let targets = find_link_targets(&mut client)?;
+    assert_eq!(targets, vec![client.uri("paper/chapter.tex")?]);
+    client.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn added_folder() -> Result<()> {
+    let mut client = Client::spawn()?;
+    client.initialize(ClientCapabilities::default(), None)?;
+    setup(&mut client)?;
+
+    let folder = paper_folder(&client)?;
+    client.notify::<DidChangeWorkspaceFolders>(DidChangeWorkspaceFoldersParams {
+        event: WorkspaceFoldersChangeEvent {
+            added: vec![folder],
+            removed: Vec::new(),
+        },
+    })?;
+
+    let targets = find_link_targets(&mut client)?;
+    assert_eq!(targets, vec![client.uri("paper/chapter.tex")?]);
+    client.shutdown()?;
+    Ok(())
+}